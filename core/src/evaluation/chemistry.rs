@@ -0,0 +1,1204 @@
+use crate::error::SiffraExecutionError;
+use crate::evaluation::{evaluate_expr, is_built_in_name, SiffraState};
+use crate::grammar::representation::ParsedExpr;
+use crate::grammar::Span;
+use crate::representations::{
+    Activity, Amount, BalanceError, Compound, Dimension, Element, Expression, Float, GasConditions,
+    InnerExpression, Length, Mass, Object, Proportion, Quantity, Reaction, Time, Value, Volume,
+};
+use crate::{siffra_error, siffra_try};
+
+/// Evaluates functions that operate on chemicals rather than numbers. Returns `None` if `name` is
+/// not a chemical function.
+pub fn evaluate_chemical_function(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Option<Result<Object, SiffraExecutionError>> {
+    match name {
+        "balance" => Some(balance(args, objects, expr)),
+        "limiting_reagent" | "theoretical_yield" => {
+            Some(reaction_yield(name, args, objects, expr, state))
+        }
+        "percent_yield" => Some(percent_yield(args, objects, expr)),
+        "dilute" | "dilution" => Some(dilute(name, args, objects, expr)),
+        "decay" => Some(decay(args, objects, expr)),
+        "half_life" => Some(half_life(args, objects, expr, state)),
+        "decay_time" => Some(decay_time(args, objects, expr)),
+        "activity" => Some(activity(args, objects, expr)),
+        "molar_mass" => Some(molar_mass(args, objects, expr, state)),
+        "percent_composition" => Some(percent_composition(args, objects, expr, state)),
+        "atomic_number" | "atomic_mass" | "atomic_radius" | "electronegativity"
+        | "element_name" => Some(element_property(name, args, objects, expr, state)),
+        "empirical_formula" => Some(empirical_formula(args, objects, expr, state)),
+        "molecular_formula" => Some(molecular_formula(args, objects, expr, state)),
+        _ => None,
+    }
+}
+
+fn molar_mass(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    if objects.len() != 1 {
+        return Err(siffra_error!(
+            "Argument Error",
+            "molar_mass function takes exactly 1 argument",
+            expr.span()
+        ));
+    }
+
+    let compound = compound_arg(&args[0], &objects[0], state)?;
+
+    Ok(Value::new(compound.particulate_mass(), Some(grams_per_mole())).into())
+}
+
+fn percent_composition(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    if objects.len() != 2 {
+        return Err(siffra_error!(
+            "Argument Error",
+            "percent_composition function takes exactly 2 arguments: a compound and an element",
+            expr.span()
+        ));
+    }
+
+    let compound = compound_arg(&args[0], &objects[0], state)?;
+    let element = element_arg(&args[1], &objects[1], state)?;
+
+    if compound.count(&element) == 0 {
+        return Err(siffra_error!(
+            "Chemical Error",
+            format!("'{}' does not contain {}", compound, element.name()),
+            args[1].span()
+        ));
+    }
+
+    let element_mass = &element.atomic_mass() * &Float::from(compound.count(&element));
+
+    Ok(in_percent(&element_mass / &compound.particulate_mass()).into())
+}
+
+fn element_property(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    if objects.len() != 1 {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 1 argument", name),
+            expr.span()
+        ));
+    }
+
+    let element = element_arg(&args[0], &objects[0], state)?;
+
+    let unknown = || {
+        siffra_error!(
+            "Chemical Error",
+            format!(
+                "The {} of {} is not known",
+                name.replace('_', " "),
+                element.name()
+            ),
+            args[0].span()
+        )
+    };
+
+    match name {
+        "atomic_number" => Ok(Value::new(Float::from(element.atomic_number() as u64), None).into()),
+        "atomic_mass" => Ok(Value::new(element.atomic_mass(), Some(grams_per_mole())).into()),
+        "atomic_radius" => {
+            if element.atomic_radius() == 0 {
+                return Err(unknown());
+            }
+            Ok(Value::new(
+                Float::from(element.atomic_radius()),
+                Some(Dimension::new(vec![(
+                    Quantity::Length(Length::Picometer),
+                    Float::from(1),
+                )])),
+            )
+            .into())
+        }
+        "electronegativity" => {
+            if element.electronegativity() == 0.0 {
+                return Err(unknown());
+            }
+            Ok(Value::new(
+                Float::parse(&element.electronegativity().to_string()).unwrap(),
+                None,
+            )
+            .into())
+        }
+        _ => Ok(Object::Text(element.name().to_string())),
+    }
+}
+
+fn balance(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    if objects.len() != 1 {
+        return Err(siffra_error!(
+            "Argument Error",
+            "balance function takes exactly 1 argument",
+            expr.span()
+        ));
+    }
+
+    let Object::Reaction(reaction) = &objects[0] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "balance function takes a reaction, such as 'Fe + O2 -> Fe2O3'",
+            args[0].span()
+        ));
+    };
+
+    reaction
+        .balance()
+        .map(Object::Reaction)
+        .map_err(|err| balance_error(reaction, err, &args[0]))
+}
+
+/// Evaluates `limiting_reagent` and `theoretical_yield`, which both take a reaction followed by
+/// the amounts of its reactants. The theoretical yield is given in moles of the product that
+/// ends the arguments, as in `theoretical_yield(2H2 + O2 -> 2H2O, 4 g H2, 16 g O2, H2O)`, or of
+/// the first product if none is given.
+fn reaction_yield(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    if objects.len() < 2 {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!(
+                "{} function takes a reaction followed by the reactant amounts",
+                name
+            ),
+            expr.span()
+        ));
+    }
+
+    let Object::Reaction(reaction) = &objects[0] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes a reaction as its first argument", name),
+            args[0].span()
+        ));
+    };
+
+    // Reactant amounts are values, so anything after them names the product
+    let (objects, args, product) = match (objects.split_last(), args.split_last()) {
+        (Some((object, objects)), Some((arg, args)))
+            if name == "theoretical_yield" && object.as_value().is_none() =>
+        {
+            (
+                objects,
+                args,
+                Some((compound_arg(arg, object, state)?, arg)),
+            )
+        }
+        _ => (objects, args, None),
+    };
+
+    let (reaction, limiting, extent) =
+        limiting_reagent(reaction, &args[0], &objects[1..], &args[1..])?;
+
+    if name == "limiting_reagent" {
+        return Ok(Object::Compound(limiting));
+    }
+
+    let (coefficient, product) = match product {
+        Some((product, arg)) => reaction
+            .products()
+            .iter()
+            .find(|(_, species)| species.same_substance(&product))
+            .cloned()
+            .ok_or_else(|| {
+                siffra_error!(
+                    "Chemical Error",
+                    format!("'{}' is not a product in the reaction", product),
+                    arg.span()
+                )
+            })?,
+        None => reaction.products()[0].clone(),
+    };
+    Ok(Value::new(
+        &extent * &Float::from(coefficient),
+        Some(Dimension::moles_of(product)),
+    )
+    .into())
+}
+
+fn percent_yield(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [Object::Value(actual), Object::Value(theoretical)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "percent_yield function takes exactly 2 quantities: the actual and theoretical yields",
+            expr.span()
+        ));
+    };
+
+    let actual = siffra_try!(
+        actual.convert(&theoretical.dimension).ok_or(()),
+        "Unit Error",
+        "Actual and theoretical yields must be convertible to each other",
+        args[0].span()
+    );
+
+    if theoretical.value == Float::from(0) {
+        return Err(siffra_error!(
+            "Division Error",
+            "The theoretical yield is zero",
+            args[1].span()
+        ));
+    }
+    Ok(in_percent(&actual.value / &theoretical.value).into())
+}
+
+/// A ratio written in percent.
+fn in_percent(ratio: Float) -> Value {
+    Value::new(
+        &ratio * &Float::from(100),
+        Some(Dimension(vec![(
+            Quantity::Proportion(Proportion::Percent),
+            Float::from(1),
+        )])),
+    )
+}
+
+/// Solves C1V1 = C2V2 for a dilution. Given a final volume, such as `dilute(2 M NaCl, 50 mL,
+/// 250 mL)`, returns the final concentration. Given a final concentration, such as
+/// `dilute(2 M NaCl, 50 mL, 0.5 M)`, returns the final volume.
+fn dilute(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [Object::Value(concentration), Object::Value(volume), Object::Value(target)] = objects
+    else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!(
+                "{} function takes exactly 3 quantities: the initial concentration and volume, \
+                and the final volume or concentration",
+                name
+            ),
+            expr.span()
+        ));
+    };
+    let liters = Dimension(vec![(
+        Quantity::Volume(Volume::Liter, None),
+        Float::from(1),
+    )]);
+    if volume.convert(&liters).is_none() {
+        return Err(siffra_error!(
+            "Unit Error",
+            format!("{} function takes a volume as the initial volume", name),
+            args[1].span()
+        ));
+    }
+
+    let solute = siffra_try!(
+        concentration.try_mul(volume).ok_or(()),
+        "Unit Error",
+        "Initial concentration and volume can't be multiplied",
+        args[1].span()
+    );
+    let result = siffra_try!(
+        solute.try_div(target).ok_or(()),
+        "Unit Error",
+        "Final volume or concentration can't be divided by",
+        args[2].span()
+    );
+
+    // A final volume gives a concentration, and a final concentration gives a volume
+    let dimension = if target.convert(&volume.dimension).is_some() {
+        &concentration.dimension
+    } else {
+        &volume.dimension
+    };
+
+    Ok(siffra_try!(
+        result.convert(dimension).ok_or(()),
+        "Unit Error",
+        "Final quantity must be a volume or a concentration like the initial ones",
+        args[2].span()
+    )
+    .into())
+}
+
+/// Returns what is left of a radioactive sample after some time, in the sample's own units, such
+/// as `decay(10 g C-14, 5730 y, 1000 y)`. The half-life can be left out for known isotopes.
+fn decay(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let (amount, half_life, elapsed) = match objects {
+        [Object::Value(amount), Object::Value(half_life), Object::Value(elapsed)] => (
+            amount,
+            (half_life.clone(), args[1].span()),
+            (elapsed, args[2].span()),
+        ),
+        [Object::Value(amount), Object::Value(elapsed)] => (
+            amount,
+            (known_half_life(amount, args[0].span())?, args[0].span()),
+            (elapsed, args[1].span()),
+        ),
+        _ => {
+            return Err(siffra_error!(
+                "Argument Error",
+                "decay function takes an amount, an optional half-life and the time elapsed",
+                expr.span()
+            ))
+        }
+    };
+
+    let half_lives = half_lives(elapsed, half_life)?;
+
+    Ok(Value::new(
+        &amount.value * &Float::from(0.5).pow(&half_lives),
+        Some(amount.dimension.clone()),
+    )
+    .into())
+}
+
+/// Returns the half-life of a sample that decayed from one amount to another over some time, such
+/// as `half_life(10 g, 2.5 g, 8 d)`, or the known half-life of an isotope, such as
+/// `half_life(C-14)`.
+fn half_life(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    match objects {
+        [Object::Value(initial), Object::Value(remaining), Object::Value(elapsed)] => {
+            let decayed = decayed_fraction(initial, remaining, &args[1])?;
+            Ok(Value::new(
+                &(&elapsed.value * &Float::from(2).ln()) / &decayed.ln(),
+                Some(elapsed.dimension.clone()),
+            )
+            .into())
+        }
+        [Object::Value(value)] => Ok(known_half_life(value, args[0].span())?.into()),
+        [object] => {
+            let compound = compound_arg(&args[0], object, state)?;
+            Ok(compound_half_life(&compound, args[0].span())?.into())
+        }
+        _ => Err(siffra_error!(
+            "Argument Error",
+            "half_life function takes an isotope, or the initial and remaining amounts and the \
+            time elapsed",
+            expr.span()
+        )),
+    }
+}
+
+/// Returns how long a sample takes to decay from one amount to another, such as
+/// `decay_time(10 g, 2.5 g, 8 d)`. The half-life can be left out for known isotopes.
+fn decay_time(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let (initial, remaining, half_life) = match objects {
+        [Object::Value(initial), Object::Value(remaining), Object::Value(half_life)] => {
+            (initial, remaining, half_life.clone())
+        }
+        [Object::Value(initial), Object::Value(remaining)] => (
+            initial,
+            remaining,
+            known_half_life(initial, args[0].span())?,
+        ),
+        _ => {
+            return Err(siffra_error!(
+                "Argument Error",
+                "decay_time function takes the initial and remaining amounts and an optional \
+                half-life",
+                expr.span()
+            ))
+        }
+    };
+
+    let decayed = decayed_fraction(initial, remaining, &args[1])?;
+
+    Ok(Value::new(
+        &(&half_life.value * &decayed.ln()) / &Float::from(2).ln(),
+        Some(half_life.dimension),
+    )
+    .into())
+}
+
+/// Returns the activity of a radioactive sample in becquerels, such as `activity(1 g Ra-226)`.
+/// The half-life can be left out for known isotopes.
+fn activity(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let (amount, half_life) = match objects {
+        [Object::Value(amount), Object::Value(half_life)] => (amount, half_life.clone()),
+        [Object::Value(amount)] => (amount, known_half_life(amount, args[0].span())?),
+        _ => {
+            return Err(siffra_error!(
+                "Argument Error",
+                "activity function takes an amount and an optional half-life",
+                expr.span()
+            ))
+        }
+    };
+
+    let particles = siffra_try!(
+        amount
+            .convert(&Dimension::new(vec![(
+                Quantity::Amount(Amount::Particle, None),
+                Float::from(1),
+            )]))
+            .ok_or(()),
+        "Unit Error",
+        "Amount must be a number of particles, an amount, or a mass of a known compound",
+        args[0].span()
+    );
+    let seconds = siffra_try!(
+        half_life.convert(&seconds()).ok_or(()),
+        "Unit Error",
+        "Half-life must be a time",
+        args.get(1).and_then(Expression::span)
+    );
+
+    // Each particle decays at a rate of ln(2) / half-life
+    Ok(Value::new(
+        &(&particles.value * &Float::from(2).ln()) / &seconds.value,
+        Some(Dimension::new(vec![(
+            Quantity::Activity(Activity::Becquerel),
+            Float::from(1),
+        )])),
+    )
+    .into())
+}
+
+/// Returns the number of half-lives in the time elapsed, given each with its span.
+fn half_lives(
+    (elapsed, elapsed_span): (&Value, Option<Span>),
+    (half_life, half_life_span): (Value, Option<Span>),
+) -> Result<Float, SiffraExecutionError> {
+    if half_life.convert(&seconds()).is_none() {
+        return Err(siffra_error!(
+            "Unit Error",
+            "Half-life must be a time",
+            half_life_span
+        ));
+    }
+
+    let elapsed = siffra_try!(
+        elapsed.convert(&half_life.dimension).ok_or(()),
+        "Unit Error",
+        "Time elapsed must be a time",
+        elapsed_span
+    );
+
+    Ok(&elapsed.value / &half_life.value)
+}
+
+/// Returns how many times larger the initial amount is than the remaining amount.
+fn decayed_fraction(
+    initial: &Value,
+    remaining: &Value,
+    remaining_arg: &Expression,
+) -> Result<Float, SiffraExecutionError> {
+    let remaining = siffra_try!(
+        remaining.convert(&initial.dimension).ok_or(()),
+        "Unit Error",
+        "Remaining amount must be in the same units as the initial amount",
+        remaining_arg.span()
+    );
+
+    if remaining.value <= Float::from(0) || remaining.value >= initial.value {
+        return Err(siffra_error!(
+            "Argument Error",
+            "Remaining amount must be more than zero and less than the initial amount",
+            remaining_arg.span()
+        ));
+    }
+
+    Ok(&initial.value / &remaining.value)
+}
+
+/// Returns the half-life of the isotope a quantity is tagged with, such as `10 g C-14`.
+fn known_half_life(value: &Value, span: Option<Span>) -> Result<Value, SiffraExecutionError> {
+    match value.dimension.compound() {
+        Some(compound) => compound_half_life(compound, span),
+        None => Err(siffra_error!(
+            "Argument Error",
+            "Half-life must be given unless the amount is of a known isotope, like `10 g C-14`",
+            span
+        )),
+    }
+}
+
+fn compound_half_life(
+    compound: &Compound,
+    span: Option<Span>,
+) -> Result<Value, SiffraExecutionError> {
+    let (half_life, unit) = siffra_try!(
+        compound
+            .as_element()
+            .and_then(|element| element.half_life())
+            .ok_or(()),
+        "Argument Error",
+        format!("Half-life of '{}' is not known", compound),
+        span
+    );
+
+    Ok(Value::new(
+        half_life,
+        Some(Dimension::new(vec![(Quantity::Time(unit), Float::from(1))])),
+    ))
+}
+
+/// Returns the dimension s.
+fn seconds() -> Dimension {
+    Dimension::new(vec![(Quantity::Time(Time::Second), Float::from(1))])
+}
+
+/// Converts a quantity of one compound into a quantity of another using the mole ratio in a
+/// reaction, such as `10 g Fe to g Fe2O3 via 4Fe + 3O2 -> 2Fe2O3`.
+pub fn convert_via(
+    value: &Value,
+    value_span: Option<Span>,
+    dimension: &Dimension,
+    conditions: &GasConditions,
+    reaction: &Expression,
+    state: &SiffraState,
+) -> Result<Value, SiffraExecutionError> {
+    let Object::Reaction(unbalanced) = evaluate_expr(reaction, state)? else {
+        return Err(siffra_error!(
+            "Type Error",
+            "Expected a reaction after 'via'",
+            reaction.span()
+        ));
+    };
+    let balanced = balanced(&unbalanced, reaction)?;
+
+    let Some(source) = value.dimension.compound() else {
+        return Err(siffra_error!(
+            "Chemical Error",
+            "Value must be a mass, amount or gas volume of a compound, such as '10 g Fe'",
+            value_span
+        ));
+    };
+    let Some(target) = dimension.compound() else {
+        return Err(siffra_error!(
+            "Chemical Error",
+            "Target unit must be a mass, amount or gas volume of a compound, such as 'g Fe2O3'",
+            reaction.span()
+        ));
+    };
+
+    let source_coefficient = coefficient(&balanced, source, reaction)?;
+    let target_coefficient = coefficient(&balanced, target, reaction)?;
+
+    let moles = siffra_try!(
+        value
+            .convert_at(&Dimension::moles_of(source.clone()), conditions)
+            .ok_or(()),
+        "Unit Error",
+        format!("Unable to convert value to moles of {}", source),
+        value_span
+    );
+
+    let target_moles = Value::new(
+        &(&moles.value * &Float::from(target_coefficient)) / &Float::from(source_coefficient),
+        Some(Dimension::moles_of(target.clone())),
+    );
+
+    target_moles
+        .convert_at(dimension, conditions)
+        .ok_or_else(|| conversion_error(&target_moles.dimension, dimension, reaction.span()))
+}
+
+/// The error for a failed conversion, which explains that only gases have a molar volume.
+pub(super) fn conversion_error(
+    from: &Dimension,
+    to: &Dimension,
+    span: Option<Span>,
+) -> SiffraExecutionError {
+    if from.needs_gas(to) {
+        siffra_error!(
+            "Unit Error",
+            "Only gases convert between an amount or mass and a volume. Mark the compound as a gas, as in CO2(g), or give conditions, as in 'at STP'",
+            span
+        )
+    } else {
+        siffra_error!("Unit Error", "Error converting units", span)
+    }
+}
+
+/// Finds the reactant that runs out first. Returns the balanced reaction, the limiting reactant,
+/// and the extent of reaction in moles.
+fn limiting_reagent(
+    reaction: &Reaction,
+    reaction_expr: &Expression,
+    objects: &[Object],
+    args: &[Expression],
+) -> Result<(Reaction, Compound, Float), SiffraExecutionError> {
+    let balanced = balanced(reaction, reaction_expr)?;
+
+    let mut limiting: Option<(Compound, Float)> = None;
+
+    for (object, arg) in objects.iter().zip(args) {
+        let Some((value, compound)) = object
+            .as_value()
+            .and_then(|value| Some((value, value.dimension.compound()?)))
+        else {
+            return Err(siffra_error!(
+                "Chemical Error",
+                "Reactant amounts must be masses, amounts or gas volumes of a compound, such as '10 g Fe'",
+                arg.span()
+            ));
+        };
+
+        if !balanced
+            .reactants()
+            .iter()
+            .any(|(_, reactant)| reactant.same_substance(compound))
+        {
+            return Err(siffra_error!(
+                "Chemical Error",
+                format!("'{}' is not a reactant in the reaction", compound),
+                arg.span()
+            ));
+        }
+
+        let moles_of = Dimension::moles_of(compound.clone());
+        let moles = value
+            .convert(&moles_of)
+            .ok_or_else(|| conversion_error(&value.dimension, &moles_of, arg.span()))?;
+        let extent = &moles.value / &Float::from(coefficient(&balanced, compound, reaction_expr)?);
+
+        if limiting
+            .as_ref()
+            .is_none_or(|(_, smallest)| extent < *smallest)
+        {
+            limiting = Some((compound.clone(), extent));
+        }
+    }
+
+    let Some((compound, extent)) = limiting else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "Expected the amounts of the reactants after the reaction",
+            reaction_expr.span()
+        ));
+    };
+    Ok((balanced, compound, extent))
+}
+
+/// Finds the empirical formula from element-quantity pairs, such as
+/// `empirical_formula(C, 40%, H, 6.7%, O, 53.3%)`. Quantities may be percentages, masses or
+/// amounts, as long as every pair uses the same kind.
+fn empirical_formula(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    if objects.is_empty() || !objects.len().is_multiple_of(2) {
+        return Err(siffra_error!(
+            "Argument Error",
+            "empirical_formula function takes pairs of elements and their percentages, masses or amounts",
+            expr.span()
+        ));
+    }
+
+    empirical(args, objects, expr, state).map(Object::Compound)
+}
+
+/// Finds the molecular formula from an empirical formula, or element-quantity pairs, followed by
+/// the molar mass, such as `molecular_formula(CH2O, 180 g/mol)`.
+fn molecular_formula(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
+    let (Some((molar_mass, objects)), Some((molar_mass_arg, args))) =
+        (objects.split_last(), args.split_last())
+    else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "molecular_formula function takes an empirical formula and a molar mass",
+            expr.span()
+        ));
+    };
+
+    let empirical = match objects.len() {
+        1 => compound_arg(&args[0], &objects[0], state)?,
+        n if n.is_multiple_of(2) => empirical(args, objects, expr, state)?,
+        _ => {
+            return Err(siffra_error!(
+                "Argument Error",
+                "molecular_formula function takes an empirical formula and a molar mass",
+                expr.span()
+            ))
+        }
+    };
+
+    let molar_mass = match molar_mass {
+        Object::Value(value) if value.is_unitless() => value.value(),
+        Object::Value(value) => {
+            siffra_try!(
+                value.convert(&grams_per_mole()).ok_or(()),
+                "Unit Error",
+                "Molar mass must be convertible to g/mol",
+                molar_mass_arg.span()
+            )
+            .value
+        }
+        object => {
+            return Err(siffra_error!(
+                "Argument Error",
+                format!("Expected a molar mass, found a {}", object.kind()),
+                molar_mass_arg.span()
+            ))
+        }
+    };
+
+    empirical
+        .molecular(&molar_mass)
+        .map(Object::Compound)
+        .ok_or_else(|| {
+            siffra_error!(
+                "Chemical Error",
+                format!(
+                    "Molar mass is not a whole multiple of the molar mass of '{}'",
+                    empirical
+                ),
+                molar_mass_arg.span()
+            )
+        })
+}
+
+/// Converts element-quantity pairs into moles of each element and finds their empirical formula.
+fn empirical(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Compound, SiffraExecutionError> {
+    let grams = Dimension::new(vec![(Quantity::Mass(Mass::Gram, None), Float::from(1))]);
+    let moles = Dimension::new(vec![(Quantity::Amount(Amount::Mole, None), Float::from(1))]);
+
+    let mut amounts = Vec::new();
+    let mut kind = None;
+
+    for (pair, arg) in objects.chunks(2).zip(args.chunks(2)) {
+        let element = element_arg(&arg[0], &pair[0], state)?;
+        let Object::Value(value) = &pair[1] else {
+            return Err(siffra_error!(
+                "Argument Error",
+                format!("Expected a quantity, found a {}", pair[1].kind()),
+                arg[1].span()
+            ));
+        };
+
+        let atomic_mass = element.atomic_mass();
+        let (amount, value_kind) = if let Some(fraction) = value.convert(&Dimension::default()) {
+            (&fraction.value / &atomic_mass, "a percentage")
+        } else if let Some(mass) = value.convert(&grams) {
+            (&mass.value / &atomic_mass, "a mass")
+        } else if let Some(amount) = value.convert(&moles) {
+            (amount.value, "an amount")
+        } else {
+            return Err(siffra_error!(
+                "Unit Error",
+                "Expected a percentage, mass or amount",
+                arg[1].span()
+            ));
+        };
+
+        if *kind.get_or_insert(value_kind) != value_kind {
+            return Err(siffra_error!(
+                "Argument Error",
+                format!(
+                    "Expected {} like the other quantities, found {}",
+                    kind.unwrap(),
+                    value_kind
+                ),
+                arg[1].span()
+            ));
+        }
+
+        if amount <= Float::from(0) {
+            return Err(siffra_error!(
+                "Argument Error",
+                "Quantities must be positive",
+                arg[1].span()
+            ));
+        }
+
+        amounts.push((element, amount));
+    }
+
+    Compound::empirical(&amounts).ok_or_else(|| {
+        siffra_error!(
+            "Chemical Error",
+            "No whole-number ratio of elements matches these quantities",
+            expr.span()
+        )
+    })
+}
+
+/// Looks up the compounds that units such as `g water` refer to by name. Variables and units
+/// come first, so `10 kg x` is `10 kg` times `x`.
+pub fn resolve_compound_names(
+    expr: &mut ParsedExpr,
+    state: &SiffraState,
+) -> Result<(), SiffraExecutionError> {
+    expr.separate_unit_names(&|name| {
+        state.get_variable(name).is_ok() || is_built_in_name(name, state)
+    });
+    let mut result = Ok(());
+
+    expr.for_each_unit(&mut |unit| {
+        let Some(chemical) = &unit.chemical else {
+            return;
+        };
+        // Formulas start with an element symbol, names with a lowercase letter
+        if !chemical.starts_with(|c: char| c.is_ascii_lowercase()) {
+            return;
+        }
+
+        match state.compound(chemical) {
+            Some(compound) => unit.compound = Some(compound),
+            None if result.is_ok() => {
+                result = Err(siffra_error!(
+                    "Chemical Error",
+                    format!("Unknown compound '{}'", chemical),
+                    Some(unit.span)
+                ))
+            }
+            None => {}
+        }
+    });
+
+    result
+}
+
+/// Interprets a function argument as a compound. Bare names that form a valid formula are read as
+/// compounds unless a variable with that name has been defined, so `K` means potassium here.
+fn compound_arg(
+    arg: &Expression,
+    object: &Object,
+    state: &SiffraState,
+) -> Result<Compound, SiffraExecutionError> {
+    if let InnerExpression::Variable(name) = arg.inner() {
+        if state.get_variable(name).is_err() {
+            if let Ok(compound) = Compound::parse(name) {
+                return Ok(compound);
+            }
+        }
+    }
+
+    match object {
+        Object::Compound(compound) => Ok(compound.clone()),
+        object => Err(siffra_error!(
+            "Argument Error",
+            format!("Expected a compound, found a {}", object.kind()),
+            arg.span()
+        )),
+    }
+}
+
+fn element_arg(
+    arg: &Expression,
+    object: &Object,
+    state: &SiffraState,
+) -> Result<Element, SiffraExecutionError> {
+    let compound = compound_arg(arg, object, state)?;
+
+    compound.as_element().ok_or_else(|| {
+        siffra_error!(
+            "Argument Error",
+            format!("Expected a single element, found '{}'", compound),
+            arg.span()
+        )
+    })
+}
+
+/// Returns the dimension g/mol.
+fn grams_per_mole() -> Dimension {
+    Dimension::new(vec![
+        (Quantity::Mass(Mass::Gram, None), Float::from(1)),
+        (Quantity::Amount(Amount::Mole, None), Float::from(-1)),
+    ])
+}
+
+/// Returns the reaction as-is if it is balanced, otherwise balances it.
+fn balanced(reaction: &Reaction, expr: &Expression) -> Result<Reaction, SiffraExecutionError> {
+    if reaction.is_balanced() {
+        Ok(reaction.clone())
+    } else {
+        reaction
+            .balance()
+            .map_err(|err| balance_error(reaction, err, expr))
+    }
+}
+
+fn coefficient(
+    reaction: &Reaction,
+    compound: &Compound,
+    expr: &Expression,
+) -> Result<u32, SiffraExecutionError> {
+    reaction.coefficient(compound).ok_or_else(|| {
+        siffra_error!(
+            "Chemical Error",
+            format!("'{}' does not take part in the reaction", compound),
+            expr.span()
+        )
+    })
+}
+
+fn balance_error(
+    reaction: &Reaction,
+    err: BalanceError,
+    expr: &Expression,
+) -> SiffraExecutionError {
+    match err {
+        BalanceError::Unbalanceable(Some(index)) => siffra_error!(
+            "Balance Error",
+            format!(
+                "Reaction cannot be balanced: no positive coefficient works for '{}'",
+                reaction.species().nth(index).unwrap().1
+            ),
+            species_span(expr, index)
+        ),
+        BalanceError::Unbalanceable(None) => siffra_error!(
+            "Balance Error",
+            "Reaction cannot be balanced with positive coefficients",
+            expr.span()
+        ),
+        BalanceError::Ambiguous(index) => siffra_error!(
+            "Balance Error",
+            format!(
+                "Reaction has more than one balancing: the coefficient of '{}' is not determined",
+                reaction.species().nth(index).unwrap().1
+            ),
+            species_span(expr, index)
+        ),
+    }
+}
+
+/// Returns the span of a species in a reaction literal, falling back to the whole expression.
+fn species_span(expr: &Expression, index: usize) -> Option<Span> {
+    match expr.inner() {
+        InnerExpression::Reaction(_, spans) => spans.get(index).copied().or(expr.span()),
+        _ => expr.span(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::evaluate_line;
+
+    #[test]
+    fn variables_and_units_come_before_compound_names() {
+        let mut state = SiffraState::new();
+        state.set_variable("x", Value::from(2.0).into());
+        let mut evaluate = |line| {
+            evaluate_line(line, &mut state)
+                .unwrap()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(evaluate("10 kg x"), "20 kg");
+        assert_eq!(evaluate("10 kg m"), "10 kg*m");
+        assert_eq!(evaluate("10 g water"), "10 g water (H2O)");
+        assert!(evaluate_line("10 g unobtainium", &mut state).is_err());
+    }
+
+    #[test]
+    fn constants_come_before_compound_names() {
+        let mut state = SiffraState::new();
+        let mut evaluate = |line| {
+            evaluate_line(line, &mut state)
+                .unwrap()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(evaluate("10 kg pi"), "31.4159265358979323846264338327 kg");
+        assert_eq!(evaluate("10 kg e"), "27.1828182845904523536028747135 kg");
+    }
+
+    #[test]
+    fn formulas_cannot_name_compounds() {
+        let mut state = SiffraState::new();
+        for line in ["compound Fe = Fe2O3", "compound H2O = NaCl"] {
+            let error = evaluate_line(line, &mut state).unwrap_err();
+            assert_eq!(error.message(), "Chemical Error", "{}", line);
+        }
+        assert_eq!(
+            evaluate_line("Fe", &mut state)
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "Fe"
+        );
+        assert!(evaluate_line("compound rust = Fe2O3", &mut state).is_ok());
+    }
+
+    #[test]
+    fn concentrations_keep_the_solution_volume_plain() {
+        let mut state = SiffraState::new();
+        let mut evaluate = |line| {
+            evaluate_line(line, &mut state)
+                .unwrap()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(evaluate("0.5 M NaCl to g/L"), "29.21988464 g NaCl/L");
+        assert_eq!(evaluate("1 mM NaCl to M"), "0.001 mol NaCl/L");
+        assert!(evaluate("2 mol CO2(g) to L").ends_with(" L CO2(g)"));
+    }
+
+    #[test]
+    fn reaction_yields() {
+        let mut state = SiffraState::new();
+        // Every call is on the reaction 2H2 + O2 -> 2H2O
+        let mut evaluate = |call: &str| {
+            let line = call.replacen('(', "(2H2 + O2 -> 2H2O, ", 1);
+            evaluate_line(&line, &mut state).map(|object| object.unwrap().to_string())
+        };
+
+        assert_eq!(evaluate("limiting_reagent(4 g H2, 16 g O2)").unwrap(), "O2");
+        assert_eq!(evaluate("limiting_reagent(1 g H2, 16 g O2)").unwrap(), "H2");
+        let moles = evaluate("theoretical_yield(4 g H2, 16 g O2)").unwrap();
+        assert!(moles.starts_with("1.0000625039") && moles.ends_with(" mol H2O"));
+        let grams = evaluate("theoretical_yield(4 g H2, 16 g O2, H2O) to g").unwrap();
+        assert!(grams.starts_with("18.016126007") && grams.ends_with(" g H2O"));
+
+        let error = evaluate("theoretical_yield(4 g H2, 16 g O2, NaCl)").unwrap_err();
+        assert_eq!(error.message(), "Chemical Error");
+        let error = evaluate("limiting_reagent(4 g H2, 2 s)").unwrap_err();
+        assert_eq!(error.message(), "Chemical Error");
+    }
+
+    #[test]
+    fn percent_yield_is_in_percent() {
+        let mut state = SiffraState::new();
+        let mut evaluate =
+            |line| evaluate_line(line, &mut state).map(|object| object.unwrap().to_string());
+
+        assert_eq!(evaluate("percent_yield(8 g, 10 g)").unwrap(), "80 %");
+        assert_eq!(evaluate("percent_yield(800 mg, 1 g)").unwrap(), "80 %");
+        let error = evaluate("percent_yield(10 g, 0 g)").unwrap_err();
+        assert_eq!(error.message(), "Division Error");
+        let error = evaluate("percent_yield(1 g, 1 mol)").unwrap_err();
+        assert_eq!(error.message(), "Unit Error");
+    }
+
+    #[test]
+    fn dilution_keeps_the_amount_of_solute() {
+        let mut state = SiffraState::new();
+        let mut evaluate =
+            |line| evaluate_line(line, &mut state).map(|object| object.unwrap().to_string());
+
+        // C1 V1 = C2 V2
+        assert_eq!(
+            evaluate("dilute(10 g/L NaCl, 50 mL, 250 mL)").unwrap(),
+            "2 g NaCl/L"
+        );
+        assert_eq!(
+            evaluate("dilution(2 M NaCl, 50 mL, 0.5 mol/L NaCl)").unwrap(),
+            "200 mL"
+        );
+        // Volumes in other units are converted
+        assert_eq!(
+            evaluate("dilute(2 M NaCl, 0.05 L, 250 cm^3)").unwrap(),
+            "0.4 mol NaCl/L"
+        );
+
+        let error = evaluate("dilution(1 M, 1 s, 2 s)").unwrap_err();
+        assert_eq!(error.message(), "Unit Error");
+        assert_eq!(
+            error.description(),
+            Some("dilution function takes a volume as the initial volume".to_string())
+        );
+        let error = evaluate("dilute(2 M NaCl, 50 mL, 1 g)").unwrap_err();
+        assert_eq!(error.message(), "Unit Error");
+    }
+
+    #[test]
+    fn decay_halves_the_amount_every_half_life() {
+        let mut state = SiffraState::new();
+        let mut evaluate =
+            |line| evaluate_line(line, &mut state).map(|object| object.unwrap().to_string());
+
+        assert_eq!(evaluate("decay(10 g, 5730 y, 17190 y)").unwrap(), "1.25 g");
+        // The half-life of a known isotope can be left out
+        assert_eq!(evaluate("decay(80 g C-14, 11460 y)").unwrap(), "20 g C-14");
+        // Times are converted to the units of the half-life
+        assert_eq!(evaluate("decay(1 mol, 2 d, 48 h)").unwrap(), "0.5 mol");
+        assert_eq!(
+            evaluate("decay_time(100 g, 25 g, 5730 y)").unwrap(),
+            "11,460 y"
+        );
+
+        let error = evaluate("decay(8 g, 1 h, 1 kg)").unwrap_err();
+        assert_eq!(error.message(), "Unit Error");
+    }
+
+    #[test]
+    fn activity_is_in_becquerels() {
+        let mut state = SiffraState::new();
+        let mut evaluate =
+            |line| evaluate_line(line, &mut state).map(|object| object.unwrap().to_string());
+
+        // ln(2) decays per second from 1 particle with a half-life of 1 s
+        let activity = evaluate("activity(1 particle, 1 s)").unwrap();
+        assert!(activity.starts_with("0.69314718055") && activity.ends_with(" Bq"));
+        let activity = evaluate("activity(1 g Co-60)").unwrap();
+        assert!(activity.starts_with("4.1867255399") && activity.ends_with("E13 Bq"));
+        let curies = evaluate("activity(1 g Co-60) to Ci").unwrap();
+        assert!(curies.starts_with("1,131.5474432") && curies.ends_with(" Ci"));
+
+        let error = evaluate("activity(1 g, 1 h)").unwrap_err();
+        assert_eq!(error.message(), "Unit Error");
+    }
+
+    #[test]
+    fn unknown_names_are_name_errors() {
+        let mut state = SiffraState::new();
+        for line in ["A", "AB", "x = A + 1", "molar_mass(A)"] {
+            let error = evaluate_line(line, &mut state).unwrap_err();
+            assert_eq!(error.message(), "Name Error", "{}", line);
+        }
+    }
+}
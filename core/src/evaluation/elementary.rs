@@ -0,0 +1,312 @@
+use super::{complex_value, radians, real_args};
+use crate::error::SiffraExecutionError;
+use crate::representations::{Complex, Expression, Float, Rational, Value};
+use crate::{siffra_error, siffra_try};
+use num_integer::Integer;
+
+/// Evaluates exponentials, hyperbolic functions, roots and the functions that compare, combine
+/// or take the sign of numbers. Returns `None` if `name` is not one of them.
+pub fn evaluate_elementary_function(
+    name: &str,
+    args: &[Value],
+    expr: &Expression,
+) -> Option<Result<Value, SiffraExecutionError>> {
+    match name {
+        "exp" => Some(exp(args, expr)),
+        "sinh" | "cosh" | "tanh" | "asinh" | "acosh" | "atanh" => {
+            Some(hyperbolic(name, args, expr))
+        }
+        "root" | "cbrt" => Some(root(name, args, expr)),
+        "sign" => Some(sign(args, expr)),
+        "clamp" => Some(clamp(args, expr)),
+        "hypot" => Some(hypot(args, expr)),
+        "atan2" => Some(atan2(args, expr)),
+        _ => None,
+    }
+}
+
+fn exp(args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let [x] = args else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "exp function takes exactly 1 argument",
+            expr.span()
+        ));
+    };
+    if !x.is_unitless() {
+        return Err(siffra_error!(
+            "Argument Error",
+            "exp function takes a number without units",
+            expr.span()
+        ));
+    }
+
+    if x.is_complex() {
+        Ok(complex_value(x.complex().exp(), x.dimension()))
+    } else {
+        Ok(Value::new(x.value.exp(), None))
+    }
+}
+
+fn hyperbolic(
+    name: &str,
+    args: &[Value],
+    expr: &Expression,
+) -> Result<Value, SiffraExecutionError> {
+    let [x] = args else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 1 argument", name),
+            expr.span()
+        ));
+    };
+    if x.is_complex() || !x.is_unitless() {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes a real number without units", name),
+            expr.span()
+        ));
+    }
+
+    let outside_domain = match name {
+        "acosh" => x.value < Float::from(1),
+        "atanh" => x.value.abs() >= Float::from(1),
+        _ => false,
+    };
+    if outside_domain {
+        let domain = if name == "acosh" {
+            "a number of at least 1"
+        } else {
+            "a number between -1 and 1"
+        };
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes {}", name, domain),
+            expr.span()
+        ));
+    }
+
+    let value = match name {
+        "sinh" => x.value.sinh(),
+        "cosh" => x.value.cosh(),
+        "tanh" => x.value.tanh(),
+        "asinh" => x.value.asinh(),
+        "acosh" => x.value.acosh(),
+        _ => x.value.atanh(),
+    };
+    Ok(Value::new(value, None))
+}
+
+/// Finds `root(n, x)`, written `root_n(x)`, or `cbrt(x)`. Odd roots of negative numbers are
+/// negative, as in `cbrt(-8) = -2`, and roots of units are taken too, as in `cbrt(27 m^3) = 3 m`.
+fn root(name: &str, args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let three = Value::from(Rational::from_integer(3.into()));
+    let (degree, x) = match (name, args) {
+        ("cbrt", [x]) => (&three, x),
+        ("root", [degree, x]) => (degree, x),
+        _ => {
+            return Err(siffra_error!(
+                "Argument Error",
+                if name == "cbrt" {
+                    "cbrt function takes exactly 1 argument"
+                } else {
+                    "root function takes a degree and a number, as in root_3(x)"
+                },
+                expr.span()
+            ))
+        }
+    };
+    if degree.is_complex() || !degree.is_unitless() || degree.value == Float::from(0) {
+        return Err(siffra_error!(
+            "Argument Error",
+            "The degree of a root must be a real number other than zero, without units",
+            expr.span()
+        ));
+    }
+
+    let power = match &degree.exact {
+        Some(exact) => Value::from(exact.recip()),
+        None => Value::new(degree.value.recip(), None),
+    };
+    let odd = degree.as_integer().is_some_and(|n| n.is_odd());
+    let root = if odd && !x.is_complex() && x.value < Float::from(0) {
+        let negative_one = Value::from(Rational::from_integer((-1).into()));
+        x.try_mul(&negative_one)
+            .and_then(|x| x.try_pow(&power))
+            .and_then(|root| root.try_mul(&negative_one))
+    } else {
+        x.try_pow(&power)
+    };
+
+    Ok(siffra_try!(
+        root.ok_or(()),
+        "Root Error",
+        "Error taking root",
+        expr.span()
+    ))
+}
+
+/// Finds -1, 0 or 1 as the number is negative, zero or positive, whatever its units.
+fn sign(args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let [x] = args else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "sign function takes exactly 1 argument",
+            expr.span()
+        ));
+    };
+    real_args("sign", args, expr)?;
+
+    let zero = Float::from(0);
+    let sign = if x.value > zero {
+        1
+    } else if x.value < zero {
+        -1
+    } else {
+        0
+    };
+    Ok(Value::from(Rational::from_integer(sign.into())))
+}
+
+/// Finds `clamp(x, low, high)`, the closest value to `x` from `low` to `high`.
+fn clamp(args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let args = same_units("clamp", args, expr)?;
+    let [x, low, high] = &args[..] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "clamp function takes a number, a lower limit and an upper limit",
+            expr.span()
+        ));
+    };
+    if low.value > high.value {
+        return Err(siffra_error!(
+            "Argument Error",
+            "The lower limit of clamp must not be above the upper limit",
+            expr.span()
+        ));
+    }
+
+    if x.value < low.value {
+        Ok(low.clone())
+    } else if x.value > high.value {
+        Ok(high.clone())
+    } else {
+        Ok(x.clone())
+    }
+}
+
+/// Finds the length of the hypotenuse with the given sides, `sqrt(a^2 + b^2 + ...)`.
+fn hypot(args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let args = same_units("hypot", args, expr)?;
+    let Some(first) = args.first() else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "hypot function takes at least 1 argument",
+            expr.span()
+        ));
+    };
+
+    let sum = args
+        .iter()
+        .fold(Float::from(0), |sum, arg| &sum + &(&arg.value * &arg.value));
+    Ok(Value::new(sum.sqrt(), Some(first.dimension())))
+}
+
+/// Finds `atan2(y, x)`, the angle from the positive x axis to the point `(x, y)`, from -π to π.
+fn atan2(args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let args = same_units("atan2", args, expr)?;
+    let [y, x] = &args[..] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "atan2 function takes exactly 2 arguments",
+            expr.span()
+        ));
+    };
+
+    let angle = Complex::new(x.value.clone(), y.value.clone()).argument();
+    Ok(Value::new(angle, Some(radians())))
+}
+
+/// Converts real arguments to the units of the first one.
+pub(super) fn same_units(
+    name: &str,
+    args: &[Value],
+    expr: &Expression,
+) -> Result<Vec<Value>, SiffraExecutionError> {
+    real_args(name, args, expr)?;
+    let Some(first) = args.first() else {
+        return Ok(Vec::new());
+    };
+
+    args.iter()
+        .map(|arg| {
+            Ok(siffra_try!(
+                arg.convert(&first.dimension).ok_or(()),
+                "Unit Error",
+                format!("Units do not match in {}", name),
+                expr.span()
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{evaluate_line, SiffraState};
+
+    fn evaluate(line: &str) -> String {
+        evaluate_line(line, &mut SiffraState::new())
+            .unwrap()
+            .unwrap()
+            .to_string()
+    }
+
+    fn argument_error(line: &str) -> bool {
+        evaluate_line(line, &mut SiffraState::new())
+            .is_err_and(|error| error.message() == "Argument Error")
+    }
+
+    #[test]
+    fn roots_keep_signs_and_units() {
+        assert_eq!(evaluate("cbrt(-8)"), "-2");
+        assert_eq!(evaluate("cbrt(27 m^3)"), "3 m");
+        assert_eq!(evaluate("root(4, 16)"), "2");
+    }
+
+    #[test]
+    fn inverse_functions_check_their_domains() {
+        assert!(argument_error("acosh(0.5)"));
+        assert!(argument_error("atanh(2)"));
+        assert!(argument_error("atanh(1)"));
+        assert!(argument_error("asin(2)"));
+        assert!(argument_error("acos(-1.5)"));
+        assert!(evaluate("atanh(0.5)").starts_with("0.5493061443"));
+        assert!(evaluate("acosh(2)").starts_with("1.3169578969"));
+    }
+
+    #[test]
+    fn logarithms_need_a_positive_base_other_than_1() {
+        assert!(argument_error("log_1(5)"));
+        assert!(argument_error("log_0(5)"));
+        assert!(argument_error("log(-2, 8)"));
+        assert_eq!(evaluate("log_2(8)"), "3");
+    }
+
+    #[test]
+    fn clamp_needs_ordered_limits() {
+        assert_eq!(evaluate("clamp(5, 1, 10)"), "5");
+        assert_eq!(evaluate("clamp(15, 1, 10)"), "10");
+        assert!(argument_error("clamp(5, 10, 1)"));
+    }
+
+    #[test]
+    fn atan2_finds_the_quadrant() {
+        let quarter = "0.78539816339744830961566084582 rad";
+        let three_quarters = "2.35619449019234492884698253745 rad";
+        assert_eq!(evaluate("atan2(1, 1)"), quarter);
+        assert_eq!(evaluate("atan2(1, -1)"), three_quarters);
+        assert_eq!(evaluate("atan2(-1, -1)"), format!("-{}", three_quarters));
+        assert_eq!(evaluate("atan2(-1, 1)"), format!("-{}", quarter));
+        assert_eq!(evaluate("hypot(3, 4)"), "5");
+    }
+}
@@ -0,0 +1,516 @@
+use super::real_args;
+use crate::error::SiffraExecutionError;
+use crate::representations::{Expression, Float, FloatContext, Object, Rational, Value};
+use crate::{siffra_error, siffra_try};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+/// The most numbers `nCr` and `nPr` multiply together, since larger counts would take too long to
+/// find.
+const MAX_COUNTING_TERMS: u64 = 100_000;
+/// Factors below this are found by dividing by every number, and larger ones with Pollard's rho.
+const TRIAL_DIVISION_LIMIT: u32 = 10_000;
+/// How many steps Pollard's rho takes before giving up on finding a factor, which finds factors
+/// up to about the square of this while keeping `factor` quick for numbers it can't factor.
+const MAX_RHO_STEPS: usize = 50_000;
+/// How many steps Pollard's rho takes between each gcd.
+const RHO_BATCH: usize = 100;
+/// The most bits a number can be shifted by.
+const MAX_SHIFT: u32 = 10_000;
+
+/// Evaluates functions of whole numbers, and the rounding and remainders of other numbers.
+/// Returns `None` if `name` is not one of them.
+pub fn evaluate_integer_function(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Option<Result<Object, SiffraExecutionError>> {
+    match name {
+        "mod" | "div" => Some(remainder(name, args, objects, expr)),
+        "floor" | "ceil" | "round" | "trunc" => Some(rounded(name, objects, expr)),
+        "gcd" | "lcm" => Some(gcd_lcm(name, args, objects, expr)),
+        "is_prime" => Some(prime_test(args, objects, expr)),
+        "factor" => Some(factor(args, objects, expr)),
+        "nCr" | "nPr" => Some(counting(name, args, objects, expr)),
+        "and" | "or" | "xor" | "not" | "shl" | "shr" => Some(bitwise(name, args, objects, expr)),
+        _ => None,
+    }
+}
+
+/// Finds `div(a, b)`, the number of times `b` fits in `a`, or `mod(a, b)`, what is left over.
+/// The quotient is rounded down, so the remainder has the sign of `b` as in `mod(-7, 3) = 2`.
+fn remainder(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [Object::Value(dividend), Object::Value(divisor)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 2 numbers", name),
+            expr.span()
+        ));
+    };
+    real_args(name, objects.iter().filter_map(Object::as_value), expr)?;
+
+    let divisor = siffra_try!(
+        divisor.convert(&dividend.dimension).ok_or(()),
+        "Unit Error",
+        format!("Units do not match in {}", name),
+        args[1].span()
+    );
+    if divisor.value == Float::from(0) {
+        return Err(siffra_error!(
+            "Division Error",
+            "Division by zero",
+            expr.span()
+        ));
+    }
+
+    let quotient = match (&dividend.exact, &divisor.exact) {
+        (Some(dividend), Some(divisor)) => Value::from((dividend / divisor).floor()),
+        _ => Value::new((&dividend.value / &divisor.value).floor(), None),
+    };
+    if name == "div" {
+        return Ok(quotient.into());
+    }
+
+    let remainder = divisor
+        .try_mul(&quotient)
+        .and_then(|multiple| dividend.try_sub(&multiple));
+    Ok(siffra_try!(
+        remainder.ok_or(()),
+        "Unit Error",
+        format!("Units do not match in {}", name),
+        expr.span()
+    )
+    .into())
+}
+
+/// Rounds a number to a whole number of its units. `round` rounds halves away from zero, as
+/// numbers are when they are displayed.
+fn rounded(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [Object::Value(value)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 1 number", name),
+            expr.span()
+        ));
+    };
+    real_args(name, objects.iter().filter_map(Object::as_value), expr)?;
+
+    let exact = value.exact.as_ref().map(|exact| match name {
+        "floor" => exact.floor(),
+        "ceil" => exact.ceil(),
+        "round" => exact.round(),
+        _ => exact.trunc(),
+    });
+    let rounded = match name {
+        "floor" => value.value.floor(),
+        "ceil" => value.value.ceil(),
+        "round" => value.value.round(),
+        _ => value.value.trunc(),
+    };
+
+    Ok(Value::new(rounded, Some(value.dimension()))
+        .with_exact(exact)
+        .into())
+}
+
+fn gcd_lcm(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let numbers = integer_args(name, args, objects)?;
+    let result = numbers
+        .into_iter()
+        .reduce(|a, b| if name == "gcd" { a.gcd(&b) } else { a.lcm(&b) })
+        .ok_or_else(|| {
+            siffra_error!(
+                "Argument Error",
+                format!("{} function takes at least 1 number", name),
+                expr.span()
+            )
+        })?;
+
+    Ok(Value::from(Rational::from_integer(result)).into())
+}
+
+fn prime_test(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [number] = &integer_args("is_prime", args, objects)?[..] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "is_prime function takes exactly 1 number",
+            expr.span()
+        ));
+    };
+
+    // 1 for primes and 0 otherwise
+    let prime = BigInt::from(u8::from(is_prime(number)));
+    Ok(Value::from(Rational::from_integer(prime)).into())
+}
+
+/// Lists the prime factors of a whole number with repeats, as in `[2, 2, 2, 3, 3, 5]` for 360.
+/// Negative numbers start with a factor of -1.
+fn factor(
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [number] = &integer_args("factor", args, objects)?[..] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "factor function takes exactly 1 number",
+            expr.span()
+        ));
+    };
+    if number.is_zero() {
+        return Err(siffra_error!(
+            "Argument Error",
+            "0 has no prime factors",
+            args[0].span()
+        ));
+    }
+    if number.abs().is_one() {
+        return Ok(Object::List(vec![Value::from(Rational::from_integer(
+            number.clone(),
+        ))]));
+    }
+
+    let factors = siffra_try!(
+        prime_factors(&number.abs()).ok_or(()),
+        "Argument Error",
+        format!("{} is too large to factor", number),
+        args[0].span()
+    );
+
+    let sign = number.is_negative().then(|| -BigInt::one());
+    Ok(Object::List(
+        sign.into_iter()
+            .chain(factors)
+            .map(|factor| Value::from(Rational::from_integer(factor)))
+            .collect(),
+    ))
+}
+
+/// Finds `nCr(n, r)`, the number of ways to choose `r` of `n` things, or `nPr(n, r)`, the number
+/// of ways to arrange them.
+fn counting(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [n, r] = &integer_args(name, args, objects)?[..] else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 2 numbers", name),
+            expr.span()
+        ));
+    };
+    if n.is_negative() || r.is_negative() {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes numbers of at least 0", name),
+            expr.span()
+        ));
+    }
+    if r > n {
+        return Ok(Value::from(Rational::zero()).into());
+    }
+
+    // Choosing `r` things is the same as choosing the `n - r` left behind
+    let terms = if name == "nCr" {
+        r.clone().min(n - r)
+    } else {
+        r.clone()
+    };
+    let terms = siffra_try!(
+        terms
+            .to_u64()
+            .filter(|terms| *terms <= MAX_COUNTING_TERMS)
+            .ok_or(()),
+        "Argument Error",
+        format!("{}({}, ...) is too large to calculate", name, n),
+        expr.span()
+    );
+
+    let mut result = BigInt::one();
+    for i in 0..terms {
+        result *= n - i;
+        if name == "nCr" {
+            // Each partial product is itself a number of combinations, so this divides exactly
+            result /= i + 1;
+        }
+    }
+
+    Ok(Value::from(Rational::from_integer(result)).into())
+}
+
+/// Combines the bits of whole numbers, with negative numbers in two's complement. In programmer
+/// mode, results wrap around into the signed numbers a word holds, so `not 0` is `-1`.
+fn bitwise(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let result = match (name, &integer_args(name, args, objects)?[..]) {
+        ("not", [n]) => !n,
+        ("and", [a, b]) => a & b,
+        ("or", [a, b]) => a | b,
+        ("xor", [a, b]) => a ^ b,
+        ("shl" | "shr", [n, shift]) => {
+            let shift = siffra_try!(
+                shift.to_u32().filter(|shift| *shift <= MAX_SHIFT).ok_or(()),
+                "Argument Error",
+                format!("Numbers can only be shifted by 0 to {} bits", MAX_SHIFT),
+                args[1].span()
+            );
+            if name == "shl" {
+                n << shift
+            } else {
+                n >> shift
+            }
+        }
+        _ => {
+            return Err(siffra_error!(
+                "Argument Error",
+                if name == "not" {
+                    "not function takes exactly 1 number".to_string()
+                } else {
+                    format!("{} function takes exactly 2 numbers", name)
+                },
+                expr.span()
+            ))
+        }
+    };
+
+    let result = match FloatContext::current().format.word_size {
+        Some(bits) => wrap_to_word(result, bits),
+        None => result,
+    };
+    Ok(Value::from(Rational::from_integer(result)).into())
+}
+
+/// Wraps a whole number around into the signed numbers a word of `bits` bits holds.
+fn wrap_to_word(number: BigInt, bits: u32) -> BigInt {
+    let modulus = BigInt::one() << bits;
+    let wrapped = number.mod_floor(&modulus);
+    if wrapped >= &modulus >> 1 {
+        wrapped - modulus
+    } else {
+        wrapped
+    }
+}
+
+/// Interprets every argument as a whole number without units.
+fn integer_args(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+) -> Result<Vec<BigInt>, SiffraExecutionError> {
+    args.iter()
+        .zip(objects)
+        .map(|(arg, object)| {
+            let whole = match object {
+                Object::Value(value) if value.is_unitless() => value.as_integer(),
+                _ => None,
+            };
+            whole.ok_or_else(|| {
+                siffra_error!(
+                    "Argument Error",
+                    format!("{} function takes whole numbers without units", name),
+                    arg.span()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Tests whether a number is prime with the Miller-Rabin test, using the primes up to 41 as
+/// bases. This is certain below 3.3 × 10^24, and no larger composite number is known to pass.
+fn is_prime(n: &BigInt) -> bool {
+    const BASES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+    if *n < BigInt::from(2) {
+        return false;
+    }
+    for base in BASES {
+        if *n == BigInt::from(base) {
+            return true;
+        }
+        if (n % base).is_zero() {
+            return false;
+        }
+    }
+
+    // n - 1 = odd × 2^twos
+    let n_minus_one = n - BigInt::one();
+    let twos = n_minus_one.trailing_zeros().unwrap_or(0);
+    let odd = &n_minus_one >> twos;
+
+    BASES.iter().all(|base| {
+        let mut x = BigInt::from(*base).modpow(&odd, n);
+        if x.is_one() || x == n_minus_one {
+            return true;
+        }
+        for _ in 1..twos {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// The prime factors of a number greater than 1 from smallest to largest, or nothing if a factor
+/// is too large to find.
+fn prime_factors(n: &BigInt) -> Option<Vec<BigInt>> {
+    let mut factors = Vec::new();
+    let mut n = n.clone();
+
+    let mut divisor = BigInt::from(2);
+    while divisor < BigInt::from(TRIAL_DIVISION_LIMIT) && &divisor * &divisor <= n {
+        while (&n % &divisor).is_zero() {
+            n /= &divisor;
+            factors.push(divisor.clone());
+        }
+        divisor += 1;
+    }
+
+    let mut unfactored = vec![n];
+    while let Some(n) = unfactored.pop() {
+        if n.is_one() {
+            continue;
+        }
+        if is_prime(&n) {
+            factors.push(n);
+            continue;
+        }
+        let divisor = pollard_rho(&n)?;
+        unfactored.push(&n / &divisor);
+        unfactored.push(divisor);
+    }
+
+    factors.sort();
+    Some(factors)
+}
+
+/// Finds a factor of a composite number by looking for a repeat in a pseudo-random sequence
+/// modulo the factor, which happens after about the square root of the factor's steps. The
+/// differences are multiplied together so that only one gcd is taken for each batch of steps.
+fn pollard_rho(n: &BigInt) -> Option<BigInt> {
+    'constants: for constant in 1..=3 {
+        let step = |x: &BigInt| (x * x + constant) % n;
+        let mut slow = BigInt::from(2);
+        let mut fast = BigInt::from(2);
+
+        for _ in 0..MAX_RHO_STEPS / RHO_BATCH {
+            let (batch_slow, batch_fast) = (slow.clone(), fast.clone());
+            let mut product = BigInt::one();
+            for _ in 0..RHO_BATCH {
+                slow = step(&slow);
+                fast = step(&step(&fast));
+                product = product * (&slow - &fast).abs() % n;
+            }
+            let divisor = product.gcd(n);
+            if divisor.is_one() {
+                continue;
+            }
+            if divisor != *n {
+                return Some(divisor);
+            }
+
+            // The batch found every factor at once, so it is repeated one step at a time, and
+            // another constant is tried if a single step finds them all too
+            (slow, fast) = (batch_slow, batch_fast);
+            loop {
+                slow = step(&slow);
+                fast = step(&step(&fast));
+                let divisor = (&slow - &fast).abs().gcd(n);
+                if divisor == *n {
+                    continue 'constants;
+                }
+                if !divisor.is_one() {
+                    return Some(divisor);
+                }
+            }
+        }
+        // Other constants would take as long, so the search stops here
+        return None;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::{evaluate_line, SiffraState};
+
+    #[test]
+    fn test_is_prime() {
+        let primes: [u64; 5] = [2, 3, 41, 7919, 2_147_483_647];
+        let composites: [u64; 6] = [0, 1, 4, 561, 3_215_031_751, 2_147_483_649];
+
+        assert!(primes.iter().all(|n| is_prime(&BigInt::from(*n))));
+        assert!(!composites.iter().any(|n| is_prime(&BigInt::from(*n))));
+        // 2^89 - 1 is a Mersenne prime
+        assert!(is_prime(&((BigInt::one() << 89u32) - 1)));
+    }
+
+    #[test]
+    fn test_wrap_to_word() {
+        assert_eq!(wrap_to_word(BigInt::from(255), 8), BigInt::from(-1));
+        assert_eq!(wrap_to_word(BigInt::from(127), 8), BigInt::from(127));
+        assert_eq!(wrap_to_word(BigInt::from(-129), 8), BigInt::from(127));
+    }
+
+    #[test]
+    fn test_prime_factors() {
+        let factors = |n: u64| {
+            prime_factors(&BigInt::from(n))
+                .unwrap()
+                .iter()
+                .map(|factor| factor.to_u64().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(factors(360), vec![2, 2, 2, 3, 3, 5]);
+        assert_eq!(factors(1_000_003), vec![1_000_003]);
+        // Too large for trial division
+        assert_eq!(factors(1_000_003 * 1_000_033), vec![1_000_003, 1_000_033]);
+        // 2^128 + 1 has two factors above 10^16, which are given up on quickly
+        assert_eq!(prime_factors(&((BigInt::one() << 128u32) + 1)), None);
+    }
+
+    #[test]
+    fn mod_operator_matches_mod_function() {
+        let mut state = SiffraState::new();
+        let mut evaluate = |line| {
+            evaluate_line(line, &mut state)
+                .unwrap()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(evaluate("mod(-17, 5)"), "3");
+        assert_eq!(evaluate("-17 mod 5"), "3");
+        assert_eq!(evaluate("17 mod -5"), "-3");
+        assert_eq!(evaluate("-2^2"), "-4");
+    }
+}
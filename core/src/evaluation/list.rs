@@ -0,0 +1,191 @@
+use crate::error::SiffraExecutionError;
+use crate::grammar::Span;
+use crate::representations::{Expression, Object, Rational, Value};
+use crate::{siffra_error, siffra_try};
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+
+/// Evaluates functions of lists, and `x[n]`, which is a call to `index`. Returns `None` if `name`
+/// is not one of them.
+pub fn evaluate_list_function(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Option<Result<Object, SiffraExecutionError>> {
+    match name {
+        "len" => Some(length(objects, expr)),
+        "sum" | "product" => Some(total(name, objects, expr)),
+        "sort" => Some(sort(objects, expr)),
+        "index" => Some(index(args, objects)),
+        _ => None,
+    }
+}
+
+fn length(objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let [Object::List(values)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "len function takes exactly 1 list",
+            expr.span()
+        ));
+    };
+    Ok(Value::from(Rational::from_integer(BigInt::from(values.len()))).into())
+}
+
+/// Adds or multiplies together the numbers of a list, or any number of numbers, as in
+/// `sum(1, 2, 3)`.
+fn total(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let values = numbers(name, objects, expr)?;
+    let Some((first, rest)) = values.split_first() else {
+        // The empty sum is 0 and the empty product is 1
+        let empty = match name {
+            "sum" => Rational::zero(),
+            _ => Rational::one(),
+        };
+        return Ok(Value::from(empty).into());
+    };
+
+    let mut total = first.clone();
+    for value in rest {
+        total = siffra_try!(
+            match name {
+                "sum" => total.try_add(value),
+                _ => total.try_mul(value),
+            }
+            .ok_or(()),
+            "Unit Error",
+            format!("Units do not match in {}", name),
+            expr.span()
+        );
+    }
+    Ok(total.into())
+}
+
+/// Sorts a list from smallest to largest. Numbers with different units that measure the same
+/// thing are compared, as in `sort([1 m, 50 cm])`.
+fn sort(objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let [Object::List(values)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "sort function takes exactly 1 list",
+            expr.span()
+        ));
+    };
+    sorted("sort", values, expr).map(Object::List)
+}
+
+/// Sorts numbers from smallest to largest, keeping the units each is in.
+pub(super) fn sorted(
+    name: &str,
+    values: &[Value],
+    expr: &Expression,
+) -> Result<Vec<Value>, SiffraExecutionError> {
+    if values.iter().any(Value::is_complex) {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("Complex numbers can't be sorted in {}", name),
+            expr.span()
+        ));
+    }
+
+    let mut keyed = Vec::with_capacity(values.len());
+    for value in values {
+        let key = siffra_try!(
+            value.convert(&values[0].dimension).ok_or(()),
+            "Unit Error",
+            format!("Units do not match in {}", name),
+            expr.span()
+        );
+        keyed.push((key.value, value.clone()));
+    }
+    keyed.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    Ok(keyed.into_iter().map(|(_, value)| value).collect())
+}
+
+/// Finds `list[n]`, counting from 1. Negative indices count back from the end, so `list[-1]` is
+/// the last number.
+fn index(args: &[Expression], objects: &[Object]) -> Result<Object, SiffraExecutionError> {
+    let [Object::List(values), position] = objects else {
+        return Err(siffra_error!(
+            "Type Error",
+            format!("Only lists can be indexed, not a {}", objects[0].kind()),
+            args[0].span()
+        ));
+    };
+    let offset = offset(
+        position,
+        values.len(),
+        &format!("a list of {} numbers", values.len()),
+        args[1].span(),
+    )?;
+    Ok(values[offset].clone().into())
+}
+
+/// Finds where `position`, counting from 1 or back from the end if negative, is in something
+/// `length` long, as an offset from 0.
+pub(super) fn offset(
+    position: &Object,
+    length: usize,
+    container: &str,
+    span: Option<Span>,
+) -> Result<usize, SiffraExecutionError> {
+    let position = siffra_try!(
+        position
+            .as_value()
+            .filter(|position| position.is_unitless())
+            .and_then(Value::as_integer)
+            .ok_or(()),
+        "Index Error",
+        "An index must be a whole number",
+        span
+    );
+
+    let offset = if position.is_negative() {
+        BigInt::from(length) + &position
+    } else {
+        &position - BigInt::one()
+    };
+    Ok(siffra_try!(
+        offset
+            .to_usize()
+            .filter(|offset| !position.is_zero() && *offset < length)
+            .ok_or(()),
+        "Index Error",
+        format!("Index {} is outside {}", position, container),
+        span
+    ))
+}
+
+/// The numbers of a single list argument, or the arguments themselves.
+pub(super) fn numbers(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Vec<Value>, SiffraExecutionError> {
+    if let [Object::List(values)] = objects {
+        return Ok(values.clone());
+    }
+
+    objects
+        .iter()
+        .map(|object| match object {
+            Object::Value(value) => Ok(value.clone()),
+            object => Err(siffra_error!(
+                "Argument Error",
+                format!(
+                    "{} function takes a list or numbers, not a {}",
+                    name,
+                    object.kind()
+                ),
+                expr.span()
+            )),
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use super::list::offset;
+use crate::error::SiffraExecutionError;
+use crate::grammar::Span;
+use crate::representations::{Expression, Matrix, MatrixError, Object, Value};
+use crate::{siffra_error, siffra_try};
+
+/// Evaluates functions of matrices, and `A[n]`, the nth row of a matrix, which is a call to
+/// `index`. Returns `None` if `name` is not one of them.
+pub fn evaluate_matrix_function(
+    name: &str,
+    args: &[Expression],
+    objects: &[Object],
+    expr: &Expression,
+) -> Option<Result<Object, SiffraExecutionError>> {
+    match (name, objects) {
+        ("index", [Object::Matrix(matrix), position]) => {
+            Some(row(matrix, position, args[1].span()))
+        }
+        ("transpose", _) => Some(matrix_arg(name, objects, expr).map(|a| a.transpose().into())),
+        ("det" | "determinant", _) => Some(
+            matrix_arg(name, objects, expr)
+                .and_then(|a| a.determinant().map_err(|e| matrix_error(e, expr.span())))
+                .map(Object::Value),
+        ),
+        ("inv" | "inverse", _) => Some(
+            matrix_arg(name, objects, expr)
+                .and_then(|a| a.inverse().map_err(|e| matrix_error(e, expr.span())))
+                .map(Object::Matrix),
+        ),
+        ("solve", _) => Some(solve(objects, expr)),
+        ("eig" | "eigenvalues", _) => Some(
+            matrix_arg(name, objects, expr)
+                .and_then(|a| a.eigenvalues().map_err(|e| matrix_error(e, expr.span())))
+                .map(Object::List),
+        ),
+        _ => None,
+    }
+}
+
+/// Multiplies matrices as matrices. A list is a column vector after a matrix and a row vector
+/// before one, and the product is a list again. Returns `None` for other objects.
+pub(super) fn multiply(
+    a: &Object,
+    b: &Object,
+    expr: &Expression,
+) -> Option<Result<Object, SiffraExecutionError>> {
+    let product = |a: &Matrix, b: &Matrix| a.try_mul(b).map_err(|e| matrix_error(e, expr.span()));
+    match (a, b) {
+        (Object::Matrix(a), Object::Matrix(b)) => Some(product(a, b).map(Object::Matrix)),
+        (Object::Matrix(a), Object::List(b)) => {
+            let column = b.iter().map(|b| vec![b.clone()]).collect();
+            Some(
+                vector(column, expr)
+                    .and_then(|b| product(a, &b))
+                    .map(into_list),
+            )
+        }
+        (Object::List(a), Object::Matrix(b)) => Some(
+            vector(vec![a.clone()], expr)
+                .and_then(|a| product(&a, b))
+                .map(into_list),
+        ),
+        _ => None,
+    }
+}
+
+/// Raises a square matrix to a whole power, as in `A^2` or `A^-1`.
+pub(super) fn power(
+    matrix: &Matrix,
+    exponent: &Object,
+    exponent_span: Option<Span>,
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let exponent = siffra_try!(
+        exponent
+            .as_value()
+            .filter(|exponent| exponent.is_unitless())
+            .and_then(Value::as_integer)
+            .ok_or(()),
+        "Matrix Error",
+        "A matrix can only be raised to a whole power",
+        exponent_span
+    );
+    matrix
+        .power(&exponent)
+        .map(Object::Matrix)
+        .map_err(|e| matrix_error(e, expr.span()))
+}
+
+/// Finds `solve(A, b)`, the list `x` for which `A x = b`.
+fn solve(objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let [Object::Matrix(a), Object::List(b)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "solve function takes a matrix and a list, as in solve(A, b) for A x = b",
+            expr.span()
+        ));
+    };
+    a.solve(b)
+        .map(Object::List)
+        .map_err(|e| matrix_error(e, expr.span()))
+}
+
+fn row(
+    matrix: &Matrix,
+    position: &Object,
+    span: Option<Span>,
+) -> Result<Object, SiffraExecutionError> {
+    let offset = offset(
+        position,
+        matrix.rows(),
+        &format!("a matrix of {} rows", matrix.rows()),
+        span,
+    )?;
+    Ok(Object::List(matrix.row(offset).to_vec()))
+}
+
+fn matrix_arg<'a>(
+    name: &str,
+    objects: &'a [Object],
+    expr: &Expression,
+) -> Result<&'a Matrix, SiffraExecutionError> {
+    match objects {
+        [Object::Matrix(matrix)] => Ok(matrix),
+        _ => Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 1 matrix", name),
+            expr.span()
+        )),
+    }
+}
+
+fn vector(rows: Vec<Vec<Value>>, expr: &Expression) -> Result<Matrix, SiffraExecutionError> {
+    Matrix::from_rows(rows).map_err(|_| {
+        siffra_error!(
+            "Matrix Error",
+            "An empty list can't be multiplied by a matrix",
+            expr.span()
+        )
+    })
+}
+
+fn into_list(matrix: Matrix) -> Object {
+    Object::List(matrix.entries().to_vec())
+}
+
+pub(super) fn matrix_error(error: MatrixError, span: Option<Span>) -> SiffraExecutionError {
+    let title = match error {
+        MatrixError::Units => "Unit Error",
+        _ => "Matrix Error",
+    };
+    siffra_error!(title, error.to_string(), span)
+}
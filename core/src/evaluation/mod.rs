@@ -1,21 +1,53 @@
+mod chemistry;
+mod elementary;
+mod integer;
+mod list;
+mod matrix;
 mod state;
+mod statistics;
 
 use crate::error::SiffraExecutionError;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
 use pest::error::InputLocation;
 use pest::Parser;
 pub use state::SiffraState;
 use std::ops::Mul;
 
+use crate::evaluation::chemistry::{
+    conversion_error, convert_via, evaluate_chemical_function, resolve_compound_names,
+};
+use crate::evaluation::elementary::evaluate_elementary_function;
+use crate::evaluation::integer::evaluate_integer_function;
+use crate::evaluation::list::evaluate_list_function;
+use crate::evaluation::matrix::{evaluate_matrix_function, matrix_error};
 use crate::evaluation::state::VariableAccessError;
-use crate::grammar::representation::ParsedLine;
+use crate::evaluation::statistics::evaluate_statistics_function;
+use crate::grammar::representation::{formula_error, ParsedLine};
 use crate::grammar::{parse_line, Rule, SiffraParser, Span};
-use crate::representations::{Dimension, Expression, Float, InnerExpression, Value};
+use crate::representations::{
+    Angle, Complex, Compound, Digits, Dimension, Element, Expression, Float, FloatFormat,
+    InnerExpression, Locale, Matrix, Notation, Object, Quantity, Rational, Rounding, Uncertainty,
+    Value, MAX_PRECISION, MIN_PRECISION,
+};
 use crate::{siffra_error, siffra_try};
 
-pub type EvaluationResult = Result<Option<Value>, SiffraExecutionError>;
+pub type EvaluationResult = Result<Option<Object>, SiffraExecutionError>;
+
+/// The largest factorial found exactly. Larger ones are found with the gamma function.
+const MAX_EXACT_FACTORIAL: u64 = 10_000;
 
 pub fn evaluate_line(line: &str, state: &mut SiffraState) -> EvaluationResult {
-    let pairs = SiffraParser::parse(Rule::line, line).map_err(|e| match e.location {
+    state.clear_line_format();
+    // Every number in the line is calculated with the document's precision and rounding
+    state
+        .float_context()
+        .scope(|| evaluate_line_in_context(line, state))
+}
+
+fn evaluate_line_in_context(line: &str, state: &mut SiffraState) -> EvaluationResult {
+    let line = state.locale().normalize_numbers(line);
+    let pairs = SiffraParser::parse(Rule::line, &line).map_err(|e| match e.location {
         InputLocation::Pos(pos) => siffra_error!(
             "Syntax Error",
             "Could not parse the provided line",
@@ -33,7 +65,40 @@ pub fn evaluate_line(line: &str, state: &mut SiffraState) -> EvaluationResult {
             state.clear_block_total();
             Ok(None)
         }
-        ParsedLine::Expression(expr) => {
+        ParsedLine::Compound {
+            name,
+            formula,
+            span,
+        } => {
+            // Names that are formulas would be read as the formula instead of the compound
+            if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+                let reason = if Element::from_symbol(&name).is_some() {
+                    "the symbol of an element"
+                } else {
+                    "a chemical formula"
+                };
+                return Err(siffra_error!(
+                    "Chemical Error",
+                    format!(
+                        "'{}' is {}, so it can't name a compound. Compound names start with a \
+                        lowercase letter",
+                        name, reason
+                    ),
+                    Some(span)
+                ));
+            }
+            let compound =
+                Compound::parse(&formula).map_err(|err| formula_error(err, &formula, span))?;
+            state.define_compound(&name, compound);
+            Ok(state.compound(&name).map(Object::Compound))
+        }
+        ParsedLine::Macro { name, input, span } => {
+            evaluate_macro(&name, input.as_deref(), span, state)?;
+            Ok(None)
+        }
+        ParsedLine::Expression(mut expr) => {
+            state.change_line_format(&expr.take_format_changes());
+            resolve_compound_names(&mut expr, state)?;
             let value = match evaluate_expr(&expr.try_into()?, state) {
                 Ok(value) => value,
                 Err(err) => {
@@ -43,15 +108,19 @@ pub fn evaluate_line(line: &str, state: &mut SiffraState) -> EvaluationResult {
             };
             state.set_previous_value(value.clone());
             state.add_to_block_total(&value);
+            state.show_significant_figures(&value);
             Ok(Some(value))
         }
-        ParsedLine::Variable(name, expr) => {
+        ParsedLine::Variable(name, mut expr) => {
+            state.change_line_format(&expr.take_format_changes());
+            resolve_compound_names(&mut expr, state)?;
             let value = evaluate_expr(&expr.try_into()?, state);
             match value {
                 Ok(value) => {
                     state.set_variable(&name, value.clone());
                     state.set_previous_value(value.clone());
                     state.add_to_block_total(&value);
+                    state.show_significant_figures(&value);
                     Ok(Some(value))
                 }
                 Err(err) => {
@@ -64,26 +133,297 @@ pub fn evaluate_line(line: &str, state: &mut SiffraState) -> EvaluationResult {
     }
 }
 
-pub fn evaluate_expr(
+/// Applies a directive such as `#precision 512` to the rest of the document.
+fn evaluate_macro(
+    name: &str,
+    input: Option<&str>,
+    span: Span,
+    state: &mut SiffraState,
+) -> Result<(), SiffraExecutionError> {
+    match name {
+        "precision" => {
+            let precision = siffra_try!(
+                input
+                    .and_then(|input| input.parse::<usize>().ok())
+                    .filter(|precision| (MIN_PRECISION..=MAX_PRECISION).contains(precision))
+                    .ok_or(()),
+                "Directive Error",
+                format!(
+                    "Precision must be a number of bits between {} and {}",
+                    MIN_PRECISION, MAX_PRECISION
+                ),
+                Some(span)
+            );
+            state.set_precision(precision);
+        }
+        "rounding" => {
+            let rounding = siffra_try!(
+                input.unwrap_or_default().parse::<Rounding>(),
+                "Directive Error",
+                "Rounding must be one of even, odd, up, down, zero or away",
+                Some(span)
+            );
+            state.set_rounding(rounding);
+        }
+        "format" | "notation" => {
+            let notation = siffra_try!(
+                input.unwrap_or_default().parse::<Notation>(),
+                "Directive Error",
+                "Format must be one of auto, sci, eng, plain, fraction, repeating, polar or rectangular",
+                Some(span)
+            );
+            state.set_format(FloatFormat {
+                notation,
+                ..state.format()
+            });
+        }
+        "dp" | "decimals" | "sf" | "sigfigs" => {
+            let tracks = name.starts_with('s') && input == Some("auto");
+            state.set_tracks_significant_figures(tracks);
+            let digits = match input {
+                Some("off" | "default") => Digits::Default,
+                _ if tracks => Digits::Default,
+                input => {
+                    let count = siffra_try!(
+                        input.unwrap_or_default().parse::<usize>(),
+                        "Directive Error",
+                        if name.starts_with('s') {
+                            format!("#{} takes a number of digits, auto or off", name)
+                        } else {
+                            format!("#{} takes a number of digits, or off", name)
+                        },
+                        Some(span)
+                    );
+                    if name.starts_with('d') {
+                        Digits::DecimalPlaces(count)
+                    } else {
+                        Digits::SignificantFigures(count)
+                    }
+                }
+            };
+            state.set_format(FloatFormat {
+                digits,
+                ..state.format()
+            });
+        }
+        "grouping" => {
+            let grouping = match input {
+                Some("on") => true,
+                Some("off") => false,
+                _ => {
+                    return Err(siffra_error!(
+                        "Directive Error",
+                        "Grouping must be on or off",
+                        Some(span)
+                    ))
+                }
+            };
+            state.set_format(FloatFormat {
+                grouping,
+                ..state.format()
+            });
+        }
+        "imaginary" => {
+            let imaginary_unit = match input {
+                Some("i") => 'i',
+                Some("j") => 'j',
+                _ => {
+                    return Err(siffra_error!(
+                        "Directive Error",
+                        "The imaginary unit must be i or j",
+                        Some(span)
+                    ))
+                }
+            };
+            state.set_format(FloatFormat {
+                imaginary_unit,
+                ..state.format()
+            });
+        }
+        // Programmer mode: bitwise results wrap around into a word of this many bits
+        "word" | "word_size" => {
+            let word_size = match input {
+                Some("off") => None,
+                _ => Some(siffra_try!(
+                    input
+                        .and_then(|bits| bits.parse::<u32>().ok())
+                        .filter(|bits| (1..=1024).contains(bits))
+                        .ok_or(()),
+                    "Directive Error",
+                    "Word size must be a number of bits from 1 to 1024, or off",
+                    Some(span)
+                )),
+            };
+            state.set_format(FloatFormat {
+                word_size,
+                ..state.format()
+            });
+        }
+        "locale" => {
+            let locale = siffra_try!(
+                Locale::from_name(input.unwrap_or_default()).ok_or(()),
+                "Directive Error",
+                format!("Unknown locale '{}'", input.unwrap_or_default()),
+                Some(span)
+            );
+            state.set_locale(locale);
+        }
+        "separators" => {
+            let separators = input
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(separator)
+                .collect::<Option<Vec<_>>>();
+            let Some([group_separator, decimal_separator]) = separators.as_deref() else {
+                return Err(siffra_error!(
+                    "Directive Error",
+                    "#separators takes a group separator and a decimal separator, like `#separators space ,`",
+                    Some(span)
+                ));
+            };
+            state.set_format(FloatFormat {
+                group_separator: *group_separator,
+                decimal_separator: *decimal_separator,
+                ..state.format()
+            });
+        }
+        name => {
+            return Err(siffra_error!(
+                "Name Error",
+                format!("The directive '#{}' is not defined", name),
+                Some(span)
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a separator given to `#separators`, either as itself or by name, like `space`.
+fn separator(input: &str) -> Option<char> {
+    match input {
+        "comma" => Some(','),
+        "period" | "dot" => Some('.'),
+        "space" => Some(' '),
+        "thin_space" => Some('\u{2009}'),
+        "apostrophe" => Some('\''),
+        "underscore" => Some('_'),
+        input => {
+            let mut chars = input.chars();
+            let separator = chars.next()?;
+            chars.next().is_none().then_some(separator)
+        }
+    }
+}
+
+/// Evaluates an expression that must produce a number.
+pub fn evaluate_value(
     expr: &Expression,
     state: &SiffraState,
 ) -> Result<Value, SiffraExecutionError> {
+    expect_value(evaluate_expr(expr, state)?, expr.span())
+}
+
+fn expect_value(object: Object, span: Option<Span>) -> Result<Value, SiffraExecutionError> {
+    match object {
+        Object::Value(value) => Ok(value),
+        object => Err(siffra_error!(
+            "Type Error",
+            format!("Expected a number, found a {}", object.kind()),
+            span
+        )),
+    }
+}
+
+pub fn evaluate_expr(
+    expr: &Expression,
+    state: &SiffraState,
+) -> Result<Object, SiffraExecutionError> {
     match expr.inner() {
-        InnerExpression::Constant(name) => Ok(name.clone()),
-        InnerExpression::Convert(val, dim) => {
-            let val = evaluate_expr(val, state)?;
-            if dim.is_unitless() {
-                return Ok(val.with_units(Dimension::default()));
-            } else if val.dimension().is_unitless() {
-                return Ok(val.with_units(dim.clone()));
-            } else {
-                Ok(siffra_try!(
-                    val.convert(dim).ok_or(()),
-                    "Unit Error",
-                    "Error converting units",
-                    expr.span()
-                ))
+        InnerExpression::Constant(name) => Ok(name.clone().into()),
+        InnerExpression::Reaction(reaction, _) => Ok(Object::Reaction(reaction.clone())),
+        InnerExpression::Convert {
+            value,
+            dimension: dim,
+            conditions,
+            reaction,
+        } => {
+            // Giving conditions, as in `at STP`, marks the compounds being converted as gases
+            let gas = |dimension: &Dimension| match conditions {
+                Some(_) => dimension.as_gas(),
+                None => dimension.clone(),
+            };
+            let convert = |val: Value| {
+                if dim.is_unitless() {
+                    return Ok(val.with_units(Dimension::default()));
+                } else if val.dimension().is_unitless() {
+                    return Ok(val.with_units(dim.clone()));
+                }
+
+                let val = val.with_units(gas(&val.dimension));
+                let target = gas(&dim.inherit_compound(&val.dimension));
+                val.convert_at(&target, &conditions.clone().unwrap_or_default())
+                    .ok_or_else(|| conversion_error(&val.dimension, &target, expr.span()))
+            };
+            // Lists and matrices are converted number by number
+            let val = match evaluate_expr(value, state)? {
+                Object::List(values) if reaction.is_none() => {
+                    return values
+                        .into_iter()
+                        .map(convert)
+                        .collect::<Result<_, _>>()
+                        .map(Object::List);
+                }
+                Object::Matrix(matrix) if reaction.is_none() => {
+                    return matrix
+                        .try_map(|entry| convert(entry.clone()))
+                        .map(Object::Matrix);
+                }
+                object => expect_value(object, value.span())?,
+            };
+            if let Some(reaction) = reaction {
+                return convert_via(
+                    &val.with_units(gas(&val.dimension)),
+                    value.span(),
+                    &gas(dim),
+                    &conditions.clone().unwrap_or_default(),
+                    reaction,
+                    state,
+                )
+                .map(Object::Value);
             }
+            convert(val).map(Object::Value)
+        }
+        InnerExpression::List(elements) => {
+            let objects = elements
+                .iter()
+                .map(|element| evaluate_expr(element, state))
+                .collect::<Result<Vec<_>, _>>()?;
+            // A list of lists is a matrix, as in `[[1, 2], [3, 4]]`
+            if !matches!(objects.first(), Some(Object::List(_))) {
+                return objects
+                    .into_iter()
+                    .zip(elements)
+                    .map(|(object, element)| expect_value(object, element.span()))
+                    .collect::<Result<_, _>>()
+                    .map(Object::List);
+            }
+
+            let rows = objects
+                .into_iter()
+                .zip(elements)
+                .map(|(object, element)| match object {
+                    Object::List(row) => Ok(row),
+                    object => Err(siffra_error!(
+                        "Type Error",
+                        format!("Expected a row of a matrix, found a {}", object.kind()),
+                        element.span()
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Matrix::from_rows(rows)
+                .map(Object::Matrix)
+                .map_err(|e| matrix_error(e, expr.span()))
         }
         InnerExpression::Variable(name) => {
             if let Ok(v) = state.get_variable(name) {
@@ -96,12 +436,11 @@ pub fn evaluate_expr(
                 ))
             } else {
                 match name.as_str() {
-                    "pi" => Ok(Value::new(Float::pi(), None)),
-                    "e" => Ok(Value::new(Float::e(), None)),
-                    "tau" => Ok(Value::new(
-                        Float::pi().mul(&Float::parse("2").unwrap()),
-                        None,
-                    )),
+                    "pi" => Ok(Value::new(Float::pi(), None).into()),
+                    "e" => Ok(Value::new(Float::e(), None).into()),
+                    "tau" => {
+                        Ok(Value::new(Float::pi().mul(&Float::parse("2").unwrap()), None).into())
+                    }
                     "prev" | "ans" | "previous" => {
                         if let Some(prev) = state.previous_value() {
                             Ok(prev.clone())
@@ -115,7 +454,7 @@ pub fn evaluate_expr(
                     }
                     "total" | "sum" => {
                         if let Some(total) = state.block_total() {
-                            Ok(total.clone())
+                            Ok(total.clone().into())
                         } else {
                             Err(siffra_error!(
                                 "Total Error",
@@ -124,194 +463,596 @@ pub fn evaluate_expr(
                             ))
                         }
                     }
-                    "k" | "K" => Ok(Value::new(Float::parse("1000").unwrap(), None)),
-                    _ => Err(siffra_error!(
-                        "Name Error",
-                        format!("Variable '{}' not found", name),
-                        expr.span()
-                    )),
+                    "k" | "K" => Ok(Value::new(Float::parse("1000").unwrap(), None).into()),
+                    // `j` is the imaginary unit instead of `i` after `#imaginary j`
+                    "i" | "j" if name.starts_with(state.format().imaginary_unit) => {
+                        Ok(complex_value(Complex::i(), Dimension::default()).into())
+                    }
+                    _ => match state.compound(name).or(Compound::parse(name).ok()) {
+                        Some(compound) => Ok(Object::Compound(compound)),
+                        None => Err(siffra_error!(
+                            "Name Error",
+                            format!("Variable '{}' not found", name),
+                            expr.span()
+                        )),
+                    },
                 }
             }
         }
         InnerExpression::FunctionCall { name, args } => {
-            let args = args
+            let objects = args
                 .iter()
                 .map(|arg| evaluate_expr(arg, state))
                 .collect::<Result<Vec<_>, _>>()?;
-            match &**name {
-                "factorial" => {
-                    // TODO: Implement gamma function
-                    Err(siffra_error!(
-                        "Not Yet Implemented Error",
-                        "Factorial function not yet implemented",
-                        expr.function_name_span().or(expr.span())
-                    ))
-                }
-                "log" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().log10(), None))
-                    } else if args.len() == 2 {
-                        Ok(Value::new(
-                            &args[1].value.clone().ln() / &args[0].value.clone().ln(),
-                            None,
-                        ))
-                    } else {
-                        Err(siffra_error!(
-                            "Argument Error",
-                            "log function takes either 1 or 2 arguments",
-                            expr.span()
-                        ))
-                    }
-                }
-                "ln" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().ln(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "Argument Error",
-                            "ln function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
-                }
-                "sqrt" => {
-                    if args.len() == 1 {
-                        Ok(siffra_try!(
-                            args[0]
-                                .try_pow(&Value::new(Float::from(0.5), None))
-                                .ok_or(()),
-                            "Root Error",
-                            "Error taking square root",
-                            expr.span()
-                        ))
-                    } else {
-                        Err(siffra_error!(
-                            "Argument Error",
-                            "sqrt function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+
+            if let Some(result) = evaluate_matrix_function(name, args, &objects, expr) {
+                return result;
+            }
+            if let Some(result) = evaluate_list_function(name, args, &objects, expr) {
+                return result;
+            }
+            if let Some(result) = evaluate_statistics_function(name, &objects, expr) {
+                return result;
+            }
+            if let Some(result) = evaluate_chemical_function(name, args, &objects, expr, state) {
+                return result;
+            }
+            if let Some(result) = evaluate_integer_function(name, args, &objects, expr) {
+                return result;
+            }
+
+            let args = objects
+                .into_iter()
+                .zip(args.iter())
+                .map(|(object, arg)| expect_value(object, arg.span()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            propagate_uncertainty(&args, |args| evaluate_function(name, args, expr))
+                .map(Object::Value)
+        }
+        InnerExpression::Multiply(a, b) => {
+            // A quantity of a compound, such as `500 g of water`
+            let operands = match (evaluate_expr(a, state)?, evaluate_expr(b, state)?) {
+                (Object::Value(value), Object::Compound(compound))
+                | (Object::Compound(compound), Object::Value(value)) => {
+                    let dimension = siffra_try!(
+                        value.dimension.of_compound(&compound).ok_or(()),
+                        "Unit Error",
+                        format!("Only a plain mass, amount or volume can be of {}", compound),
+                        expr.span()
+                    );
+                    return Ok(value.with_units(dimension).into());
                 }
-                "sin" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().sin(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "Argument Error",
-                            "sin function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+                operands => operands,
+            };
+            if let Some(result) = matrix::multiply(&operands.0, &operands.1, expr) {
+                return result;
+            }
+
+            element_wise(operands, (a, b), expr, |a, b| {
+                Ok(siffra_try!(
+                    a.try_mul(b).ok_or(()),
+                    "Unit Error",
+                    "Units do not match in multiplication",
+                    expr.span()
+                ))
+            })
+        }
+        InnerExpression::Divide(a, b) => {
+            let operands = (evaluate_expr(a, state)?, evaluate_expr(b, state)?);
+            if let Object::Matrix(_) = operands.1 {
+                return Err(siffra_error!(
+                    "Matrix Error",
+                    "Dividing by a matrix isn't defined, so multiply by its inverse, inv(B)",
+                    b.span()
+                ));
+            }
+            element_wise(operands, (a, b), expr, |a, b| {
+                Ok(siffra_try!(
+                    a.try_div(b).ok_or(()),
+                    "Division Error",
+                    "Division by zero",
+                    expr.span()
+                ))
+            })
+        }
+        InnerExpression::Add(a, b) => {
+            let operands = (evaluate_expr(a, state)?, evaluate_expr(b, state)?);
+            element_wise(operands, (a, b), expr, |a, b| {
+                Ok(siffra_try!(
+                    a.try_add(b).ok_or(()),
+                    "Unit Error",
+                    "Units do not match in addition",
+                    expr.span()
+                ))
+            })
+        }
+        InnerExpression::Subtract(a, b) => {
+            let operands = (evaluate_expr(a, state)?, evaluate_expr(b, state)?);
+            element_wise(operands, (a, b), expr, |a, b| {
+                Ok(siffra_try!(
+                    a.try_sub(b).ok_or(()),
+                    "Unit Error",
+                    "Units do not match in subtraction",
+                    expr.span()
+                ))
+            })
+        }
+        InnerExpression::Exponent(a, b) => {
+            let operands = (evaluate_expr(a, state)?, evaluate_expr(b, state)?);
+            match &operands {
+                (Object::Matrix(matrix), exponent) => {
+                    return matrix::power(matrix, exponent, b.span(), expr);
                 }
-                "cos" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().cos(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "Argument Error",
-                            "cos function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+                (_, Object::Matrix(_)) => {
+                    return Err(siffra_error!(
+                        "Matrix Error",
+                        "A matrix can't be an exponent",
+                        b.span()
+                    ));
                 }
-                "tan" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().tan(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "Argument Error",
-                            "tan function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+                _ => {}
+            }
+            element_wise(operands, (a, b), expr, |a, b| {
+                Ok(siffra_try!(
+                    a.try_pow(b).ok_or(()),
+                    "Unit Error",
+                    "Exponent must be unitless",
+                    expr.span()
+                ))
+            })
+        }
+        InnerExpression::Uncertain(value, uncertainty) => {
+            let val = evaluate_value(value, state)?;
+            let error = evaluate_value(uncertainty, state)?;
+            // The units of `5.0 ± 0.2 m` belong to both numbers
+            let val = if val.is_unitless() {
+                val.with_units(error.dimension())
+            } else {
+                val
+            };
+            let error = if error.is_unitless() {
+                error.value
+            } else {
+                siffra_try!(
+                    error.convert(&val.dimension).ok_or(()),
+                    "Unit Error",
+                    "An uncertainty must have the same units as its value",
+                    uncertainty.span()
+                )
+                .value
+            };
+            Ok(val.with_uncertainty(error.abs()).into())
+        }
+    }
+}
+
+/// Applies an operation to two numbers. Lists and matrices are operated on number by number,
+/// pairing up the numbers of two lists or matrices of the same size, or pairing every number with
+/// a single number.
+fn element_wise(
+    operands: (Object, Object),
+    expressions: (&Expression, &Expression),
+    expr: &Expression,
+    operation: impl Fn(&Value, &Value) -> Result<Value, SiffraExecutionError>,
+) -> Result<Object, SiffraExecutionError> {
+    let (a_span, b_span) = (expressions.0.span(), expressions.1.span());
+    match operands {
+        (Object::Matrix(a), Object::Matrix(b)) => a
+            .try_zip(&b, operation)
+            .map_err(|e| matrix_error(e, expr.span()))?
+            .map(Object::Matrix),
+        (Object::Matrix(_), Object::List(_)) | (Object::List(_), Object::Matrix(_)) => {
+            Err(siffra_error!(
+                "Matrix Error",
+                "A matrix and a list can only be multiplied",
+                expr.span()
+            ))
+        }
+        (Object::Matrix(a), b) => {
+            let b = expect_value(b, b_span)?;
+            a.try_map(|a| operation(a, &b)).map(Object::Matrix)
+        }
+        (a, Object::Matrix(b)) => {
+            let a = expect_value(a, a_span)?;
+            b.try_map(|b| operation(&a, b)).map(Object::Matrix)
+        }
+        (Object::List(a), Object::List(b)) => {
+            if a.len() != b.len() {
+                return Err(siffra_error!(
+                    "List Error",
+                    format!(
+                        "Lists of {} and {} numbers can't be combined",
+                        a.len(),
+                        b.len()
+                    ),
+                    expr.span()
+                ));
+            }
+            a.iter()
+                .zip(&b)
+                .map(|(a, b)| operation(a, b))
+                .collect::<Result<_, _>>()
+                .map(Object::List)
+        }
+        (Object::List(a), b) => {
+            let b = expect_value(b, b_span)?;
+            a.iter()
+                .map(|a| operation(a, &b))
+                .collect::<Result<_, _>>()
+                .map(Object::List)
+        }
+        (a, Object::List(b)) => {
+            let a = expect_value(a, a_span)?;
+            b.iter()
+                .map(|b| operation(&a, b))
+                .collect::<Result<_, _>>()
+                .map(Object::List)
+        }
+        (a, b) => {
+            operation(&expect_value(a, a_span)?, &expect_value(b, b_span)?).map(Object::Value)
+        }
+    }
+}
+
+/// Propagates the uncertainty of the arguments through a function, using the function's slope
+/// at the arguments.
+fn propagate_uncertainty(
+    args: &[Value],
+    function: impl Fn(&[Value]) -> Result<Value, SiffraExecutionError>,
+) -> Result<Value, SiffraExecutionError> {
+    let exact = args
+        .iter()
+        .map(|arg| Value {
+            uncertainty: None,
+            ..arg.clone()
+        })
+        .collect::<Vec<_>>();
+    let result = function(&exact)?;
+    // Complex results don't track uncertainty
+    if result.is_complex() {
+        return Ok(result);
+    }
+
+    let mut sensitivities = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let Some(uncertainty) = &arg.uncertainty else {
+            continue;
+        };
+        // A step much smaller than the uncertainty keeps the slope accurate
+        let step = &uncertainty.standard() * &Float::parse("1e-6").unwrap();
+        if step == Float::from(0) {
+            continue;
+        }
+
+        let shifted = |step: &Float| {
+            let mut args = exact.clone();
+            args[i].value = &args[i].value + step;
+            args[i].exact = None;
+            function(&args)
+        };
+        let rise = &shifted(&step)?.value - &shifted(&-step.clone())?.value;
+        let slope = &rise / &(&step * &Float::from(2));
+        sensitivities.push((Some(uncertainty), slope));
+    }
+
+    Ok(Value {
+        uncertainty: Uncertainty::linear(sensitivities),
+        ..result
+    })
+}
+
+/// Evaluates a built-in numeric function.
+fn evaluate_function(
+    name: &str,
+    args: &[Value],
+    expr: &Expression,
+) -> Result<Value, SiffraExecutionError> {
+    if let Some(result) = evaluate_elementary_function(name, args, expr) {
+        return result;
+    }
+
+    match name {
+        "factorial" | "gamma" => gamma(name, args, expr),
+        "log" => {
+            if args.len() == 1 {
+                if needs_complex(&args[0]) {
+                    let ln_ten = Complex::new(Float::from(10).ln(), Float::from(0));
+                    Ok(complex_value(
+                        args[0].complex().ln().div(&ln_ten),
+                        Dimension::default(),
+                    ))
+                } else {
+                    Ok(Value::new(args[0].value.clone().log10(), None))
                 }
-                "asin" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().asin(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "asin function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+            } else if args.len() == 2 {
+                let base = &args[0];
+                if !base.is_complex()
+                    && (base.value <= Float::from(0) || base.value == Float::from(1))
+                {
+                    return Err(siffra_error!(
+                        "Argument Error",
+                        "The base of a logarithm must be positive and not 1",
+                        expr.span()
+                    ));
                 }
-                "acos" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().acos(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "acos function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+                if needs_complex(&args[0]) || needs_complex(&args[1]) {
+                    Ok(complex_value(
+                        args[1].complex().ln().div(&args[0].complex().ln()),
+                        Dimension::default(),
+                    ))
+                } else {
+                    Ok(Value::new(
+                        &args[1].value.clone().ln() / &args[0].value.clone().ln(),
+                        None,
+                    ))
                 }
-                "atan" => {
-                    if args.len() == 1 {
-                        Ok(Value::new(args[0].value.clone().atan(), None))
-                    } else {
-                        Err(siffra_error!(
-                            "atan function takes exactly 1 argument",
-                            expr.span()
-                        ))
-                    }
+            } else {
+                Err(siffra_error!(
+                    "Argument Error",
+                    "log function takes either 1 or 2 arguments",
+                    expr.span()
+                ))
+            }
+        }
+        "ln" => {
+            if args.len() == 1 {
+                if needs_complex(&args[0]) {
+                    Ok(complex_value(args[0].complex().ln(), Dimension::default()))
+                } else {
+                    Ok(Value::new(args[0].value.clone().ln(), None))
                 }
-
-                name => Err(siffra_error!(
-                    "Name Error",
-                    format!("The function '{}' is not defined", name),
-                    expr.function_name_span().or(expr.span())
-                )),
+            } else {
+                Err(siffra_error!(
+                    "Argument Error",
+                    "ln function takes exactly 1 argument",
+                    expr.span()
+                ))
             }
         }
-        InnerExpression::Multiply(a, b) => {
-            let a = evaluate_expr(a, state)?;
-            let b = evaluate_expr(b, state)?;
-            Ok(siffra_try!(
-                a.try_mul(&b).ok_or(()),
-                "Unit Error",
-                "Units do not match in multiplication",
-                expr.span()
-            ))
+        "sqrt" => {
+            if args.len() == 1 {
+                Ok(siffra_try!(
+                    args[0]
+                        .try_pow(&Value::new(Float::from(0.5), None))
+                        .ok_or(()),
+                    "Root Error",
+                    "Error taking square root",
+                    expr.span()
+                ))
+            } else {
+                Err(siffra_error!(
+                    "Argument Error",
+                    "sqrt function takes exactly 1 argument",
+                    expr.span()
+                ))
+            }
         }
-        InnerExpression::Divide(a, b) => {
-            let a = evaluate_expr(a, state)?;
-            let b = evaluate_expr(b, state)?;
-            Ok(siffra_try!(
-                a.try_div(&b).ok_or(()),
-                "Division Error",
-                "Division by zero",
+        "sin" | "cos" | "tan" => {
+            let [angle] = args else {
+                return Err(siffra_error!(
+                    "Argument Error",
+                    format!("{} function takes exactly 1 argument", name),
+                    expr.span()
+                ));
+            };
+            let angle = siffra_try!(
+                in_radians(angle).ok_or(()),
+                "Argument Error",
+                format!("{} function takes an angle or a number without units", name),
                 expr.span()
-            ))
+            );
+
+            if angle.is_complex() {
+                let angle = angle.complex();
+                let result = match name {
+                    "sin" => angle.sin(),
+                    "cos" => angle.cos(),
+                    _ => angle.tan(),
+                };
+                Ok(complex_value(result, Dimension::default()))
+            } else {
+                let result = match name {
+                    "sin" => angle.value.sin(),
+                    "cos" => angle.value.cos(),
+                    _ => angle.value.tan(),
+                };
+                Ok(Value::new(result, None))
+            }
         }
-        InnerExpression::Add(a, b) => {
-            let a = evaluate_expr(a, state)?;
-            let b = evaluate_expr(b, state)?;
-            Ok(siffra_try!(
-                a.try_add(&b).ok_or(()),
-                "Unit Error",
-                "Units do not match in addition",
+        "asin" | "acos" | "atan" if args.iter().any(Value::is_complex) => Err(siffra_error!(
+            "Argument Error",
+            format!("{} function does not take complex numbers", name),
+            expr.span()
+        )),
+        "asin" | "acos" if args.len() == 1 && args[0].value.abs() > Float::from(1) => {
+            Err(siffra_error!(
+                "Argument Error",
+                format!("{} function takes a number from -1 to 1", name),
                 expr.span()
             ))
         }
-        InnerExpression::Subtract(a, b) => {
-            let a = evaluate_expr(a, state)?;
-            let b = evaluate_expr(b, state)?;
-            Ok(siffra_try!(
-                a.try_sub(&b).ok_or(()),
-                "Unit Error",
-                "Units do not match in subtraction",
+        "asin" => {
+            if args.len() == 1 {
+                Ok(Value::new(args[0].value.clone().asin(), Some(radians())))
+            } else {
+                Err(siffra_error!(
+                    "asin function takes exactly 1 argument",
+                    expr.span()
+                ))
+            }
+        }
+        "acos" => {
+            if args.len() == 1 {
+                Ok(Value::new(args[0].value.clone().acos(), Some(radians())))
+            } else {
+                Err(siffra_error!(
+                    "acos function takes exactly 1 argument",
+                    expr.span()
+                ))
+            }
+        }
+        "atan" => {
+            if args.len() == 1 {
+                Ok(Value::new(args[0].value.clone().atan(), Some(radians())))
+            } else {
+                Err(siffra_error!(
+                    "atan function takes exactly 1 argument",
+                    expr.span()
+                ))
+            }
+        }
+        "re" | "im" | "abs" | "arg" | "conj" if args.len() != 1 => Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 1 argument", name),
+            expr.span()
+        )),
+        "re" => Ok(Value {
+            imaginary: None,
+            ..args[0].clone()
+        }),
+        "im" => Ok(Value::new(args[0].complex().im, Some(args[0].dimension()))),
+        "abs" => {
+            if args[0].is_complex() {
+                Ok(Value::new(
+                    args[0].complex().magnitude(),
+                    Some(args[0].dimension()),
+                ))
+            } else {
+                Ok(Value {
+                    value: args[0].value.abs(),
+                    exact: args[0].exact.as_ref().map(Signed::abs),
+                    ..args[0].clone()
+                })
+            }
+        }
+        "arg" => Ok(Value::new(args[0].complex().argument(), Some(radians()))),
+        "conj" => Ok(args[0].clone().with_complex(args[0].complex().conjugate())),
+        "polar" => {
+            let [magnitude, angle] = args else {
+                return Err(siffra_error!(
+                    "Argument Error",
+                    "polar function takes a magnitude and an angle",
+                    expr.span()
+                ));
+            };
+            let angle = siffra_try!(
+                in_radians(angle)
+                    .map(|angle| angle.value)
+                    .filter(|_| !magnitude.is_complex() && !angle.is_complex())
+                    .ok_or(()),
+                "Argument Error",
+                "polar function takes a real magnitude and an angle",
                 expr.span()
+            );
+            Ok(complex_value(
+                Complex::from_polar(&magnitude.value, &angle),
+                magnitude.dimension(),
             ))
         }
-        InnerExpression::Exponent(a, b) => {
-            let a = evaluate_expr(a, state)?;
-            let b = evaluate_expr(b, state)?;
-            Ok(siffra_try!(
-                a.try_pow(&b).ok_or(()),
-                "Unit Error",
-                "Exponent must be unitless",
+        name => Err(siffra_error!(
+            "Name Error",
+            format!("The function '{}' is not defined", name),
+            expr.function_name_span().or(expr.span())
+        )),
+    }
+}
+
+/// Finds `n!`, or `gamma(x)`, which is `(x - 1)!`. Factorials of whole numbers are exact.
+fn gamma(name: &str, args: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let [arg] = args else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes exactly 1 argument", name),
+            expr.span()
+        ));
+    };
+    if arg.is_complex() || !arg.is_unitless() {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function takes a real number without units", name),
+            expr.span()
+        ));
+    }
+
+    // The factorial of `n` is `gamma(n + 1)`
+    let offset = if name == "factorial" { 1 } else { 0 };
+    if let Some(n) = arg.as_integer().map(|n| n + BigInt::from(offset - 1)) {
+        if n.is_negative() {
+            return Err(siffra_error!(
+                "Argument Error",
+                if name == "factorial" {
+                    "Factorials of negative whole numbers are undefined"
+                } else {
+                    "gamma function is undefined at zero and negative whole numbers"
+                },
                 expr.span()
-            ))
+            ));
         }
+        if let Some(n) = n.to_u64().filter(|n| *n <= MAX_EXACT_FACTORIAL) {
+            let factorial = (1..=n).map(BigInt::from).product();
+            return Ok(Value::from(Rational::from_integer(factorial)));
+        }
+    }
+
+    let result = (&arg.value + &Float::from(offset)).gamma();
+    if !result.is_finite() {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{}({}) is too large to calculate", name, arg.value),
+            expr.span()
+        ));
+    }
+    Ok(Value::new(result, None))
+}
+
+/// Checks that no argument is complex.
+pub(super) fn real_args<'a>(
+    name: &str,
+    args: impl IntoIterator<Item = &'a Value>,
+    expr: &Expression,
+) -> Result<(), SiffraExecutionError> {
+    if args.into_iter().any(Value::is_complex) {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function does not take complex numbers", name),
+            expr.span()
+        ));
     }
+    Ok(())
+}
+
+/// Whether `name` is a constant or another name that has a value without being defined, such as
+/// `pi` or `ans`.
+pub(super) fn is_built_in_name(name: &str, state: &SiffraState) -> bool {
+    match name {
+        "pi" | "e" | "tau" | "prev" | "ans" | "previous" | "total" | "sum" | "k" | "K" => true,
+        "i" | "j" => name.starts_with(state.format().imaginary_unit),
+        _ => false,
+    }
+}
+
+/// Whether a function of the value needs complex numbers, as the logarithm of a negative number
+/// does.
+fn needs_complex(value: &Value) -> bool {
+    value.is_complex() || value.value < Float::from(0)
+}
+
+/// A complex result of a function, which is real if its imaginary part is zero.
+fn complex_value(complex: Complex, dimension: Dimension) -> Value {
+    Value::new(Float::from(0), Some(dimension)).with_complex(complex)
+}
+
+/// Converts an angle to radians. Angles without units are already in radians.
+fn in_radians(angle: &Value) -> Option<Value> {
+    if angle.is_unitless() {
+        Some(angle.clone())
+    } else {
+        angle.convert(&radians())
+    }
+}
+
+/// The dimension of an angle in radians.
+fn radians() -> Dimension {
+    Dimension(vec![(Quantity::Angle(Angle::Radian), Float::from(1))])
 }
@@ -1,16 +1,24 @@
 use crate::error::SiffraExecutionError;
-use crate::representations::Value;
+use crate::representations::{
+    normalize_name, Compound, Digits, FloatContext, FloatFormat, FormatChange, Locale, Object,
+    Rounding, Value,
+};
 use std::collections::BTreeMap;
 
 pub enum VariableValue {
     Error(SiffraExecutionError),
-    Value(Value),
+    Value(Object),
 }
 
 pub struct SiffraState {
     variables: BTreeMap<String, VariableValue>,
-    previous_value: Option<Value>,
+    previous_value: Option<Object>,
     block_total: Option<Value>,
+    compounds: BTreeMap<String, Compound>,
+    float_context: FloatContext,
+    line_format: Option<FloatFormat>,
+    locale: Locale,
+    tracks_significant_figures: bool,
 }
 
 pub enum VariableAccessError {
@@ -24,10 +32,15 @@ impl SiffraState {
             variables: BTreeMap::new(),
             previous_value: None,
             block_total: Some(Value::from(0.0)),
+            compounds: BTreeMap::new(),
+            float_context: FloatContext::default(),
+            line_format: None,
+            locale: Locale::default(),
+            tracks_significant_figures: false,
         }
     }
 
-    pub fn get_variable(&self, name: &str) -> Result<&Value, VariableAccessError> {
+    pub fn get_variable(&self, name: &str) -> Result<&Object, VariableAccessError> {
         match self.variables.get(name) {
             Some(VariableValue::Value(value)) => Ok(value),
             Some(VariableValue::Error(err)) => Err(VariableAccessError::Error(err.clone())),
@@ -35,7 +48,7 @@ impl SiffraState {
         }
     }
 
-    pub fn set_variable(&mut self, name: &str, value: Value) {
+    pub fn set_variable(&mut self, name: &str, value: Object) {
         self.variables
             .insert(name.to_string(), VariableValue::Value(value));
     }
@@ -45,11 +58,108 @@ impl SiffraState {
             .insert(name.to_string(), VariableValue::Error(error));
     }
 
-    pub fn set_previous_value(&mut self, value: Value) {
+    /// Names a compound for the rest of the document, as in `compound aspirin = C9H8O4`.
+    pub fn define_compound(&mut self, name: &str, compound: Compound) {
+        let name = normalize_name(name);
+        self.compounds
+            .insert(name.clone(), compound.with_name(&name));
+    }
+
+    /// Looks up a compound by a name defined in the document or a common name.
+    pub fn compound(&self, name: &str) -> Option<Compound> {
+        self.compounds
+            .get(&normalize_name(name))
+            .cloned()
+            .or_else(|| Compound::from_name(name))
+    }
+
+    /// Returns the precision and rounding numbers in the document are calculated with, and the
+    /// format the last line's result is shown in.
+    pub fn float_context(&self) -> FloatContext {
+        FloatContext {
+            format: self.line_format.unwrap_or(self.float_context.format),
+            ..self.float_context
+        }
+    }
+
+    /// Returns the format results are shown in, unless a line changes it.
+    pub fn format(&self) -> FloatFormat {
+        self.float_context.format
+    }
+
+    /// Sets the format results are shown in for the rest of the document.
+    pub fn set_format(&mut self, format: FloatFormat) {
+        self.float_context.format = format;
+    }
+
+    /// Returns the locale numbers in the document are typed and shown in.
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    /// Sets the locale for the rest of the document, as in `#locale de`, including the
+    /// separators results are shown with.
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+        self.set_format(FloatFormat {
+            group_separator: locale.group_separator,
+            decimal_separator: locale.decimal_separator,
+            ..self.format()
+        });
+    }
+
+    /// Changes the format of the current line's result only, as in `to 3 sf`.
+    pub fn change_line_format(&mut self, changes: &[FormatChange]) {
+        let format = changes
+            .iter()
+            .fold(self.format(), |format, change| format.with_change(*change));
+        self.line_format = Some(format);
+    }
+
+    /// Shows results with as many significant figures as their inputs were measured to, as with
+    /// `#sf auto`.
+    pub fn set_tracks_significant_figures(&mut self, tracks: bool) {
+        self.tracks_significant_figures = tracks;
+    }
+
+    /// Rounds the current line's result to its significant figures, unless the line asks for
+    /// other digits.
+    pub fn show_significant_figures(&mut self, value: &Object) {
+        let Object::Value(value) = value else {
+            return;
+        };
+        let format = self.float_context().format;
+        if let (true, Digits::Default, Some(figures)) = (
+            self.tracks_significant_figures,
+            format.digits,
+            value.significant_figures,
+        ) {
+            self.line_format = Some(FloatFormat {
+                digits: Digits::SignificantFigures(figures.count),
+                ..format
+            });
+        }
+    }
+
+    pub fn clear_line_format(&mut self) {
+        self.line_format = None;
+    }
+
+    /// Sets the precision, in bits, for the rest of the document, as in `#precision 512`.
+    pub fn set_precision(&mut self, precision: usize) {
+        self.float_context.precision = precision;
+    }
+
+    /// Sets the rounding for the rest of the document, as in `#rounding down`.
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.float_context.rounding = rounding;
+    }
+
+    pub fn set_previous_value(&mut self, value: Object) {
         self.previous_value = Some(value);
     }
 
-    pub fn previous_value(&self) -> Option<&Value> {
+    pub fn previous_value(&self) -> Option<&Object> {
         self.previous_value.as_ref()
     }
 
@@ -61,7 +171,11 @@ impl SiffraState {
         &self.block_total
     }
 
-    pub fn add_to_block_total(&mut self, value: &Value) {
+    pub fn add_to_block_total(&mut self, value: &Object) {
+        let Object::Value(value) = value else {
+            return;
+        };
+
         if let Some(total) = self.block_total.take() {
             if total == Value::from(0.0) {
                 self.block_total = Some(value.clone());
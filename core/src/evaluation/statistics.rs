@@ -0,0 +1,311 @@
+use super::elementary::same_units;
+use super::list::{numbers, sorted};
+use crate::error::SiffraExecutionError;
+use crate::representations::{Expression, Float, Object, Rational, Value};
+use crate::{siffra_error, siffra_try};
+use num_bigint::BigInt;
+
+/// Evaluates statistics of a list, or of any number of numbers, as in `mean(3 m, 4 m, 500 cm)`.
+/// Results are in the units of the first number. Returns `None` if `name` is not one of them.
+pub fn evaluate_statistics_function(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Option<Result<Object, SiffraExecutionError>> {
+    match name {
+        "mean" | "average" | "avg" => Some(mean(name, objects, expr)),
+        "median" => Some(median(objects, expr)),
+        "mode" => Some(mode(objects, expr)),
+        "min" | "max" => Some(extreme(name, objects, expr)),
+        "variance" | "var" | "pvariance" | "pvar" | "stddev" | "std" | "stdev" | "pstddev"
+        | "pstd" | "pstdev" => Some(spread(name, objects, expr)),
+        "percentile" => Some(percentile(objects, expr)),
+        "weighted_mean" | "wmean" => Some(weighted_mean(name, objects, expr)),
+        _ => None,
+    }
+}
+
+fn mean(name: &str, objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let values = sample(name, objects, expr, 1)?;
+    Ok(average(name, &values, expr)?.into())
+}
+
+/// Finds the middle number, or the mean of the two middle numbers.
+fn median(objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let values = sorted("median", &sample("median", objects, expr, 1)?, expr)?;
+    let middle = values.len() / 2;
+    if values.len() % 2 == 1 {
+        Ok(values[middle].clone().into())
+    } else {
+        Ok(average("median", &values[middle - 1..=middle], expr)?.into())
+    }
+}
+
+/// Finds the most common number, or a list of the most common numbers if there is a tie.
+fn mode(objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let values = sorted("mode", &sample("mode", objects, expr, 1)?, expr)?;
+
+    // Equal numbers are next to each other once sorted
+    let mut runs: Vec<(&Value, usize)> = Vec::new();
+    for value in &values {
+        match runs.last_mut() {
+            Some((first, count)) if first.value == value.value => *count += 1,
+            _ => runs.push((value, 1)),
+        }
+    }
+    let most = runs.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let mut modes = runs
+        .into_iter()
+        .filter(|(_, count)| *count == most)
+        .map(|(value, _)| value.clone())
+        .collect::<Vec<_>>();
+
+    if modes.len() == 1 {
+        Ok(modes.remove(0).into())
+    } else {
+        Ok(Object::List(modes))
+    }
+}
+
+/// Finds the smallest or largest number.
+fn extreme(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let values = sorted(name, &sample(name, objects, expr, 1)?, expr)?;
+    let extreme = match name {
+        "min" => values.first(),
+        _ => values.last(),
+    };
+    Ok(extreme.unwrap().clone().into())
+}
+
+/// Finds the variance or standard deviation, of a sample or, for functions starting with `p`, of
+/// a whole population. The standard deviation has the units of the numbers, and the variance
+/// those units squared.
+fn spread(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let population = name.starts_with('p');
+    let values = sample(name, objects, expr, if population { 1 } else { 2 })?;
+
+    let count = Float::from(values.len() as u64);
+    let mean = &values
+        .iter()
+        .fold(Float::from(0), |sum, value| &sum + &value.value)
+        / &count;
+    let squares = values.iter().fold(Float::from(0), |sum, value| {
+        let deviation = &value.value - &mean;
+        &sum + &(&deviation * &deviation)
+    });
+    let degrees_of_freedom = if population {
+        count
+    } else {
+        &count - &Float::from(1)
+    };
+    let variance = &squares / &degrees_of_freedom;
+
+    let dimension = &values[0].dimension;
+    if name.contains("var") {
+        Ok(Value::new(variance, Some(dimension.pow(&Float::from(2)))).into())
+    } else {
+        Ok(Value::new(variance.sqrt(), Some(dimension.clone())).into())
+    }
+}
+
+/// Finds `percentile(list, p)`, the number `p` percent of the way through the sorted numbers,
+/// interpolating between the numbers either side of it.
+fn percentile(objects: &[Object], expr: &Expression) -> Result<Object, SiffraExecutionError> {
+    let Some((Object::Value(percent), objects)) = objects.split_last() else {
+        return Err(siffra_error!(
+            "Argument Error",
+            "percentile function takes a list and a percentage from 0 to 100",
+            expr.span()
+        ));
+    };
+    let percent = siffra_try!(
+        Some(&percent.value)
+            .filter(|value| {
+                !percent.is_complex()
+                    && percent.is_unitless()
+                    && **value >= Float::from(0)
+                    && **value <= Float::from(100)
+            })
+            .ok_or(()),
+        "Argument Error",
+        "A percentile must be from 0 to 100",
+        expr.span()
+    );
+    let values = sorted("percentile", &sample("percentile", objects, expr, 1)?, expr)?;
+
+    // The position in the sorted numbers, counting from 0
+    let position = &(percent * &Float::from(values.len() as u64 - 1)) / &Float::from(100);
+    let lower = position.floor();
+    let fraction = &position - &lower;
+    let index = lower.to_f64() as usize;
+    let Some(upper) = values.get(index + 1) else {
+        return Ok(values[index].clone().into());
+    };
+
+    let lower = &values[index];
+    let value = &lower.value + &(&fraction * &(&upper.value - &lower.value));
+    Ok(Value::new(value, Some(lower.dimension.clone())).into())
+}
+
+/// Finds `weighted_mean(values, weights)`, the sum of each value times its weight over the sum
+/// of the weights.
+fn weighted_mean(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+) -> Result<Object, SiffraExecutionError> {
+    let [Object::List(values), Object::List(weights)] = objects else {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!(
+                "{} function takes a list of values and a list of weights",
+                name
+            ),
+            expr.span()
+        ));
+    };
+    if values.len() != weights.len() || values.is_empty() {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!("{} function needs one weight for each value", name),
+            expr.span()
+        ));
+    }
+    let values = same_units(name, values, expr)?;
+    let weights = same_units(name, weights, expr)?;
+
+    let products: Option<Vec<Value>> = values
+        .iter()
+        .zip(&weights)
+        .map(|(value, weight)| value.try_mul(weight))
+        .collect();
+    let (total, total_weight) = siffra_try!(
+        products
+            .and_then(|products| sum(&products))
+            .zip(sum(&weights))
+            .ok_or(()),
+        "Unit Error",
+        format!("Units do not match in {}", name),
+        expr.span()
+    );
+
+    if total_weight.value == Float::from(0) {
+        return Err(siffra_error!(
+            "Division Error",
+            "The weights add up to zero",
+            expr.span()
+        ));
+    }
+    Ok(siffra_try!(
+        total.try_div(&total_weight).ok_or(()),
+        "Unit Error",
+        format!("Units do not match in {}", name),
+        expr.span()
+    )
+    .into())
+}
+
+/// The numbers of a list or the arguments, converted to the units of the first, of which there
+/// must be at least `least`.
+fn sample(
+    name: &str,
+    objects: &[Object],
+    expr: &Expression,
+    least: usize,
+) -> Result<Vec<Value>, SiffraExecutionError> {
+    let values = same_units(name, &numbers(name, objects, expr)?, expr)?;
+    if values.len() < least {
+        return Err(siffra_error!(
+            "Argument Error",
+            format!(
+                "{} function takes at least {} number{}",
+                name,
+                least,
+                if least == 1 { "" } else { "s" }
+            ),
+            expr.span()
+        ));
+    }
+    Ok(values)
+}
+
+/// The mean of numbers in the same units, which is exact if they are.
+fn average(name: &str, values: &[Value], expr: &Expression) -> Result<Value, SiffraExecutionError> {
+    let count = Value::from(Rational::from_integer(BigInt::from(values.len())));
+    Ok(siffra_try!(
+        sum(values).and_then(|sum| sum.try_div(&count)).ok_or(()),
+        "Unit Error",
+        format!("Units do not match in {}", name),
+        expr.span()
+    ))
+}
+
+/// The sum of numbers, or `None` if their units do not match.
+fn sum(values: &[Value]) -> Option<Value> {
+    values[1..]
+        .iter()
+        .try_fold(values[0].clone(), |sum, value| sum.try_add(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::representations::{Dimension, Length, Quantity, Time};
+
+    fn list(numbers: &[i64]) -> Object {
+        Object::List(
+            numbers
+                .iter()
+                .map(|n| Value::from(Rational::from_integer(BigInt::from(*n))))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_statistics() {
+        let expr = Expression::constant(Value::from(0.0));
+        let data = [list(&[2, 4, 4, 4, 5, 5, 7, 9])];
+
+        assert_eq!(mean("mean", &data, &expr).unwrap(), Value::from(5.0).into());
+        assert_eq!(median(&data, &expr).unwrap(), Value::from(4.5).into());
+        assert_eq!(mode(&data, &expr).unwrap(), Value::from(4.0).into());
+        assert_eq!(
+            spread("pstddev", &data, &expr).unwrap(),
+            Value::from(2.0).into()
+        );
+        assert_eq!(mode(&[list(&[1, 2, 2, 1])], &expr).unwrap(), list(&[1, 2]));
+    }
+
+    #[test]
+    fn weighted_mean_needs_matching_units() {
+        let expr = Expression::constant(Value::from(0.0));
+        let with_unit = |n: i64, quantity: Quantity| {
+            Value::new(
+                Float::from(n),
+                Some(Dimension(vec![(quantity, Float::from(1))])),
+            )
+        };
+        let meters = |n| with_unit(n, Quantity::Length(Length::Meter));
+
+        let values = Object::List(vec![meters(1), with_unit(2, Quantity::Time(Time::Second))]);
+        let error = weighted_mean("weighted_mean", &[values, list(&[1, 1])], &expr).unwrap_err();
+        assert_eq!(error.message(), "Unit Error");
+
+        let values = Object::List(vec![meters(1), meters(3)]);
+        let Object::Value(mean) =
+            weighted_mean("weighted_mean", &[values, list(&[1, 1])], &expr).unwrap()
+        else {
+            panic!("expected a value");
+        };
+        assert_eq!(mean.value, Float::from(2));
+        assert_eq!(mean.dimension, meters(1).dimension);
+    }
+}
@@ -3,6 +3,7 @@ mod span;
 
 pub use span::Span;
 
+use crate::representations::{parse_radix, Digits, FormatChange};
 use lazy_static;
 use pest::iterators::{Pair, Pairs};
 use representation::*;
@@ -21,15 +22,25 @@ lazy_static::lazy_static! {
 
         // Precedence is defined lowest to highest
         PrattParser::new()
+            // Bitwise operators bind loosest, as in C, so `1 << 4 - 1` shifts by 3
+            .op(Op::infix(bit_or, Left))
+            .op(Op::infix(bit_xor, Left))
+            .op(Op::infix(bit_and, Left))
+            .op(Op::infix(shift_left, Left) | Op::infix(shift_right, Left))
             // Addition and subtract have equal precedence
             .op(Op::infix(add, Left) | Op::infix(subtract, Left))
             .op(Op::infix(multiply, Left)
                 | Op::infix(divide, Left)
-                | Op::prefix(negative)
+                | Op::infix(modulo, Left)
                 | Op::postfix(convert)
+                | Op::postfix(display_format)
                 | Op::postfix(percent))
+            // Negation binds tighter than `mod`, so `-17 mod 5` is `mod(-17, 5)`, but looser
+            // than powers, so `-2^2` is -4
+            .op(Op::prefix(negative))
+            .op(Op::infix(plus_minus, Left))
             .op(Op::infix(exponent, Right))
-            .op(Op::postfix(factorial))
+            .op(Op::postfix(factorial) | Op::postfix(index))
     };
 }
 
@@ -80,6 +91,7 @@ pub fn parse_unit_expr(pair: Pair<Rule>) -> ParsedDimension {
                         ParsedUnit {
                             name,
                             chemical: Some(chemical),
+                            compound: None,
                             span: unit.as_span().into(),
                         },
                         power,
@@ -92,6 +104,7 @@ pub fn parse_unit_expr(pair: Pair<Rule>) -> ParsedDimension {
                         ParsedUnit {
                             name,
                             chemical: None,
+                            compound: None,
                             span: unit.as_span().into(),
                         },
                         power,
@@ -109,12 +122,38 @@ pub fn parse_unit_expr(pair: Pair<Rule>) -> ParsedDimension {
         parse_mul_group(denominator, &mut units.denominator);
     }
 
-    if units.numerator.is_empty() && units.denominator.is_empty() {
+    if units.numerator.is_empty() {
         if let Some(mul_group) = pairs
             .clone()
             .find(|pair| pair.as_rule() == Rule::unit_mul_group)
         {
             parse_mul_group(mul_group, &mut units.numerator);
+        } else if let Some(unit) = pairs
+            .clone()
+            .find(|pair| pair.as_rule() == Rule::concentration_expr)
+        {
+            // The compound is of the solute, and the volume is of the solution
+            let inner = unit.clone().into_inner();
+            let tagged = |tag| inner.clone().find_first_tagged(tag).unwrap();
+            let volume = tagged("volume");
+            units.numerator.push((
+                ParsedUnit {
+                    name: tagged("name").as_str().to_string(),
+                    chemical: Some(tagged("chemical").as_str().to_string()),
+                    compound: None,
+                    span: unit.as_span().into(),
+                },
+                1,
+            ));
+            units.denominator.push((
+                ParsedUnit {
+                    name: volume.as_str().to_string(),
+                    chemical: None,
+                    compound: None,
+                    span: volume.as_span().into(),
+                },
+                1,
+            ));
         } else if let Some(unit) = pairs.find(|pair| pair.as_rule() == Rule::ungrouped_unit_atom) {
             if unit
                 .clone()
@@ -142,6 +181,7 @@ pub fn parse_unit_expr(pair: Pair<Rule>) -> ParsedDimension {
                     ParsedUnit {
                         name,
                         chemical: Some(chemical),
+                        compound: None,
                         span: unit.as_span().into(),
                     },
                     1,
@@ -154,6 +194,7 @@ pub fn parse_unit_expr(pair: Pair<Rule>) -> ParsedDimension {
                     ParsedUnit {
                         name,
                         chemical: None,
+                        compound: None,
                         span: unit.as_span().into(),
                     },
                     1,
@@ -165,6 +206,43 @@ pub fn parse_unit_expr(pair: Pair<Rule>) -> ParsedDimension {
     units
 }
 
+pub fn parse_gas_conditions(pair: Pair<Rule>) -> ParsedGasConditions {
+    let inner = pair.into_inner();
+
+    if let Some(standard) = inner
+        .clone()
+        .find(|pair| pair.as_rule() == Rule::standard_conditions)
+    {
+        return match standard.as_str() {
+            "SATP" => ParsedGasConditions::Ambient,
+            _ => ParsedGasConditions::Standard,
+        };
+    }
+
+    ParsedGasConditions::Custom(
+        inner
+            .filter(|pair| pair.as_rule() == Rule::gas_condition)
+            .map(|pair| {
+                let span = pair.as_span().into();
+                let inner = pair.into_inner();
+                ParsedGasCondition {
+                    value: inner
+                        .find_first_tagged("value")
+                        .unwrap()
+                        .as_str()
+                        .to_string(),
+                    unit: inner
+                        .find_first_tagged("unit")
+                        .unwrap()
+                        .as_str()
+                        .to_string(),
+                    span,
+                }
+            })
+            .collect(),
+    )
+}
+
 pub fn parse_expr(pairs: Pairs<Rule>) -> ParsedExpr {
     PRATT_PARSER
         .map_primary(|primary| match primary.as_rule() {
@@ -190,7 +268,56 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> ParsedExpr {
                     .map(|pair| parse_unit_expr(pair)),
                 span: primary.as_span().into(),
             },
-            Rule::variable => ParsedExpr::Variable {
+            Rule::list | Rule::dimensional_list => {
+                let span = primary.as_span().into();
+                let (list, units) = match primary.as_rule() {
+                    Rule::list => (primary, None),
+                    _ => {
+                        let mut inner = primary.into_inner();
+                        (inner.next().unwrap(), inner.next().map(parse_unit_expr))
+                    }
+                };
+                ParsedExpr::List {
+                    elements: list
+                        .into_inner()
+                        .map(|pair| parse_expr(Pairs::single(pair)))
+                        .collect(),
+                    units,
+                    span,
+                }
+            }
+            Rule::reaction => {
+                let mut sides = primary
+                    .clone()
+                    .into_inner()
+                    .filter(|pair| pair.as_rule() == Rule::reaction_side)
+                    .map(|side| {
+                        side.into_inner()
+                            .map(|species| {
+                                let span = species.as_span().into();
+                                let inner = species.into_inner();
+                                ParsedSpecies {
+                                    coefficient: inner
+                                        .find_first_tagged("coefficient")
+                                        .map(|pair| pair.as_str().to_string()),
+                                    chemical: inner
+                                        .find_first_tagged("chemical")
+                                        .unwrap()
+                                        .as_str()
+                                        .to_string(),
+                                    span,
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    });
+                ParsedExpr::Reaction {
+                    reactants: sides.next().unwrap(),
+                    products: sides.next().unwrap(),
+                    span: primary.as_span().into(),
+                }
+            }
+            // Compounds are looked up like variables so a variable with the same name wins
+            Rule::variable | Rule::compound_literal => ParsedExpr::Variable {
                 name: primary.as_str().to_string(),
                 span: primary.as_span().into(),
             },
@@ -236,6 +363,23 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> ParsedExpr {
                     function_span: name_span.into(),
                 }
             }
+            Rule::dimensional_paren => {
+                // `(3 + 4i) Ω` is the expression times one of the units
+                let span = primary.as_span().into();
+                let mut inner = primary.into_inner();
+                let expr = inner.next().unwrap().into_inner();
+                let units = inner.next().unwrap();
+                ParsedExpr::BinOp {
+                    lhs: Box::new(parse_expr(expr)),
+                    op: Op::Multiply,
+                    rhs: Box::new(ParsedExpr::Number {
+                        value: "1".to_string(),
+                        span: units.as_span().into(),
+                        units: Some(parse_unit_expr(units)),
+                    }),
+                    span,
+                }
+            }
             Rule::grouped_mul_atom => {
                 let primary_span = primary.as_span();
                 let inner = primary.into_inner();
@@ -261,7 +405,14 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> ParsedExpr {
                 Rule::subtract => Op::Subtract,
                 Rule::multiply => Op::Multiply,
                 Rule::divide => Op::Divide,
+                Rule::modulo => Op::Modulo,
                 Rule::exponent => Op::Exponent,
+                Rule::plus_minus => Op::PlusMinus,
+                Rule::bit_and => Op::BitAnd,
+                Rule::bit_or => Op::BitOr,
+                Rule::bit_xor => Op::BitXor,
+                Rule::shift_left => Op::ShiftLeft,
+                Rule::shift_right => Op::ShiftRight,
                 rule => unreachable!("Expr::parse expected infix operation, found {:?}", rule),
             };
             ParsedExpr::BinOp {
@@ -276,12 +427,27 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> ParsedExpr {
             let op = match op_pairs.as_rule() {
                 Rule::factorial => OpPost::Factorial,
                 Rule::percent => OpPost::Percent,
-                Rule::convert => OpPost::Convert(parse_unit_expr(
-                    op_pairs
-                        .into_inner()
-                        .find(|pair| pair.as_rule() == Rule::units_expr)
-                        .unwrap(),
-                )),
+                Rule::convert => {
+                    let inner = op_pairs.into_inner();
+                    OpPost::Convert {
+                        dimension: parse_unit_expr(
+                            inner
+                                .clone()
+                                .find(|pair| pair.as_rule() == Rule::units_expr)
+                                .unwrap(),
+                        ),
+                        conditions: inner
+                            .clone()
+                            .find(|pair| pair.as_rule() == Rule::gas_conditions)
+                            .map(parse_gas_conditions),
+                        reaction: inner
+                            .clone()
+                            .find(|pair| pair.as_rule() == Rule::via_reaction)
+                            .map(|pair| Box::new(parse_expr(pair.into_inner()))),
+                    }
+                }
+                Rule::display_format => OpPost::Format(parse_format_change(op_pairs)),
+                Rule::index => OpPost::Index(Box::new(parse_expr(op_pairs.into_inner()))),
                 rule => unreachable!("Expr::parse expected postfix operation, found {:?}", rule),
             };
             ParsedExpr::UnOpPost {
@@ -304,7 +470,69 @@ pub fn parse_expr(pairs: Pairs<Rule>) -> ParsedExpr {
         .parse(pairs)
 }
 
+pub fn parse_format_change(pair: Pair<Rule>) -> FormatChange {
+    let inner = pair.into_inner();
+
+    if let Some(notation) = inner.find_first_tagged("notation") {
+        return FormatChange::Notation(notation.as_str().parse().unwrap());
+    }
+    if let Some(radix) = inner.find_first_tagged("radix") {
+        return FormatChange::Radix(parse_radix(radix.as_str()).unwrap());
+    }
+
+    let digits = inner
+        .find_first_tagged("digits")
+        .unwrap()
+        .as_str()
+        .parse()
+        .unwrap_or(usize::MAX);
+    let kind = inner.find_first_tagged("kind").unwrap().as_str();
+
+    if kind.starts_with('s') {
+        FormatChange::Digits(Digits::SignificantFigures(digits))
+    } else {
+        FormatChange::Digits(Digits::DecimalPlaces(digits))
+    }
+}
+
 pub fn parse_line(mut pairs: Pairs<Rule>) -> ParsedLine {
+    if let Some(pair) = pairs
+        .clone()
+        .find(|pair| pair.as_rule() == Rule::compound_definition)
+    {
+        let span = pair.as_span().into();
+        let inner = pair.into_inner();
+        return ParsedLine::Compound {
+            name: inner
+                .find_first_tagged("name")
+                .unwrap()
+                .as_str()
+                .to_string(),
+            formula: inner
+                .find_first_tagged("chemical")
+                .unwrap()
+                .as_str()
+                .to_string(),
+            span,
+        };
+    }
+
+    if let Some(pair) = pairs.clone().find(|pair| pair.as_rule() == Rule::r#macro) {
+        let span = pair.as_span().into();
+        let inner = pair.into_inner();
+        return ParsedLine::Macro {
+            name: inner
+                .find_first_tagged("name")
+                .unwrap()
+                .as_str()
+                .to_string(),
+            input: inner
+                .find_first_tagged("input")
+                .map(|input| input.as_str().trim().to_string()),
+            span,
+        };
+    }
+
     if let Some(pair) = pairs
         .clone()
         .find(|pair| pair.as_rule() == Rule::variable_constraint)
@@ -334,6 +562,7 @@ pub fn parse_line(mut pairs: Pairs<Rule>) -> ParsedLine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::representations::Notation;
     use pest::Parser;
 
     #[test]
@@ -342,23 +571,227 @@ mod tests {
         let _expr = parse_expr(SiffraParser::parse(Rule::expr, "a times 2% of 3").unwrap());
     }
 
+    #[test]
+    fn test_parse_number_grouping() {
+        let number = |input| SiffraParser::parse(Rule::number, input).unwrap().as_str();
+        assert_eq!(number("1,234,567.5"), "1,234,567.5");
+        assert_eq!(number("1_000"), "1_000");
+        assert_eq!(number("3,5"), "3");
+    }
+
+    #[test]
+    fn test_parse_imaginary_number() {
+        let number = |input| SiffraParser::parse(Rule::number, input).unwrap().as_str();
+        assert_eq!(number("4i"), "4i");
+        assert_eq!(number("2.5j"), "2.5j");
+        // Units that start with the letter are not imaginary, as in `2 in`
+        assert_eq!(number("2in"), "2");
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "[1, 2, 3] m").unwrap());
+        let ParsedExpr::List {
+            elements, units, ..
+        } = expr
+        else {
+            panic!("Expected list");
+        };
+        assert_eq!(elements.len(), 3);
+        assert!(units.is_some());
+
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "x[2] + 1").unwrap());
+        let ParsedExpr::BinOp { lhs, .. } = expr else {
+            panic!("Expected addition");
+        };
+        assert!(matches!(
+            *lhs,
+            ParsedExpr::UnOpPost {
+                op: OpPost::Index(_),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_radix_number() {
+        let number = |input| SiffraParser::parse(Rule::number, input).unwrap().as_str();
+        assert_eq!(number("0xFF_FF"), "0xFF_FF");
+        assert_eq!(number("0b1010"), "0b1010");
+        // Digits outside the base end the literal, so this is a zero followed by `b12`
+        assert_eq!(number("0b12"), "0");
+    }
+
     #[test]
     fn test_parse_line() {
         let line = parse_line(SiffraParser::parse(Rule::line, "x = 5").unwrap());
         assert!(matches!(line, ParsedLine::Variable(_, _)));
         let line = parse_line(SiffraParser::parse(Rule::line, "log2(5(x)(y)) as mol %").unwrap());
         assert!(matches!(line, ParsedLine::Expression(_)));
+        let line =
+            parse_line(SiffraParser::parse(Rule::line, "compound aspirin = C9H8O4").unwrap());
+        assert!(matches!(line, ParsedLine::Compound { .. }));
+        let line = parse_line(SiffraParser::parse(Rule::line, "#precision 512").unwrap());
+        assert_eq!(
+            line,
+            ParsedLine::Macro {
+                name: "precision".to_string(),
+                input: Some("512".to_string()),
+                span: Span::new(0, 14),
+            }
+        );
         let line = parse_line(SiffraParser::parse(Rule::line, "// This is a comment").unwrap());
         assert!(matches!(line, ParsedLine::Comment));
         let line = parse_line(SiffraParser::parse(Rule::line, "/* This is a comment */").unwrap());
         assert!(matches!(line, ParsedLine::Comment));
     }
 
+    #[test]
+    fn test_parse_word_operators() {
+        for input in [
+            "x plus 3", "x mod 3", "10 mod 3", "x and y", "x or 3", "1 << 4",
+        ] {
+            let expr = parse_expr(SiffraParser::parse(Rule::expr, input).unwrap());
+            assert!(matches!(expr, ParsedExpr::BinOp { .. }), "{}", input);
+        }
+    }
+
     #[test]
     fn test_ungrouped_functions() {
         let _expr = parse_expr(SiffraParser::parse(Rule::expr, "log 5a").unwrap());
     }
 
+    #[test]
+    fn test_parse_reaction() {
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "balance(2H2 + O2 -> H2O)").unwrap());
+        let ParsedExpr::FunctionCall { args, .. } = expr else {
+            panic!("Expected function call");
+        };
+        let ParsedExpr::Reaction {
+            reactants,
+            products,
+            ..
+        } = &args[0]
+        else {
+            panic!("Expected reaction");
+        };
+        assert_eq!(reactants.len(), 2);
+        assert_eq!(reactants[0].coefficient, Some("2".to_string()));
+        assert_eq!(reactants[0].chemical, "H2");
+        assert_eq!(products[0].span, Span::new(20, 23));
+    }
+
+    #[test]
+    fn test_parse_concentration() {
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "0.5 mol NaCl/L").unwrap());
+        let ParsedExpr::Number {
+            units: Some(units), ..
+        } = expr
+        else {
+            panic!("Expected number with units");
+        };
+        assert_eq!(units.numerator[0].0.chemical, Some("NaCl".to_string()));
+        assert_eq!(units.denominator[0].0.name, "L");
+
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "5% w/v NaCl").unwrap());
+        let ParsedExpr::Number {
+            units: Some(units), ..
+        } = expr
+        else {
+            panic!("Expected number with units");
+        };
+        assert_eq!(units.numerator[0].0.name, "% w/v");
+
+        // The compound can follow the volume too
+        for line in ["10 g/L NaCl", "0.5 mol/L NaCl"] {
+            let expr = parse_expr(SiffraParser::parse(Rule::expr, line).unwrap());
+            let ParsedExpr::Number {
+                units: Some(units), ..
+            } = expr
+            else {
+                panic!("Expected number with units");
+            };
+            assert_eq!(units.numerator[0].0.chemical, Some("NaCl".to_string()));
+            assert_eq!(units.denominator[0].0.name, "L");
+            assert_eq!(units.denominator[0].0.chemical, None);
+        }
+    }
+
+    #[test]
+    fn test_parse_units_after_parentheses() {
+        for line in ["(3 + 4i) Ω", "(3 + 4i)Ω"] {
+            let expr = parse_expr(SiffraParser::parse(Rule::expr, line).unwrap());
+            let ParsedExpr::BinOp {
+                lhs,
+                op: Op::Multiply,
+                rhs,
+                ..
+            } = expr
+            else {
+                panic!("Expected a multiplication, found {:?}", expr);
+            };
+            assert!(matches!(*lhs, ParsedExpr::BinOp { op: Op::Add, .. }));
+            let ParsedExpr::Number {
+                units: Some(units), ..
+            } = *rhs
+            else {
+                panic!("Expected number with units");
+            };
+            assert_eq!(units.numerator[0].0.name, "Ω");
+        }
+
+        // Parentheses next to each other still multiply
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "(2)(x)").unwrap());
+        let ParsedExpr::BinOp { rhs, .. } = expr else {
+            panic!("Expected a multiplication, found {:?}", expr);
+        };
+        assert!(matches!(*rhs, ParsedExpr::Variable { .. }));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        let mut expr =
+            parse_expr(SiffraParser::parse(Rule::expr, "2 + 1/3 to 2 dp to sci").unwrap());
+        assert_eq!(
+            expr.take_format_changes(),
+            vec![
+                FormatChange::Digits(Digits::DecimalPlaces(2)),
+                FormatChange::Notation(Notation::Scientific)
+            ]
+        );
+        assert!(matches!(expr, ParsedExpr::BinOp { op: Op::Add, .. }));
+        assert!(expr.take_format_changes().is_empty());
+    }
+
+    #[test]
+    fn test_parse_uncertainty() {
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "2 * 5.0 ± 0.2 m").unwrap());
+        let ParsedExpr::BinOp {
+            op: Op::Multiply,
+            rhs,
+            ..
+        } = expr
+        else {
+            panic!("Expected a multiplication, found {:?}", expr);
+        };
+        assert!(matches!(
+            *rhs,
+            ParsedExpr::BinOp {
+                op: Op::PlusMinus,
+                ..
+            }
+        ));
+
+        let expr = parse_expr(SiffraParser::parse(Rule::expr, "5 +/- 1").unwrap());
+        assert!(matches!(
+            expr,
+            ParsedExpr::BinOp {
+                op: Op::PlusMinus,
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_nested_functions() {
         let expr = parse_expr(SiffraParser::parse(Rule::expr, "sin(cos(5))").unwrap());
@@ -1,9 +1,13 @@
 use crate::error::SiffraExecutionError;
 use crate::grammar::Span;
-use crate::representations::Compound;
+use crate::representations::{
+    parse_rational, split_radix_prefix, Complex, Expression, Float, FormatChange, Rational,
+    SignificantFigures, Value,
+};
+use crate::representations::{Compound, FormulaError, GasConditions, Reaction};
 use crate::representations::{Dimension, Quantity};
-use crate::representations::{Expression, Float, Value};
-use crate::siffra_try;
+use crate::{siffra_error, siffra_try};
+use num_traits::One;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,12 +15,24 @@ pub enum ParsedLine {
     Comment,
     Expression(ParsedExpr),
     Variable(String, ParsedExpr),
+    Compound {
+        name: String,
+        formula: String,
+        span: Span,
+    },
+    Macro {
+        name: String,
+        input: Option<String>,
+        span: Span,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParsedUnit {
     pub name: String,
     pub chemical: Option<String>,
+    /// Compound the chemical's name refers to, filled in before evaluation
+    pub compound: Option<Compound>,
     pub span: Span,
 }
 
@@ -27,6 +43,27 @@ pub struct ParsedDimension {
     pub span: Span,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedGasCondition {
+    pub value: String,
+    pub unit: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedGasConditions {
+    Standard,
+    Ambient,
+    Custom(Vec<ParsedGasCondition>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedSpecies {
+    pub coefficient: Option<String>,
+    pub chemical: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParsedExpr {
     Number {
@@ -38,6 +75,16 @@ pub enum ParsedExpr {
         name: String,
         span: Span,
     },
+    List {
+        elements: Vec<ParsedExpr>,
+        units: Option<ParsedDimension>,
+        span: Span,
+    },
+    Reaction {
+        reactants: Vec<ParsedSpecies>,
+        products: Vec<ParsedSpecies>,
+        span: Span,
+    },
     FunctionCall {
         name: String,
         args: Vec<ParsedExpr>,
@@ -72,7 +119,13 @@ pub enum OpPre {
 pub enum OpPost {
     Factorial,
     Percent,
-    Convert(ParsedDimension),
+    Convert {
+        dimension: ParsedDimension,
+        conditions: Option<ParsedGasConditions>,
+        reaction: Option<Box<ParsedExpr>>,
+    },
+    Format(FormatChange),
+    Index(Box<ParsedExpr>),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -81,7 +134,258 @@ pub enum Op {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
     Exponent,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    PlusMinus,
+}
+
+impl ParsedDimension {
+    fn units_mut(&mut self) -> impl Iterator<Item = &mut ParsedUnit> {
+        self.numerator
+            .iter_mut()
+            .chain(self.denominator.iter_mut())
+            .map(|(unit, _)| unit)
+    }
+
+    /// Splits names of units written after a unit, as in `kg m`, into units of their own.
+    fn separate_unit_names(&mut self) {
+        for units in [&mut self.numerator, &mut self.denominator] {
+            let mut i = 0;
+            while i < units.len() {
+                let (unit, power) = &mut units[i];
+                let power = *power;
+                if let Some((name, span)) = unit.take_name(is_unit_name) {
+                    let unit = ParsedUnit {
+                        name,
+                        chemical: None,
+                        compound: None,
+                        span,
+                    };
+                    units.insert(i + 1, (unit, power));
+                }
+                i += 1;
+            }
+        }
+    }
+
+    /// Removes the first name after a unit that `matches` accepts, and returns it with its span.
+    fn take_name(&mut self, matches: impl Fn(&str) -> bool) -> Option<(String, Span)> {
+        self.units_mut().find_map(|unit| unit.take_name(&matches))
+    }
+}
+
+impl ParsedUnit {
+    /// Removes the chemical name after the unit if `matches` accepts it, and returns it with its
+    /// span. Formulas are left alone, since they start with an element symbol.
+    fn take_name(&mut self, matches: impl Fn(&str) -> bool) -> Option<(String, Span)> {
+        let name = self
+            .chemical
+            .as_deref()
+            .filter(|name| name.starts_with(|c: char| c.is_ascii_lowercase()) && matches(name))?
+            .to_string();
+        self.chemical = None;
+        let span = Span::new(self.span.end() - name.len(), self.span.end());
+        self.span = Span::new(self.span.start(), span.start() - 1);
+        Some((name, span))
+    }
+}
+
+fn is_unit_name(name: &str) -> bool {
+    Quantity::from_str(name).is_ok() || Quantity::concentration(name).is_some()
+}
+
+impl ParsedExpr {
+    /// Calls `f` on every unit in the expression, including conversion targets.
+    pub fn for_each_unit(&mut self, f: &mut impl FnMut(&mut ParsedUnit)) {
+        match self {
+            ParsedExpr::Number {
+                units: Some(units), ..
+            } => units.units_mut().for_each(f),
+            ParsedExpr::Number { .. }
+            | ParsedExpr::Variable { .. }
+            | ParsedExpr::Reaction { .. } => {}
+            ParsedExpr::List {
+                elements, units, ..
+            } => {
+                elements
+                    .iter_mut()
+                    .for_each(|element| element.for_each_unit(f));
+                if let Some(units) = units {
+                    units.units_mut().for_each(f);
+                }
+            }
+            ParsedExpr::FunctionCall { args, base, .. } => {
+                args.iter_mut().for_each(|arg| arg.for_each_unit(f));
+                if let Some(base) = base {
+                    base.for_each_unit(f);
+                }
+            }
+            ParsedExpr::UnOpPre { rhs, .. } => rhs.for_each_unit(f),
+            ParsedExpr::UnOpPost { lhs, op, .. } => {
+                lhs.for_each_unit(f);
+                match op {
+                    OpPost::Convert {
+                        dimension,
+                        reaction,
+                        ..
+                    } => {
+                        dimension.units_mut().for_each(&mut *f);
+                        if let Some(reaction) = reaction {
+                            reaction.for_each_unit(f);
+                        }
+                    }
+                    OpPost::Index(index) => index.for_each_unit(f),
+                    _ => {}
+                }
+            }
+            ParsedExpr::BinOp { lhs, rhs, .. } => {
+                lhs.for_each_unit(f);
+                rhs.for_each_unit(f);
+            }
+        }
+    }
+}
+
+impl ParsedExpr {
+    /// Calls `f` on every expression, inner expressions first. `f` may replace the expression.
+    fn for_each_expr(&mut self, f: &mut impl FnMut(&mut ParsedExpr)) {
+        match self {
+            ParsedExpr::Number { .. }
+            | ParsedExpr::Variable { .. }
+            | ParsedExpr::Reaction { .. } => {}
+            ParsedExpr::List { elements, .. } => {
+                elements
+                    .iter_mut()
+                    .for_each(|element| element.for_each_expr(f));
+            }
+            ParsedExpr::FunctionCall { args, base, .. } => {
+                args.iter_mut().for_each(|arg| arg.for_each_expr(f));
+                if let Some(base) = base {
+                    base.for_each_expr(f);
+                }
+            }
+            ParsedExpr::UnOpPre { rhs, .. } => rhs.for_each_expr(f),
+            ParsedExpr::UnOpPost { lhs, op, .. } => {
+                lhs.for_each_expr(f);
+                match op {
+                    OpPost::Convert {
+                        reaction: Some(reaction),
+                        ..
+                    } => reaction.for_each_expr(f),
+                    OpPost::Index(index) => index.for_each_expr(f),
+                    _ => {}
+                }
+            }
+            ParsedExpr::BinOp { lhs, rhs, .. } => {
+                lhs.for_each_expr(f);
+                rhs.for_each_expr(f);
+            }
+        }
+        f(self);
+    }
+
+    /// Separates names after a unit that are variables or units rather than compounds, so that
+    /// `10 kg x` is `10 kg` times `x` and `10 kg m` is in `kg*m`.
+    pub fn separate_unit_names(&mut self, is_variable: &impl Fn(&str) -> bool) {
+        self.for_each_expr(&mut |expr| {
+            let (ParsedExpr::Number {
+                units: Some(units),
+                span,
+                ..
+            }
+            | ParsedExpr::List {
+                units: Some(units),
+                span,
+                ..
+            }) = expr
+            else {
+                if let ParsedExpr::UnOpPost {
+                    op: OpPost::Convert { dimension, .. },
+                    ..
+                } = expr
+                {
+                    dimension.separate_unit_names();
+                }
+                return;
+            };
+
+            units.separate_unit_names();
+            let Some((name, name_span)) = units.take_name(|name| is_variable(name)) else {
+                return;
+            };
+            let span = *span;
+            let lhs = std::mem::replace(
+                expr,
+                ParsedExpr::Variable {
+                    name: String::new(),
+                    span,
+                },
+            );
+            *expr = ParsedExpr::BinOp {
+                lhs: Box::new(lhs),
+                op: Op::Multiply,
+                rhs: Box::new(ParsedExpr::Variable {
+                    name,
+                    span: name_span,
+                }),
+                span,
+            };
+        });
+    }
+
+    /// Removes every format change, like `to 3 sf`, from the expression and returns them in the
+    /// order they are applied. Format changes apply to the whole line wherever they are.
+    pub fn take_format_changes(&mut self) -> Vec<FormatChange> {
+        let mut changes = Vec::new();
+
+        match self {
+            ParsedExpr::Number { .. }
+            | ParsedExpr::Variable { .. }
+            | ParsedExpr::Reaction { .. } => {}
+            ParsedExpr::List { elements, .. } => {
+                for element in elements.iter_mut() {
+                    changes.extend(element.take_format_changes());
+                }
+            }
+            ParsedExpr::FunctionCall { args, base, .. } => {
+                for arg in args.iter_mut() {
+                    changes.extend(arg.take_format_changes());
+                }
+                if let Some(base) = base {
+                    changes.extend(base.take_format_changes());
+                }
+            }
+            ParsedExpr::UnOpPre { rhs, .. } => changes.extend(rhs.take_format_changes()),
+            ParsedExpr::UnOpPost { lhs, op, .. } => {
+                changes.extend(lhs.take_format_changes());
+                if let OpPost::Index(index) = op {
+                    changes.extend(index.take_format_changes());
+                }
+                if let OpPost::Format(change) = op {
+                    changes.push(*change);
+                    let lhs = std::mem::replace(
+                        lhs.as_mut(),
+                        ParsedExpr::Variable {
+                            name: String::new(),
+                            span: Span::new(0, 0),
+                        },
+                    );
+                    *self = lhs;
+                }
+            }
+            ParsedExpr::BinOp { lhs, rhs, .. } => {
+                changes.extend(lhs.take_format_changes());
+                changes.extend(rhs.take_format_changes());
+            }
+        }
+
+        changes
+    }
 }
 
 impl TryFrom<ParsedDimension> for Dimension {
@@ -90,73 +394,88 @@ impl TryFrom<ParsedDimension> for Dimension {
     fn try_from(dimension: ParsedDimension) -> Result<Self, SiffraExecutionError> {
         let mut quantities = Vec::new();
 
-        for (unit, power) in dimension.numerator {
-            if !(unit.name == "unitless" || unit.name == "number") {
-                if let Some(chemical) = unit.chemical {
-                    let compound = siffra_try!(
-                        Compound::parse(chemical.as_str()).ok_or(()),
-                        "Chemical Error",
-                        "Error parsing chemical compound",
+        let units = dimension.numerator.into_iter().chain(
+            dimension
+                .denominator
+                .into_iter()
+                .map(|(unit, power)| (unit, -power)),
+        );
+
+        for (unit, power) in units {
+            if unit.name == "unitless" || unit.name == "number" {
+                continue;
+            }
+
+            // Concentrations such as `M` stand for several quantities, like `mol/L`
+            let unit_quantities = match Quantity::concentration(unit.name.as_str()) {
+                Some(unit_quantities) => unit_quantities,
+                None => vec![(
+                    siffra_try!(
+                        Quantity::from_str(unit.name.as_str()),
+                        "Unit Error",
+                        format!("Unit '{}' not defined", unit.name),
                         Some(unit.span)
-                    );
-                    quantities.push((
-                        siffra_try!(
-                            Quantity::from_str(unit.name.as_str()),
-                            "Unit Error",
-                            format!("Unit '{}' not defined", unit.name),
-                            Some(unit.span)
-                        )
-                        .with_chemical(compound),
-                        Float::from(power),
-                    ));
-                } else {
-                    quantities.push((
-                        siffra_try!(
-                            Quantity::from_str(unit.name.as_str()),
-                            "Unit Error",
-                            format!("Unit '{}' not defined", unit.name),
-                            Some(unit.span)
-                        ),
-                        Float::from(power),
-                    ));
-                }
+                    ),
+                    Float::from(1),
+                )],
+            };
+
+            let compound = match (unit.compound, unit.chemical) {
+                (Some(compound), _) => Some(compound),
+                (None, Some(chemical)) => Some(
+                    Compound::parse(chemical.as_str())
+                        .map_err(|err| formula_error(err, &chemical, unit.span))?,
+                ),
+                (None, None) => None,
+            };
+
+            // Only the solute of a concentration is tagged, not the solvent's volume or mass
+            for (quantity, unit_power) in unit_quantities {
+                let quantity = match &compound {
+                    Some(compound) if unit_power > Float::from(0) => {
+                        quantity.with_chemical(compound.clone())
+                    }
+                    _ => quantity,
+                };
+                quantities.push((quantity, &unit_power * &Float::from(power)));
             }
         }
 
-        for (unit, power) in dimension.denominator {
-            if !(unit.name == "unitless" || unit.name == "number") {
-                if let Some(chemical) = unit.chemical {
-                    let compound = siffra_try!(
-                        Compound::parse(chemical.as_str()).ok_or(()),
-                        "Syntax Error",
-                        "Error parsing compound",
-                        Some(unit.span)
+        Ok(Dimension::new(quantities))
+    }
+}
+
+impl TryFrom<ParsedGasConditions> for GasConditions {
+    type Error = SiffraExecutionError;
+
+    fn try_from(conditions: ParsedGasConditions) -> Result<Self, SiffraExecutionError> {
+        match conditions {
+            ParsedGasConditions::Standard => Ok(GasConditions::standard()),
+            ParsedGasConditions::Ambient => Ok(GasConditions::ambient()),
+            ParsedGasConditions::Custom(custom) => {
+                let mut conditions = GasConditions::standard();
+
+                for condition in custom {
+                    let value = siffra_try!(
+                        Float::parse(&condition.value),
+                        "Error parsing number",
+                        Some(condition.span)
+                    );
+                    conditions = siffra_try!(
+                        conditions
+                            .clone()
+                            .with_temperature(&value, &condition.unit)
+                            .or_else(|| conditions.with_pressure(&value, &condition.unit))
+                            .ok_or(()),
+                        "Condition Error",
+                        "Temperature must be above absolute zero and pressure must be positive",
+                        Some(condition.span)
                     );
-                    quantities.push((
-                        siffra_try!(
-                            Quantity::from_str(unit.name.as_str()),
-                            "Syntax Error",
-                            "Error parsing quantity",
-                            Some(unit.span)
-                        )
-                        .with_chemical(compound),
-                        Float::from(-power),
-                    ));
-                } else {
-                    quantities.push((
-                        siffra_try!(
-                            Quantity::from_str(unit.name.as_str()),
-                            "Syntax Error",
-                            "Error parsing quantity",
-                            Some(unit.span)
-                        ),
-                        Float::from(-power),
-                    ));
                 }
+
+                Ok(conditions)
             }
         }
-
-        Ok(Dimension::new(quantities))
     }
 }
 
@@ -170,11 +489,91 @@ impl TryFrom<ParsedExpr> for Expression {
                     Some(units) => Some(Dimension::try_from(units)?),
                     None => None,
                 };
-                let num = siffra_try!(Float::parse(&*value), "Error parsing number", Some(span));
+                // An imaginary number, as in `4i`
+                if let Some(imaginary) = value.strip_suffix(['i', 'j']) {
+                    let num =
+                        siffra_try!(Float::parse(imaginary), "Error parsing number", Some(span));
+                    return Ok(Expression::constant(
+                        Value::new(Float::from(0), dimension)
+                            .with_complex(Complex::new(Float::from(0), num)),
+                    )
+                    .with_span(span));
+                }
+
+                let num = siffra_try!(Float::parse(&value), "Error parsing number", Some(span));
+                // Whole numbers without units are counts, which are exact, and numbers written in
+                // another base are exact bit patterns
+                let significant_figures = match split_radix_prefix(&value) {
+                    Some(_) => None,
+                    None => SignificantFigures::of_literal(&value, dimension.is_none()),
+                };
 
-                Ok(Expression::constant(Value::new(num, dimension)).with_span(span))
+                Ok(Expression::constant(
+                    Value::new(num, dimension)
+                        .measured(significant_figures)
+                        .with_exact(parse_rational(&value)),
+                )
+                .with_span(span))
             }
             ParsedExpr::Variable { name, span } => Ok(Expression::variable(name).with_span(span)),
+            ParsedExpr::List {
+                elements,
+                units,
+                span,
+            } => {
+                let list = Expression::list(
+                    elements
+                        .into_iter()
+                        .map(Expression::try_from)
+                        .collect::<Result<_, _>>()?,
+                )
+                .with_span(span);
+                match units {
+                    // `[1, 2] m` is each number times a metre
+                    Some(units) => Ok(Expression::multiply(
+                        list,
+                        Expression::constant(
+                            Value::from(Rational::one()).with_units(Dimension::try_from(units)?),
+                        ),
+                    )
+                    .with_span(span)),
+                    None => Ok(list),
+                }
+            }
+            ParsedExpr::Reaction {
+                reactants,
+                products,
+                span,
+            } => {
+                let mut spans = Vec::with_capacity(reactants.len() + products.len());
+
+                let mut parse_side = |side: Vec<ParsedSpecies>| {
+                    side.into_iter()
+                        .map(|species| {
+                            let coefficient = match species.coefficient {
+                                Some(coefficient) => siffra_try!(
+                                    coefficient.parse::<u32>(),
+                                    "Syntax Error",
+                                    "Invalid coefficient",
+                                    Some(species.span)
+                                ),
+                                None => 1,
+                            };
+                            let compound =
+                                Compound::parse(species.chemical.as_str()).map_err(|err| {
+                                    formula_error(err, &species.chemical, species.span)
+                                })?;
+                            spans.push(species.span);
+                            Ok((coefficient, compound))
+                        })
+                        .collect::<Result<Vec<_>, SiffraExecutionError>>()
+                };
+
+                let reactants = parse_side(reactants)?;
+                let products = parse_side(products)?;
+
+                Ok(Expression::reaction(Reaction::new(reactants, products), spans).with_span(span))
+            }
             ParsedExpr::FunctionCall {
                 name,
                 args,
@@ -211,34 +610,80 @@ impl TryFrom<ParsedExpr> for Expression {
                 let lhs = Box::new(Expression::try_from(*lhs)?);
 
                 match op {
+                    // Format changes are taken out of the expression before it is evaluated
+                    OpPost::Format(_) => Ok(*lhs),
                     OpPost::Factorial => Ok(Expression::function_call(
                         "factorial".to_string(),
                         vec![*lhs],
                     )
                     .with_span(span)),
+                    OpPost::Index(index) => Ok(Expression::function_call(
+                        "index".to_string(),
+                        vec![*lhs, Expression::try_from(*index)?],
+                    )
+                    .with_span(span)),
                     OpPost::Percent => Ok(Expression::divide(
                         *lhs,
                         Expression::constant(Value::from(100.0)),
                     )
                     .with_span(span)),
-                    OpPost::Convert(dimension) => {
-                        Ok(Expression::convert(*lhs, Dimension::try_from(dimension)?)
-                            .with_span(span))
+                    OpPost::Convert {
+                        dimension,
+                        conditions,
+                        reaction,
+                    } => {
+                        let conditions = match conditions {
+                            Some(conditions) => Some(GasConditions::try_from(conditions)?),
+                            None => None,
+                        };
+                        let reaction = match reaction {
+                            Some(reaction) => Some(Expression::try_from(*reaction)?),
+                            None => None,
+                        };
+                        Ok(Expression::convert(
+                            *lhs,
+                            Dimension::try_from(dimension)?,
+                            conditions,
+                            reaction,
+                        )
+                        .with_span(span))
                     }
                 }
             }
             ParsedExpr::BinOp { lhs, op, rhs, span } => {
                 let lhs = Box::new(Expression::try_from(*lhs)?);
                 let rhs = Box::new(Expression::try_from(*rhs)?);
+                // Operators without their own expression are calls to the function they name
+                let call = |name: &str, lhs: Expression, rhs: Expression| {
+                    Ok(Expression::function_call(name.to_string(), vec![lhs, rhs]).with_span(span))
+                };
 
                 match op {
                     Op::Add => Ok(Expression::add(*lhs, *rhs).with_span(span)),
                     Op::Subtract => Ok(Expression::subtract(*lhs, *rhs).with_span(span)),
                     Op::Multiply => Ok(Expression::multiply(*lhs, *rhs).with_span(span)),
                     Op::Divide => Ok(Expression::divide(*lhs, *rhs).with_span(span)),
+                    Op::Modulo => call("mod", *lhs, *rhs),
                     Op::Exponent => Ok(Expression::exponent(*lhs, *rhs).with_span(span)),
+                    Op::PlusMinus => Ok(Expression::uncertain(*lhs, *rhs).with_span(span)),
+                    Op::BitAnd => call("and", *lhs, *rhs),
+                    Op::BitOr => call("or", *lhs, *rhs),
+                    Op::BitXor => call("xor", *lhs, *rhs),
+                    Op::ShiftLeft => call("shl", *lhs, *rhs),
+                    Op::ShiftRight => call("shr", *lhs, *rhs),
                 }
             }
         }
     }
 }
+
+/// Converts an error in a chemical formula into one spanning the offending part of the formula.
+/// The formula must be at the end of `span`.
+pub fn formula_error(error: FormulaError, chemical: &str, span: Span) -> SiffraExecutionError {
+    let start = span.end() - chemical.len();
+    siffra_error!(
+        "Chemical Error",
+        error.message(),
+        Some(Span::new(start + error.start(), start + error.end()))
+    )
+}
@@ -0,0 +1,189 @@
+use crate::representations::{Float, FloatContext};
+
+/// A complex number, as in `3 + 4i`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Complex {
+    pub re: Float,
+    pub im: Float,
+}
+
+impl Complex {
+    pub fn new(re: Float, im: Float) -> Self {
+        Self { re, im }
+    }
+
+    /// The imaginary unit, `i`.
+    pub fn i() -> Self {
+        Self::new(Float::from(0), Float::from(1))
+    }
+
+    /// The number with the given magnitude and argument, in radians.
+    pub fn from_polar(magnitude: &Float, argument: &Float) -> Self {
+        Self::new(magnitude * &argument.cos(), magnitude * &argument.sin()).cleaned()
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im == Float::from(0)
+    }
+
+    /// The distance from zero, `|z|`.
+    pub fn magnitude(&self) -> Float {
+        (&(&self.re * &self.re) + &(&self.im * &self.im)).sqrt()
+    }
+
+    /// The angle from the positive real axis in radians, between -π and π.
+    pub fn argument(&self) -> Float {
+        let zero = Float::from(0);
+        let half_pi = &Float::pi() / &Float::from(2);
+
+        if self.re > zero {
+            (&self.im / &self.re).atan()
+        } else if self.re < zero && self.im >= zero {
+            &(&self.im / &self.re).atan() + &Float::pi()
+        } else if self.re < zero {
+            &(&self.im / &self.re).atan() - &Float::pi()
+        } else if self.im > zero {
+            half_pi
+        } else if self.im < zero {
+            -half_pi
+        } else {
+            zero
+        }
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.re.clone(), -self.im.clone())
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        Self::new(&self.re + &other.re, &self.im + &other.im)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        Self::new(&self.re - &other.re, &self.im - &other.im)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(
+            &(&self.re * &other.re) - &(&self.im * &other.im),
+            &(&self.re * &other.im) + &(&self.im * &other.re),
+        )
+    }
+
+    pub fn recip(&self) -> Self {
+        let denominator = &(&self.re * &self.re) + &(&self.im * &self.im);
+        Self::new(&self.re / &denominator, &(-self.im.clone()) / &denominator)
+    }
+
+    pub fn div(&self, other: &Self) -> Self {
+        self.mul(&other.recip())
+    }
+
+    pub fn exp(&self) -> Self {
+        Self::from_polar(&self.re.exp(), &self.im)
+    }
+
+    /// The principal natural logarithm, whose imaginary part is between -π and π.
+    pub fn ln(&self) -> Self {
+        Self::new(self.magnitude().ln(), self.argument())
+    }
+
+    /// The principal value of `self` raised to `power`.
+    pub fn pow(&self, power: &Self) -> Self {
+        let zero = Float::from(0);
+        if self.re == zero && self.im == zero {
+            if power.re == zero && power.im == zero {
+                return Self::new(Float::from(1), zero);
+            }
+            return Self::new(zero.clone(), zero);
+        }
+
+        // Real powers keep the magnitude exact, as in `sqrt(-4) = 2i`
+        if power.is_real() {
+            let magnitude = self.magnitude().pow(&power.re);
+            return Self::from_polar(&magnitude, &(&self.argument() * &power.re));
+        }
+
+        power.mul(&self.ln()).exp()
+    }
+
+    pub fn sin(&self) -> Self {
+        let (cosh, sinh) = hyperbolic(&self.im);
+        Self::new(&self.re.sin() * &cosh, &self.re.cos() * &sinh).cleaned()
+    }
+
+    pub fn cos(&self) -> Self {
+        let (cosh, sinh) = hyperbolic(&self.im);
+        Self::new(&self.re.cos() * &cosh, -(&self.re.sin() * &sinh)).cleaned()
+    }
+
+    pub fn tan(&self) -> Self {
+        self.sin().div(&self.cos()).cleaned()
+    }
+
+    /// Removes a part that is only rounding error next to the other, such as the real part of
+    /// `e^(iπ/2)`.
+    pub(crate) fn cleaned(self) -> Self {
+        let zero = Float::from(0);
+        let bits = FloatContext::current().precision as i32;
+        let tolerance = &self.magnitude() * &Float::from(2).pow(&Float::from(16 - bits));
+
+        let clean = |part: Float| {
+            if part.abs() < tolerance {
+                zero.clone()
+            } else {
+                part
+            }
+        };
+        Self {
+            re: clean(self.re),
+            im: clean(self.im),
+        }
+    }
+}
+
+/// Returns the hyperbolic cosine and sine of a number.
+fn hyperbolic(value: &Float) -> (Float, Float) {
+    let exp = value.exp();
+    let recip = exp.recip();
+    let two = Float::from(2);
+    (&(&exp + &recip) / &two, &(&exp - &recip) / &two)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complex(re: f64, im: f64) -> Complex {
+        Complex::new(Float::from(re), Float::from(im))
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(
+            complex(1.0, 2.0).mul(&complex(3.0, -1.0)),
+            complex(5.0, 5.0)
+        );
+        assert_eq!(
+            complex(5.0, 5.0).div(&complex(3.0, -1.0)),
+            complex(1.0, 2.0)
+        );
+        assert_eq!(complex(3.0, 4.0).magnitude(), Float::from(5));
+    }
+
+    #[test]
+    fn test_principal_values() {
+        assert_eq!(
+            complex(-4.0, 0.0).pow(&complex(0.5, 0.0)),
+            complex(0.0, 2.0)
+        );
+        assert_eq!(
+            complex(-1.0, 0.0).ln(),
+            Complex::new(Float::from(0), Float::pi())
+        );
+        assert_eq!(
+            Complex::new(Float::from(0), Float::pi()).exp(),
+            complex(-1.0, 0.0)
+        );
+    }
+}
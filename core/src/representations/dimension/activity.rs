@@ -0,0 +1,70 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the becquerel, one decay per second.
+quantity!(
+    Activity,
+    [
+        // SI units
+        (Becquerel, ratio!(1 / 1), "Bq", "becquerel", "becquerels"),
+        (
+            Kilobecquerel,
+            ratio!(1_000 / 1),
+            "kBq",
+            "kilobecquerel",
+            "kilobecquerels"
+        ),
+        (
+            Megabecquerel,
+            ratio!(1_000_000 / 1),
+            "MBq",
+            "megabecquerel",
+            "megabecquerels"
+        ),
+        (
+            Gigabecquerel,
+            ratio!(1_000_000_000 / 1),
+            "GBq",
+            "gigabecquerel",
+            "gigabecquerels"
+        ),
+        (
+            Terabecquerel,
+            ratio!(1_000_000_000_000 / 1),
+            "TBq",
+            "terabecquerel",
+            "terabecquerels"
+        ),
+        // Non-SI units
+        (Curie, ratio!(37_000_000_000 / 1), "Ci", "curie", "curies"),
+        (
+            Millicurie,
+            ratio!(37_000_000 / 1),
+            "mCi",
+            "millicurie",
+            "millicuries"
+        ),
+        (
+            Microcurie,
+            ratio!(37_000 / 1),
+            "uCi",
+            "microcurie",
+            "microcuries",
+            "µCi"
+        ),
+        (Nanocurie, ratio!(37 / 1), "nCi", "nanocurie", "nanocuries"),
+        (
+            Picocurie,
+            ratio!(37 / 1_000),
+            "pCi",
+            "picocurie",
+            "picocuries"
+        ),
+        (
+            Rutherford,
+            ratio!(1_000_000 / 1),
+            "Rd",
+            "rutherford",
+            "rutherfords"
+        )
+    ]
+);
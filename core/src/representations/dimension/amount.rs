@@ -101,6 +101,37 @@ quantity!(
             "lbmol",
             "pound-mole",
             "pound-moles"
+        ),
+        // Particle counts - one mole holds Avogadro's number of them
+        (
+            Particle,
+            ratio!(1 / 602_214_076_000_000_000_000_000),
+            "particles",
+            "particle"
+        ),
+        (
+            Atom,
+            ratio!(1 / 602_214_076_000_000_000_000_000),
+            "atoms",
+            "atom"
+        ),
+        (
+            Molecule,
+            ratio!(1 / 602_214_076_000_000_000_000_000),
+            "molecules",
+            "molecule"
+        ),
+        (
+            Ion,
+            ratio!(1 / 602_214_076_000_000_000_000_000),
+            "ions",
+            "ion"
+        ),
+        (
+            Entity,
+            ratio!(1 / 602_214_076_000_000_000_000_000),
+            "entities",
+            "entity"
         )
     ]
 );
@@ -6,7 +6,7 @@ quantity!(
         (Radian, ratio!(1 / 1), "rad", "radian", "radians", "rads"),
         (
             Degree,
-            ratio!(1783366216531 / 102179357533440),
+            &Float::pi() / &Float::from(180),
             "deg",
             "degree",
             "degs",
@@ -14,7 +14,7 @@ quantity!(
         ),
         (
             Revolution,
-            ratio!(1783366216531 / 567663097408),
+            &Float::pi() * &Float::from(2),
             "rev",
             "revolution",
             "revs",
@@ -0,0 +1,38 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the farad.
+quantity!(
+    Capacitance,
+    [
+        (Farad, ratio!(1 / 1), "F", "farad", "farads"),
+        (
+            Millifarad,
+            ratio!(1 / 1_000),
+            "mF",
+            "millifarad",
+            "millifarads"
+        ),
+        (
+            Microfarad,
+            ratio!(1 / 1_000_000),
+            "uF",
+            "microfarad",
+            "microfarads",
+            "µF"
+        ),
+        (
+            Nanofarad,
+            ratio!(1 / 1_000_000_000),
+            "nF",
+            "nanofarad",
+            "nanofarads"
+        ),
+        (
+            Picofarad,
+            ratio!(1 / 1_000_000_000_000),
+            "pF",
+            "picofarad",
+            "picofarads"
+        )
+    ]
+);
@@ -0,0 +1,192 @@
+/// Standard atomic weights and their uncertainties in g/mol, kept as decimal strings so they can be
+/// parsed into `Float`s without losing digits, indexed by atomic number minus one. Elements
+/// whose weight varies between natural sources use the IUPAC conventional value. Elements with
+/// no stable isotopes use the mass number of their longest-lived isotope and have no uncertainty.
+const STANDARD_ATOMIC_WEIGHTS: [(&str, Option<&str>); 118] = [
+    ("1.008", Some("0.0002")),             // H
+    ("4.002602", Some("0.000002")),        // He
+    ("6.94", Some("0.06")),                // Li
+    ("9.0121831", Some("0.0000005")),      // Be
+    ("10.81", Some("0.02")),               // B
+    ("12.011", Some("0.002")),             // C
+    ("14.007", Some("0.001")),             // N
+    ("15.999", Some("0.001")),             // O
+    ("18.998403162", Some("0.000000005")), // F
+    ("20.1797", Some("0.0006")),           // Ne
+    ("22.98976928", Some("0.00000002")),   // Na
+    ("24.305", Some("0.002")),             // Mg
+    ("26.9815384", Some("0.0000003")),     // Al
+    ("28.085", Some("0.001")),             // Si
+    ("30.973761998", Some("0.000000005")), // P
+    ("32.06", Some("0.02")),               // S
+    ("35.45", Some("0.01")),               // Cl
+    ("39.95", Some("0.16")),               // Ar
+    ("39.0983", Some("0.0001")),           // K
+    ("40.078", Some("0.004")),             // Ca
+    ("44.955907", Some("0.000004")),       // Sc
+    ("47.867", Some("0.001")),             // Ti
+    ("50.9415", Some("0.0001")),           // V
+    ("51.9961", Some("0.0006")),           // Cr
+    ("54.938043", Some("0.000002")),       // Mn
+    ("55.845", Some("0.002")),             // Fe
+    ("58.933194", Some("0.000003")),       // Co
+    ("58.6934", Some("0.0004")),           // Ni
+    ("63.546", Some("0.003")),             // Cu
+    ("65.38", Some("0.02")),               // Zn
+    ("69.723", Some("0.001")),             // Ga
+    ("72.630", Some("0.008")),             // Ge
+    ("74.921595", Some("0.000006")),       // As
+    ("78.971", Some("0.008")),             // Se
+    ("79.904", Some("0.003")),             // Br
+    ("83.798", Some("0.002")),             // Kr
+    ("85.4678", Some("0.0003")),           // Rb
+    ("87.62", Some("0.01")),               // Sr
+    ("88.905838", Some("0.000002")),       // Y
+    ("91.224", Some("0.002")),             // Zr
+    ("92.90637", Some("0.00001")),         // Nb
+    ("95.95", Some("0.01")),               // Mo
+    ("97", None),                          // Tc
+    ("101.07", Some("0.02")),              // Ru
+    ("102.90549", Some("0.00002")),        // Rh
+    ("106.42", Some("0.01")),              // Pd
+    ("107.8682", Some("0.0002")),          // Ag
+    ("112.414", Some("0.004")),            // Cd
+    ("114.818", Some("0.001")),            // In
+    ("118.710", Some("0.007")),            // Sn
+    ("121.760", Some("0.001")),            // Sb
+    ("127.60", Some("0.03")),              // Te
+    ("126.90447", Some("0.00003")),        // I
+    ("131.293", Some("0.006")),            // Xe
+    ("132.90545196", Some("0.00000006")),  // Cs
+    ("137.327", Some("0.007")),            // Ba
+    ("138.90547", Some("0.00007")),        // La
+    ("140.116", Some("0.001")),            // Ce
+    ("140.90766", Some("0.00001")),        // Pr
+    ("144.242", Some("0.003")),            // Nd
+    ("145", None),                         // Pm
+    ("150.36", Some("0.02")),              // Sm
+    ("151.964", Some("0.001")),            // Eu
+    ("157.25", Some("0.03")),              // Gd
+    ("158.925354", Some("0.000007")),      // Tb
+    ("162.500", Some("0.001")),            // Dy
+    ("164.930329", Some("0.000005")),      // Ho
+    ("167.259", Some("0.003")),            // Er
+    ("168.934219", Some("0.000005")),      // Tm
+    ("173.045", Some("0.010")),            // Yb
+    ("174.9668", Some("0.0001")),          // Lu
+    ("178.486", Some("0.006")),            // Hf
+    ("180.94788", Some("0.00002")),        // Ta
+    ("183.84", Some("0.01")),              // W
+    ("186.207", Some("0.001")),            // Re
+    ("190.23", Some("0.03")),              // Os
+    ("192.217", Some("0.002")),            // Ir
+    ("195.084", Some("0.009")),            // Pt
+    ("196.966570", Some("0.000004")),      // Au
+    ("200.592", Some("0.003")),            // Hg
+    ("204.38", Some("0.01")),              // Tl
+    ("207.2", Some("1.1")),                // Pb
+    ("208.98040", Some("0.00001")),        // Bi
+    ("209", None),                         // Po
+    ("210", None),                         // At
+    ("222", None),                         // Rn
+    ("223", None),                         // Fr
+    ("226", None),                         // Ra
+    ("227", None),                         // Ac
+    ("232.0377", Some("0.0004")),          // Th
+    ("231.03588", Some("0.00001")),        // Pa
+    ("238.02891", Some("0.00003")),        // U
+    ("237", None),                         // Np
+    ("244", None),                         // Pu
+    ("243", None),                         // Am
+    ("247", None),                         // Cm
+    ("247", None),                         // Bk
+    ("251", None),                         // Cf
+    ("252", None),                         // Es
+    ("257", None),                         // Fm
+    ("258", None),                         // Md
+    ("259", None),                         // No
+    ("262", None),                         // Lr
+    ("267", None),                         // Rf
+    ("268", None),                         // Db
+    ("269", None),                         // Sg
+    ("270", None),                         // Bh
+    ("269", None),                         // Hs
+    ("278", None),                         // Mt
+    ("281", None),                         // Ds
+    ("282", None),                         // Rg
+    ("285", None),                         // Cn
+    ("286", None),                         // Nh
+    ("289", None),                         // Fl
+    ("290", None),                         // Mc
+    ("293", None),                         // Lv
+    ("294", None),                         // Ts
+    ("294", None),                         // Og
+];
+
+/// Masses of commonly used nuclides as (atomic number, mass number, mass).
+const ISOTOPE_MASSES: &[(usize, u16, &str)] = &[
+    (1, 1, "1.00782503223"),
+    (1, 2, "2.01410177812"),
+    (1, 3, "3.0160492779"),
+    (2, 3, "3.0160293201"),
+    (2, 4, "4.00260325413"),
+    (3, 6, "6.0151228874"),
+    (3, 7, "7.0160034366"),
+    (4, 9, "9.012183065"),
+    (5, 10, "10.01293695"),
+    (5, 11, "11.00930536"),
+    (6, 12, "12"),
+    (6, 13, "13.00335483507"),
+    (6, 14, "14.0032419884"),
+    (7, 14, "14.00307400443"),
+    (7, 15, "15.00010889888"),
+    (8, 16, "15.99491461957"),
+    (8, 17, "16.99913175650"),
+    (8, 18, "17.99915961286"),
+    (9, 18, "18.0009380"),
+    (9, 19, "18.99840316273"),
+    (11, 22, "21.99443741"),
+    (11, 23, "22.9897692820"),
+    (12, 24, "23.985041697"),
+    (14, 28, "27.97692653465"),
+    (15, 31, "30.97376199842"),
+    (15, 32, "31.97390764"),
+    (16, 32, "31.9720711744"),
+    (16, 35, "34.96903231"),
+    (17, 35, "34.968852682"),
+    (17, 37, "36.965902602"),
+    (18, 40, "39.9623831237"),
+    (19, 39, "38.9637064864"),
+    (19, 40, "39.963998166"),
+    (20, 40, "39.962590863"),
+    (26, 56, "55.93493633"),
+    (27, 60, "59.9338163"),
+    (38, 90, "89.90772787"),
+    (43, 99, "98.9062508"),
+    (53, 131, "130.9061263"),
+    (55, 137, "136.90708923"),
+    (82, 206, "205.9744657"),
+    (82, 208, "207.9766525"),
+    (84, 210, "209.9828736"),
+    (86, 222, "222.0175782"),
+    (88, 226, "226.0254103"),
+    (90, 232, "232.0380558"),
+    (92, 234, "234.0409523"),
+    (92, 235, "235.0439301"),
+    (92, 238, "238.0507884"),
+    (94, 239, "239.0521636"),
+    (95, 241, "241.0568293"),
+];
+
+/// Returns the standard atomic weight of an element and its uncertainty, if it has one.
+pub fn standard_atomic_weight(atomic_number: usize) -> (&'static str, Option<&'static str>) {
+    STANDARD_ATOMIC_WEIGHTS[atomic_number - 1]
+}
+
+/// Returns the mass of a nuclide, if it is known.
+pub fn isotope_mass(atomic_number: usize, mass_number: u16) -> Option<&'static str> {
+    ISOTOPE_MASSES
+        .iter()
+        .find(|(z, a, _)| *z == atomic_number && *a == mass_number)
+        .map(|(_, _, mass)| *mass)
+}
@@ -1,32 +1,368 @@
-use crate::representations::dimension::chemical::parse::parse_compound;
-use crate::representations::Element;
+use crate::representations::dimension::chemical::names::compound_from_name;
+use crate::representations::dimension::chemical::parse::{parse_compound, FormulaError};
+use crate::representations::{Element, Float};
 use std::collections::BTreeMap;
 use std::fmt::Display;
 
+/// How far a scaled mole ratio may be from a whole number when finding an empirical formula.
+const EMPIRICAL_TOLERANCE: &str = "0.1";
+const MAX_EMPIRICAL_MULTIPLIER: u32 = 12;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
-pub struct Compound(pub BTreeMap<Element, u16>);
+pub struct Compound {
+    /// Atoms in one formula unit, including those in any adducts
+    elements: BTreeMap<Element, u16>,
+    /// Molecules written after a dot, such as the `5H2O` in `CuSO4·5H2O`
+    adducts: Vec<(u16, Compound)>,
+    /// The formula as it was written, such as `Cu(NO3)2`, without its charge or state. Formulas
+    /// built from counts of atoms have none and are written in a conventional order instead.
+    written: Option<String>,
+    charge: i8,
+    state: Option<PhysicalState>,
+    /// Common or user-defined name, such as `water`
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum PhysicalState {
+    Solid,
+    Liquid,
+    Gas,
+    Aqueous,
+}
+
+impl PhysicalState {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PhysicalState::Solid => "s",
+            PhysicalState::Liquid => "l",
+            PhysicalState::Gas => "g",
+            PhysicalState::Aqueous => "aq",
+        }
+    }
+}
 
 impl Compound {
-    pub fn parse(formula: &str) -> Option<Self> {
+    pub fn new(elements: BTreeMap<Element, u16>) -> Self {
+        Self {
+            elements,
+            adducts: Vec::new(),
+            written: None,
+            charge: 0,
+            state: None,
+            name: None,
+        }
+    }
+
+    pub fn parse(formula: &str) -> Result<Self, FormulaError> {
         parse_compound(formula)
     }
-    pub fn particulate_mass(&self) -> f32 {
-        self.0
+
+    /// Looks up a compound by a common name, such as `water`, `table salt` or `iron`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        compound_from_name(name)
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Adds adducts for display. Their atoms must already be counted in the compound's elements.
+    pub fn with_adducts(mut self, adducts: Vec<(u16, Compound)>) -> Self {
+        self.adducts = adducts;
+        self
+    }
+
+    /// Keeps the formula as it was written for display.
+    pub(super) fn with_written(mut self, formula: &str) -> Self {
+        self.written = Some(formula.to_string());
+        self
+    }
+
+    pub fn with_charge(mut self, charge: i8) -> Self {
+        self.charge = charge;
+        self
+    }
+
+    pub fn with_state(mut self, state: PhysicalState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Returns the number of atoms of each element in one formula unit.
+    pub fn elements(&self) -> &BTreeMap<Element, u16> {
+        &self.elements
+    }
+
+    pub fn charge(&self) -> i8 {
+        self.charge
+    }
+
+    pub fn state(&self) -> Option<PhysicalState> {
+        self.state
+    }
+
+    /// Whether two compounds are the same substance, ignoring how they are written and their
+    /// state.
+    pub fn same_substance(&self, other: &Compound) -> bool {
+        self.elements == other.elements && self.charge == other.charge
+    }
+
+    /// Returns the element if the compound is a single atom, such as `O` or `Fe`.
+    pub fn as_element(&self) -> Option<Element> {
+        match self.elements.iter().collect::<Vec<_>>().as_slice() {
+            [(element, 1)] => Some(**element),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of atoms of an element in the compound.
+    pub fn count(&self, element: &Element) -> u16 {
+        *self.elements.get(element).unwrap_or(&0)
+    }
+
+    /// Finds the simplest formula matching the relative number of moles of each element. Ratios
+    /// are scaled by the smallest whole number that brings every one of them within
+    /// `EMPIRICAL_TOLERANCE` of an integer.
+    pub fn empirical(moles: &[(Element, Float)]) -> Option<Self> {
+        let mut totals: BTreeMap<Element, Float> = BTreeMap::new();
+        for (element, amount) in moles {
+            if *amount <= Float::from(0) {
+                return None;
+            }
+            let total = totals.entry(*element).or_insert(Float::from(0));
+            *total = &*total + amount;
+        }
+
+        let smallest = totals
+            .values()
+            .min_by(|a, b| a.partial_cmp(b).unwrap())?
+            .clone();
+        let ratios = totals
+            .into_iter()
+            .map(|(element, amount)| (element, &amount / &smallest))
+            .collect::<Vec<_>>();
+
+        let tolerance = Float::parse(EMPIRICAL_TOLERANCE).unwrap();
+        (1..=MAX_EMPIRICAL_MULTIPLIER).find_map(|multiplier| {
+            let multiplier = Float::from(multiplier);
+            ratios
+                .iter()
+                .map(|(element, ratio)| {
+                    let scaled = ratio * &multiplier;
+                    let count = scaled.round();
+                    if (&scaled - &count).abs() > tolerance {
+                        return None;
+                    }
+                    Some((*element, u16::try_from(count.to_f64() as u64).ok()?))
+                })
+                .collect::<Option<BTreeMap<_, _>>>()
+                .map(Compound::new)
+        })
+    }
+
+    /// Scales an empirical formula up to the molecular formula with the given molar mass in
+    /// g/mol. Returns `None` if the molar mass is not close to a whole multiple of the formula's.
+    pub fn molecular(&self, molar_mass: &Float) -> Option<Self> {
+        let multiple = molar_mass / &self.particulate_mass();
+        let rounded = multiple.round();
+
+        if rounded < Float::from(1)
+            || (&multiple - &rounded).abs() > Float::parse(EMPIRICAL_TOLERANCE).unwrap()
+        {
+            return None;
+        }
+
+        let multiple = u16::try_from(rounded.to_f64() as u64).ok()?;
+        self.elements
             .iter()
-            .map(|(element, count)| element.atomic_mass() * *count as f32)
-            .sum()
+            .map(|(element, count)| Some((*element, count.checked_mul(multiple)?)))
+            .collect::<Option<BTreeMap<_, _>>>()
+            .map(Compound::new)
     }
-}
 
-impl Display for Compound {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// Returns the molar mass in g/mol.
+    pub fn particulate_mass(&self) -> Float {
+        self.elements
+            .iter()
+            .fold(Float::from(0), |total, (element, count)| {
+                &total + &(&element.atomic_mass() * &Float::from(*count))
+            })
+    }
+
+    /// Returns the standard uncertainty of the molar mass in g/mol, or `None` if an element has
+    /// no standard atomic weight. Atoms of the same element are fully correlated, while different
+    /// elements are independent.
+    pub fn particulate_mass_uncertainty(&self) -> Option<Float> {
+        let variance = self
+            .elements
+            .iter()
+            .map(|(element, count)| {
+                let uncertainty = &element.atomic_mass_uncertainty()? * &Float::from(*count);
+                Some(&uncertainty * &uncertainty)
+            })
+            .try_fold(Float::from(0), |total, variance| Some(&total + &variance?))?;
+        Some(variance.sqrt())
+    }
+
+    /// Returns the atoms written before any adducts.
+    fn base_elements(&self) -> BTreeMap<Element, u16> {
+        let mut elements = self.elements.clone();
+        for (count, adduct) in &self.adducts {
+            for (element, n) in &adduct.elements {
+                if let Some(total) = elements.get_mut(element) {
+                    *total -= n * count;
+                }
+            }
+        }
+        elements.retain(|_, count| *count > 0);
+        elements
+    }
+
+    /// Writes the formula from its counts of atoms, for compounds that weren't parsed.
+    fn conventional_formula(&self) -> String {
         let mut formula = String::new();
-        for (element, count) in &self.0 {
-            formula.push_str(&element.symbol());
+        for (element, count) in ordered_elements(&self.base_elements()) {
+            formula.push_str(&element.notation());
             if *count > 1 {
                 formula.push_str(&count.to_string());
             }
         }
-        write!(f, "{}", formula)
+
+        for (count, adduct) in &self.adducts {
+            formula.push('·');
+            if *count > 1 {
+                formula.push_str(&count.to_string());
+            }
+            formula.push_str(&adduct.to_string());
+        }
+        formula
+    }
+}
+
+/// Returns the elements in the order they are conventionally written: Hill order (carbon,
+/// hydrogen, then alphabetical) for organic compounds, otherwise from least to most
+/// electronegative.
+fn ordered_elements(elements: &BTreeMap<Element, u16>) -> Vec<(&Element, &u16)> {
+    let mut ordered = elements.iter().collect::<Vec<_>>();
+
+    // Isotopes of carbon and hydrogen count as carbon and hydrogen
+    let is_carbon = |element: &Element| element.atomic_number() == 6;
+    let is_hydrogen = |element: &Element| element.atomic_number() == 1;
+
+    if elements.keys().any(is_carbon) && elements.keys().any(is_hydrogen) {
+        ordered.sort_by_key(|(element, _)| {
+            let rank = if is_carbon(element) {
+                0
+            } else if is_hydrogen(element) {
+                1
+            } else {
+                2
+            };
+            (rank, element.symbol(), element.mass_number())
+        });
+    } else {
+        ordered.sort_by(|(a, _), (b, _)| {
+            a.electronegativity()
+                .total_cmp(&b.electronegativity())
+                .then(a.atomic_number().cmp(&b.atomic_number()))
+                .then(a.mass_number().cmp(&b.mass_number()))
+        });
+    }
+
+    ordered
+}
+
+impl Display for Compound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut formula = match &self.written {
+            Some(written) => written.clone(),
+            None => self.conventional_formula(),
+        };
+
+        // Monatomic ions are written without a caret, as in `Fe3+`
+        let sign = if self.charge > 0 { '+' } else { '-' };
+        match self.charge.unsigned_abs() {
+            0 => {}
+            1 => formula.push(sign),
+            charge if self.as_element().is_some() => {
+                formula.push_str(&format!("{}{}", charge, sign))
+            }
+            charge => formula.push_str(&format!("^{}{}", charge, sign)),
+        }
+
+        if let Some(state) = self.state {
+            formula.push_str(&format!("({})", state.symbol()));
+        }
+
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", name, formula),
+            None => write!(f, "{}", formula),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moles(amounts: &[(&str, &str)]) -> Vec<(Element, Float)> {
+        amounts
+            .iter()
+            .map(|(symbol, amount)| {
+                (
+                    Element::from_symbol(symbol).unwrap(),
+                    Float::parse(amount).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empirical_scales_to_whole_numbers() {
+        let compound = Compound::empirical(&moles(&[("C", "3.33"), ("H", "6.65"), ("O", "3.33")]));
+        assert_eq!(compound.unwrap().to_string(), "CH2O");
+
+        // 1 : 1.5 needs to be doubled
+        let compound = Compound::empirical(&moles(&[("Fe", "0.716"), ("O", "1.07")]));
+        assert_eq!(compound.unwrap().to_string(), "Fe2O3");
+
+        assert_eq!(Compound::empirical(&moles(&[("C", "1"), ("H", "0")])), None);
+    }
+
+    #[test]
+    fn parsed_formulas_display_as_written() {
+        for formula in [
+            "NH3",
+            "NaOH",
+            "Cu(NO3)2",
+            "K4[Fe(CN)6]",
+            "CuSO4·5H2O",
+            "C2H5OH",
+        ] {
+            assert_eq!(Compound::parse(formula).unwrap().to_string(), formula);
+        }
+        assert_eq!(
+            Compound::parse("CuSO4*5H2O").unwrap().to_string(),
+            "CuSO4·5H2O"
+        );
+
+        let ammonia = Compound::parse("NH3").unwrap().elements().clone();
+        assert_eq!(Compound::new(ammonia).to_string(), "H3N");
+    }
+
+    #[test]
+    fn molecular_multiplies_empirical_formula() {
+        let empirical = Compound::parse("CH2O").unwrap();
+        assert_eq!(
+            empirical.molecular(&Float::from(180)).unwrap().to_string(),
+            "C6H12O6"
+        );
+        assert_eq!(empirical.molecular(&Float::from(45)), None);
     }
 }
@@ -1,13 +1,24 @@
+use crate::representations::dimension::chemical::atomic_mass::{
+    isotope_mass, standard_atomic_weight,
+};
+use crate::representations::dimension::chemical::half_life::half_life;
+use crate::representations::{Float, Time};
 use periodic_table_on_an_enum::Element as PElement;
 
+/// An element, or a specific isotope of one when a mass number is given.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
 pub struct Element {
     element: PElement,
+    mass_number: Option<u16>,
 }
 
 impl Element {
+    /// Finds the element with the symbol `sym`. The elements are searched in order, since
+    /// `PElement::from_symbol` panics for symbols that sort before `Ac`.
     pub fn from_symbol(sym: &str) -> Option<Element> {
-        PElement::from_symbol(sym).map(Element::from_pt_element)
+        (1..=118)
+            .filter_map(Element::from_atomic_number)
+            .find(|element| element.symbol() == sym)
     }
 
     pub fn from_atomic_number(z: usize) -> Option<Element> {
@@ -15,14 +26,146 @@ impl Element {
     }
 
     fn from_pt_element(element: PElement) -> Element {
-        Element { element }
+        Element {
+            element,
+            mass_number: None,
+        }
+    }
+
+    /// Returns the isotope of this element with the given mass number, or `None` if its mass is
+    /// not known.
+    pub fn isotope(&self, mass_number: u16) -> Option<Element> {
+        isotope_mass(self.atomic_number(), mass_number)?;
+        Some(Element {
+            element: self.element,
+            mass_number: Some(mass_number),
+        })
+    }
+
+    pub fn mass_number(&self) -> Option<u16> {
+        self.mass_number
     }
 
     pub fn symbol(&self) -> &'static str {
         self.element.get_symbol()
     }
 
-    pub fn atomic_mass(&self) -> f32 {
-        self.element.get_atomic_mass()
+    /// Returns the symbol as written in a formula, with isotopes written like `¹⁴C`, `D` or `T`.
+    pub fn notation(&self) -> String {
+        match (self.atomic_number(), self.mass_number) {
+            (_, None) => self.symbol().to_string(),
+            (1, Some(2)) => "D".to_string(),
+            (1, Some(3)) => "T".to_string(),
+            (_, Some(mass_number)) => {
+                let superscript = mass_number
+                    .to_string()
+                    .chars()
+                    .map(|digit| SUPERSCRIPT_DIGITS[digit.to_digit(10).unwrap() as usize])
+                    .collect::<String>();
+                superscript + self.symbol()
+            }
+        }
+    }
+
+    /// Returns the atomic mass in g/mol: the isotope's mass if a mass number was given, otherwise
+    /// the standard atomic weight.
+    pub fn atomic_mass(&self) -> Float {
+        let mass = match self.mass_number {
+            Some(mass_number) => isotope_mass(self.atomic_number(), mass_number).unwrap(),
+            None => standard_atomic_weight(self.atomic_number()).0,
+        };
+        Float::parse(mass).unwrap()
+    }
+
+    /// Returns the standard uncertainty of the atomic mass in g/mol. Isotope masses are treated
+    /// as exact.
+    pub fn atomic_mass_uncertainty(&self) -> Option<Float> {
+        match self.mass_number {
+            Some(_) => Some(Float::from(0)),
+            None => standard_atomic_weight(self.atomic_number())
+                .1
+                .map(|uncertainty| Float::parse(uncertainty).unwrap()),
+        }
+    }
+
+    /// Returns the half-life of this isotope and the unit it is given in, or `None` if this is
+    /// not an isotope or its half-life is not known.
+    pub fn half_life(&self) -> Option<(Float, Time)> {
+        let (half_life, unit) = half_life(self.atomic_number(), self.mass_number?)?;
+        Some((Float::parse(half_life).unwrap(), unit))
+    }
+
+    pub fn atomic_number(&self) -> usize {
+        self.element.get_atomic_number()
+    }
+
+    /// Returns the Pauling electronegativity, or 0 if it is not known.
+    pub fn electronegativity(&self) -> f32 {
+        self.element.get_electronegativity()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.element.get_name()
+    }
+
+    /// Returns the atomic radius in picometers, or 0 if it is not known.
+    pub fn atomic_radius(&self) -> u16 {
+        self.element.get_atomic_radius()
+    }
+}
+
+pub const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_mass_keeps_every_digit() {
+        let sodium = Element::from_symbol("Na").unwrap();
+        assert_eq!(sodium.atomic_mass(), Float::parse("22.98976928").unwrap());
+        assert_eq!(
+            sodium.atomic_mass_uncertainty(),
+            Some(Float::parse("0.00000002").unwrap())
+        );
+
+        let technetium = Element::from_symbol("Tc").unwrap();
+        assert_eq!(technetium.atomic_mass_uncertainty(), None);
+    }
+
+    #[test]
+    fn from_symbol_rejects_unknown_symbols() {
+        assert_eq!(Element::from_symbol("Ac").unwrap().atomic_number(), 89);
+        assert_eq!(Element::from_symbol("Zr").unwrap().atomic_number(), 40);
+        for symbol in ["A", "AB", "Aa", "Ab", "Zz", ""] {
+            assert_eq!(Element::from_symbol(symbol), None);
+        }
+    }
+
+    #[test]
+    fn isotopes_use_their_own_mass() {
+        let carbon = Element::from_symbol("C").unwrap();
+        let carbon_14 = carbon.isotope(14).unwrap();
+        assert_ne!(carbon, carbon_14);
+        assert_eq!(carbon_14.notation(), "¹⁴C");
+        assert_eq!(
+            carbon_14.atomic_mass(),
+            Float::parse("14.0032419884").unwrap()
+        );
+        assert_eq!(carbon.isotope(99), None);
+
+        let deuterium = Element::from_symbol("H").unwrap().isotope(2).unwrap();
+        assert_eq!(deuterium.notation(), "D");
+    }
+
+    #[test]
+    fn half_life_is_known_for_common_radioisotopes() {
+        let carbon = Element::from_symbol("C").unwrap();
+        assert_eq!(
+            carbon.isotope(14).unwrap().half_life(),
+            Some((Float::from(5730), Time::Year))
+        );
+        assert_eq!(carbon.isotope(12).unwrap().half_life(), None);
+        assert_eq!(carbon.half_life(), None);
     }
 }
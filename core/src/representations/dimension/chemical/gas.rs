@@ -0,0 +1,123 @@
+use crate::ratio;
+use crate::representations::Float;
+
+/// Temperature and pressure used when treating a compound as an ideal gas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasConditions {
+    /// Absolute temperature in kelvins
+    temperature: Float,
+    /// Pressure in pascals
+    pressure: Float,
+}
+
+impl Default for GasConditions {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl GasConditions {
+    pub fn new(temperature: Float, pressure: Float) -> Self {
+        Self {
+            temperature,
+            pressure,
+        }
+    }
+
+    /// Standard temperature and pressure as used in most chemistry courses (0 °C, 1 atm).
+    pub fn standard() -> Self {
+        Self::new(ratio!(27315 / 100), ratio!(101325 / 1))
+    }
+
+    /// Standard ambient temperature and pressure (25 °C, 100 kPa).
+    pub fn ambient() -> Self {
+        Self::new(ratio!(29815 / 100), ratio!(100000 / 1))
+    }
+
+    pub fn temperature(&self) -> Float {
+        self.temperature.clone()
+    }
+
+    pub fn pressure(&self) -> Float {
+        self.pressure.clone()
+    }
+
+    /// Sets the temperature from a value in the given unit, returning `None` if the unit is
+    /// unknown or the resulting temperature is not above absolute zero.
+    pub fn with_temperature(mut self, value: &Float, unit: &str) -> Option<Self> {
+        let kelvin = match unit {
+            "K" | "kelvin" | "kelvins" => value.clone(),
+            "C" | "°C" | "degC" | "celsius" => value + &ratio!(27315 / 100),
+            "F" | "°F" | "degF" | "fahrenheit" => {
+                &(&(value - &Float::from(32)) * &ratio!(5 / 9)) + &ratio!(27315 / 100)
+            }
+            "R" | "°R" | "degR" | "rankine" => value * &ratio!(5 / 9),
+            _ => return None,
+        };
+
+        if kelvin <= Float::from(0) {
+            return None;
+        }
+
+        self.temperature = kelvin;
+        Some(self)
+    }
+
+    /// Sets the pressure from a value in the given unit, returning `None` if the unit is
+    /// unknown or the resulting pressure is not positive.
+    pub fn with_pressure(mut self, value: &Float, unit: &str) -> Option<Self> {
+        let ratio = match unit {
+            "Pa" | "pascal" | "pascals" => ratio!(1 / 1),
+            "hPa" => ratio!(100 / 1),
+            "kPa" => ratio!(1000 / 1),
+            "MPa" => ratio!(1000000 / 1),
+            "bar" | "bars" => ratio!(100000 / 1),
+            "mbar" => ratio!(100 / 1),
+            "atm" => ratio!(101325 / 1),
+            "torr" | "Torr" => ratio!(101325 / 760),
+            "mmHg" => ratio!(101325 / 760),
+            "psi" => ratio!(6894757293168 / 1000000000),
+            _ => return None,
+        };
+
+        let pascals = value * &ratio;
+
+        if pascals <= Float::from(0) {
+            return None;
+        }
+
+        self.pressure = pascals;
+        Some(self)
+    }
+
+    /// Returns the volume of one mole of an ideal gas at these conditions, in cubic meters.
+    pub fn molar_volume(&self) -> Float {
+        &(&gas_constant() * &self.temperature) / &self.pressure
+    }
+}
+
+/// Returns the molar gas constant R in J/(mol*K).
+pub fn gas_constant() -> Float {
+    ratio!(831446261815324 / 100000000000000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn molar_volume_at_stp_is_22_4_liters() {
+        let liters = &GasConditions::standard().molar_volume() * &Float::from(1000);
+        assert!((&liters - &ratio!(22413969 / 1000000)).abs() < ratio!(1 / 1000000));
+    }
+
+    #[test]
+    fn with_temperature_rejects_absolute_zero() {
+        assert!(GasConditions::standard()
+            .with_temperature(&Float::from(-300), "C")
+            .is_none());
+        assert!(GasConditions::standard()
+            .with_temperature(&Float::from(25), "C")
+            .is_some());
+    }
+}
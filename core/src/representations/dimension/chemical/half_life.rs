@@ -0,0 +1,34 @@
+use crate::representations::Time;
+
+/// Half-lives of commonly used radionuclides as (atomic number, mass number, half-life, unit).
+const HALF_LIVES: &[(usize, u16, &str, Time)] = &[
+    (1, 3, "12.32", Time::Year),
+    (6, 14, "5730", Time::Year),
+    (9, 18, "109.77", Time::Minute),
+    (11, 22, "2.6018", Time::Year),
+    (15, 32, "14.268", Time::Day),
+    (16, 35, "87.37", Time::Day),
+    (19, 40, "1.248E9", Time::Year),
+    (27, 60, "5.2714", Time::Year),
+    (38, 90, "28.79", Time::Year),
+    (43, 99, "2.111E5", Time::Year),
+    (53, 131, "8.0252", Time::Day),
+    (55, 137, "30.08", Time::Year),
+    (84, 210, "138.376", Time::Day),
+    (86, 222, "3.8235", Time::Day),
+    (88, 226, "1600", Time::Year),
+    (90, 232, "1.40E10", Time::Year),
+    (92, 234, "2.455E5", Time::Year),
+    (92, 235, "7.04E8", Time::Year),
+    (92, 238, "4.468E9", Time::Year),
+    (94, 239, "24110", Time::Year),
+    (95, 241, "432.2", Time::Year),
+];
+
+/// Returns the half-life of a nuclide and the unit it is given in, if it is known.
+pub fn half_life(atomic_number: usize, mass_number: u16) -> Option<(&'static str, Time)> {
+    HALF_LIVES
+        .iter()
+        .find(|(z, a, _, _)| *z == atomic_number && *a == mass_number)
+        .map(|(_, _, half_life, unit)| (*half_life, *unit))
+}
@@ -1,6 +1,15 @@
+mod atomic_mass;
 mod compound;
 mod element;
+mod gas;
+mod half_life;
+mod names;
 mod parse;
+mod reaction;
 
-pub use compound::Compound;
+pub use compound::{Compound, PhysicalState};
 pub use element::Element;
+pub use gas::{gas_constant, GasConditions};
+pub use names::normalize_name;
+pub use parse::FormulaError;
+pub use reaction::{BalanceError, Reaction};
@@ -0,0 +1,102 @@
+use crate::representations::{Compound, Element};
+
+/// Common names for compounds, with alternative names sharing a formula.
+const COMMON_NAMES: &[(&[&str], &str)] = &[
+    (&["water"], "H2O"),
+    (&["heavy water"], "D2O"),
+    (&["hydrogen peroxide"], "H2O2"),
+    (&["hydrogen", "hydrogen gas"], "H2"),
+    (&["oxygen", "oxygen gas"], "O2"),
+    (&["nitrogen", "nitrogen gas"], "N2"),
+    (&["ozone"], "O3"),
+    (&["chlorine", "chlorine gas"], "Cl2"),
+    (&["carbon dioxide"], "CO2"),
+    (&["carbon monoxide"], "CO"),
+    (&["nitrous oxide", "laughing gas"], "N2O"),
+    (&["ammonia"], "NH3"),
+    (&["methane", "natural gas"], "CH4"),
+    (&["ethane"], "C2H6"),
+    (&["propane"], "C3H8"),
+    (&["butane"], "C4H10"),
+    (&["octane"], "C8H18"),
+    (&["benzene"], "C6H6"),
+    (&["toluene"], "C7H8"),
+    (&["methanol", "methyl alcohol"], "CH3OH"),
+    (&["ethanol", "ethyl alcohol", "alcohol"], "C2H5OH"),
+    (&["isopropanol", "isopropyl alcohol"], "C3H7OH"),
+    (&["acetone"], "C3H6O"),
+    (&["acetic acid"], "CH3COOH"),
+    (&["glucose", "dextrose"], "C6H12O6"),
+    (&["fructose"], "C6H12O6"),
+    (&["sucrose", "sugar", "table sugar"], "C12H22O11"),
+    (&["caffeine"], "C8H10N4O2"),
+    (&["urea"], "CH4N2O"),
+    (&["table salt", "salt", "sodium chloride"], "NaCl"),
+    (&["baking soda", "sodium bicarbonate"], "NaHCO3"),
+    (&["washing soda", "sodium carbonate"], "Na2CO3"),
+    (&["lye", "caustic soda", "sodium hydroxide"], "NaOH"),
+    (&["caustic potash", "potassium hydroxide"], "KOH"),
+    (&["bleach", "sodium hypochlorite"], "NaClO"),
+    (&["chalk", "limestone", "calcium carbonate"], "CaCO3"),
+    (&["quicklime", "calcium oxide"], "CaO"),
+    (&["slaked lime", "calcium hydroxide"], "Ca(OH)2"),
+    (&["gypsum"], "CaSO4·2H2O"),
+    (&["epsom salt"], "MgSO4·7H2O"),
+    (&["rust"], "Fe2O3"),
+    (&["silica", "quartz"], "SiO2"),
+    (&["hydrochloric acid"], "HCl"),
+    (&["sulfuric acid", "sulphuric acid"], "H2SO4"),
+    (&["nitric acid"], "HNO3"),
+    (&["phosphoric acid"], "H3PO4"),
+];
+
+/// Normalizes a compound name so that case, underscores and repeated spaces don't matter.
+pub fn normalize_name(name: &str) -> String {
+    name.split([' ', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Looks up a compound by its common name, such as `water` or `table salt`, or an element by its
+/// name, such as `iron`.
+pub fn compound_from_name(name: &str) -> Option<Compound> {
+    let name = normalize_name(name);
+
+    if let Some((_, formula)) = COMMON_NAMES
+        .iter()
+        .find(|(names, _)| names.contains(&name.as_str()))
+    {
+        return Some(Compound::parse(formula).ok()?.with_name(&name));
+    }
+
+    let element = (1..=118)
+        .filter_map(Element::from_atomic_number)
+        .find(|element| element.name().to_lowercase() == name)?;
+    Some(Compound::parse(element.symbol()).ok()?.with_name(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compound_from_name_finds_common_names_and_elements() {
+        let salt = compound_from_name("Table_Salt").unwrap();
+        assert!(salt.same_substance(&Compound::parse("NaCl").unwrap()));
+        assert_eq!(salt.to_string(), "table salt (NaCl)");
+
+        let iron = compound_from_name("iron").unwrap();
+        assert_eq!(iron.as_element(), Element::from_symbol("Fe"));
+
+        assert_eq!(compound_from_name("unobtainium"), None);
+    }
+
+    #[test]
+    fn common_names_have_valid_formulas() {
+        for (names, formula) in COMMON_NAMES {
+            assert!(Compound::parse(formula).is_ok(), "{:?}", names);
+        }
+    }
+}
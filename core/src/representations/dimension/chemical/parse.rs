@@ -1,65 +1,350 @@
+use crate::representations::dimension::chemical::compound::PhysicalState;
+use crate::representations::dimension::chemical::element::SUPERSCRIPT_DIGITS;
 use crate::representations::Compound;
 use crate::representations::Element;
 use std::collections::BTreeMap;
+use std::fmt::Display;
 
-pub fn parse_compound(formula: &str) -> Option<Compound> {
-    let formula = formula.chars().collect::<Vec<_>>();
-    let mut stack = vec![];
-    stack.push(BTreeMap::new());
-    let mut i = 0;
-    while i < formula.len() {
-        match formula[i] {
-            '(' => {
-                i += 1;
-                stack.push(BTreeMap::new());
+/// An error in a chemical formula, with the byte range of the offending part of the formula.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormulaError {
+    message: String,
+    start: usize,
+    end: usize,
+}
+
+impl FormulaError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
+
+impl Display for FormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Parses formulas such as `H2O`, `Ca(OH)2`, `K4[Fe(CN)6]`, `CuSO4·5H2O`, `SO4^2-`, `Fe3+` and
+/// `NaCl(aq)`.
+pub fn parse_compound(formula: &str) -> Result<Compound, FormulaError> {
+    FormulaParser::new(formula).parse()
+}
+
+type Counts = BTreeMap<Element, u32>;
+
+struct FormulaParser<'a> {
+    formula: &'a str,
+    chars: Vec<(usize, char)>,
+    i: usize,
+}
+
+impl<'a> FormulaParser<'a> {
+    fn new(formula: &'a str) -> Self {
+        Self {
+            formula,
+            chars: formula.char_indices().collect(),
+            i: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Compound, FormulaError> {
+        let mut elements = self.parse_group(None)?;
+        if elements.is_empty() {
+            return Err(self.error_here("Expected an element"));
+        }
+
+        let mut adducts = Vec::new();
+        while let Some('·' | '•' | '*') = self.peek() {
+            let separator = self.i;
+            self.i += 1;
+            let count = self.parse_count()?.unwrap_or(1);
+            let adduct = self.parse_group(None)?;
+            if adduct.is_empty() {
+                return Err(self.error(
+                    "Expected a formula after the hydrate separator",
+                    separator,
+                    self.i,
+                ));
+            }
+
+            for (element, n) in &adduct {
+                *elements.entry(*element).or_insert(0) += n * count as u32;
             }
-            ')' => {
-                let top = stack.pop().unwrap();
-                i += 1;
-                let i_start = i;
-                while i < formula.len() && formula[i].is_ascii_digit() {
-                    i += 1;
+            adducts.push((count, Compound::new(self.to_u16(adduct, separator)?)));
+        }
+        // Hydrates are displayed with a middle dot however they were written
+        let written = self.slice(0, self.i).replace(['•', '*'], "·");
+
+        let charge = self.parse_charge()?;
+        let state = self.parse_state()?;
+
+        if let Some(c) = self.peek() {
+            return Err(match c {
+                ')' | ']' => self.error(format!("Unmatched '{}'", c), self.i, self.i + 1),
+                _ => self.error(format!("Unexpected '{}'", c), self.i, self.i + 1),
+            });
+        }
+
+        let mut compound = Compound::new(self.to_u16(elements, 0)?)
+            .with_adducts(adducts)
+            .with_written(&written)
+            .with_charge(charge);
+        if let Some(state) = state {
+            compound = compound.with_state(state);
+        }
+        Ok(compound)
+    }
+
+    /// Parses elements and bracketed groups until something else is reached.
+    fn parse_group(&mut self, closer: Option<char>) -> Result<Counts, FormulaError> {
+        let mut counts = Counts::new();
+
+        loop {
+            match self.peek() {
+                Some(c) if c.is_ascii_uppercase() || superscript_digit(c).is_some() => {
+                    let element = self.parse_element()?;
+                    let count = self.parse_count()?.unwrap_or(1);
+                    *counts.entry(element).or_insert(0) += count as u32;
+                }
+                // A lowercase letter after a parenthesis is a state symbol such as `(aq)`
+                Some('(') if self.peek_at(1).is_some_and(|c| c.is_ascii_lowercase()) => break,
+                Some(open @ ('(' | '[')) => {
+                    let start = self.i;
+                    let close = if open == '(' { ')' } else { ']' };
+                    self.i += 1;
+                    let inner = self.parse_group(Some(close))?;
+                    if self.peek() != Some(close) {
+                        return Err(self.error(format!("Unclosed '{}'", open), start, start + 1));
+                    }
+                    self.i += 1;
+                    if inner.is_empty() {
+                        return Err(self.error("Empty group", start, self.i));
+                    }
+                    let count = self.parse_count()?.unwrap_or(1);
+                    for (element, n) in inner {
+                        *counts.entry(element).or_insert(0) += n * count as u32;
+                    }
                 }
-                let multiplier = formula[i_start..i]
-                    .iter()
-                    .collect::<String>()
-                    .parse::<i32>()
-                    .unwrap_or(0)
-                    .max(1);
-                for (k, v) in top {
-                    *stack.last_mut()?.entry(k).or_insert(0) += v * multiplier;
+                Some(c) if Some(c) != closer && matches!(c, ')' | ']') => {
+                    return Err(self.error(format!("Unmatched '{}'", c), self.i, self.i + 1));
                 }
+                _ => break,
+            }
+        }
+
+        Ok(counts)
+    }
+
+    /// Parses an element symbol, including isotopes written as `¹⁴C`, `C-14`, `D` or `T`.
+    fn parse_element(&mut self) -> Result<Element, FormulaError> {
+        let start = self.i;
+
+        let mut mass_number = None;
+        while let Some(digit) = self.peek().and_then(superscript_digit) {
+            mass_number = Some(mass_number.unwrap_or(0) * 10 + digit);
+            self.i += 1;
+        }
+
+        let symbol_start = self.i;
+        if !self.peek().is_some_and(|c| c.is_ascii_uppercase()) {
+            return Err(self.error_here("Expected an element after the mass number"));
+        }
+        self.i += 1;
+        while self.peek().is_some_and(|c| c.is_ascii_lowercase()) {
+            self.i += 1;
+        }
+        let symbol = self.slice(symbol_start, self.i);
+
+        if self.peek() == Some('-') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            let digits_start = self.i + 1;
+            self.i = self.digits_end(digits_start);
+            if mass_number.is_some() {
+                return Err(self.error("Mass number is given twice", start, self.i));
+            }
+            mass_number = Some(self.slice(digits_start, self.i).parse().unwrap_or(u32::MAX));
+        }
+
+        let (element, mass_number) = match (symbol, mass_number) {
+            ("D", None) => (Element::from_symbol("H"), Some(2)),
+            ("T", None) => (Element::from_symbol("H"), Some(3)),
+            _ => (Element::from_symbol(symbol), mass_number),
+        };
+        let Some(element) = element else {
+            return Err(self.error(
+                format!("Unknown element '{}'", symbol),
+                symbol_start,
+                symbol_start + symbol.chars().count(),
+            ));
+        };
+
+        match mass_number {
+            None => Ok(element),
+            Some(mass_number) => u16::try_from(mass_number)
+                .ok()
+                .and_then(|mass_number| element.isotope(mass_number))
+                .ok_or_else(|| {
+                    self.error(
+                        format!("No mass is known for {}-{}", element.symbol(), mass_number),
+                        start,
+                        self.i,
+                    )
+                }),
+        }
+    }
+
+    /// Parses a count after an element or group. Digits between a single element and a trailing
+    /// sign, as in `Fe3+`, are a charge instead. Polyatomic ions such as `NH4+` and `MnO4-` are
+    /// read with a charge of one, so other charges need a caret, as in `SO4^2-`.
+    fn parse_count(&mut self) -> Result<Option<u16>, FormulaError> {
+        let start = self.i;
+        let end = self.digits_end(start);
+        if end == start || (self.is_trailing_sign(end) && self.is_single_element(start)) {
+            return Ok(None);
+        }
+        self.i = end;
+
+        match self.slice(start, end).parse::<u16>() {
+            Ok(0) => Err(self.error("Count must be at least 1", start, end)),
+            Ok(count) => Ok(Some(count)),
+            Err(_) => Err(self.error("Count is too large", start, end)),
+        }
+    }
+
+    fn parse_charge(&mut self) -> Result<i8, FormulaError> {
+        let start = self.i;
+        let caret = self.peek() == Some('^');
+        if caret {
+            self.i += 1;
+        }
+
+        let digits_start = self.i;
+        self.i = self.digits_end(digits_start);
+        let magnitude = if self.i == digits_start {
+            1
+        } else {
+            match self.slice(digits_start, self.i).parse::<i8>() {
+                Ok(magnitude) => magnitude,
+                Err(_) => return Err(self.error("Charge is too large", start, self.i)),
             }
+        };
+
+        match self.peek() {
+            Some('+') => {
+                self.i += 1;
+                Ok(magnitude)
+            }
+            Some('-' | '−') => {
+                self.i += 1;
+                Ok(-magnitude)
+            }
+            _ if caret => Err(self.error("Expected '+' or '-' after '^'", start, self.i)),
             _ => {
-                let i_start = i;
-                i += 1;
-                while i < formula.len() && formula[i].is_lowercase() {
-                    i += 1;
-                }
-                let name = formula[i_start..i].iter().collect::<String>();
-                let i_start = i;
-                while i < formula.len() && formula[i].is_ascii_digit() {
-                    i += 1;
-                }
-                let multiplier = formula[i_start..i]
-                    .iter()
-                    .collect::<String>()
-                    .parse::<i32>()
-                    .unwrap_or(0)
-                    .max(1);
-                *stack.last_mut()?.entry(name).or_insert(0) += multiplier;
+                self.i = start;
+                Ok(0)
             }
         }
     }
 
-    let count = stack.pop()?;
-    let mut new_count = BTreeMap::new();
+    fn parse_state(&mut self) -> Result<Option<PhysicalState>, FormulaError> {
+        if self.peek() != Some('(') {
+            return Ok(None);
+        }
+
+        let start = self.i;
+        let Some(end) = (start..self.chars.len()).find(|&i| self.chars[i].1 == ')') else {
+            return Err(self.error("Unclosed '('", start, start + 1));
+        };
+        self.i = end + 1;
+
+        match self.slice(start + 1, end) {
+            "s" => Ok(Some(PhysicalState::Solid)),
+            "l" => Ok(Some(PhysicalState::Liquid)),
+            "g" => Ok(Some(PhysicalState::Gas)),
+            "aq" => Ok(Some(PhysicalState::Aqueous)),
+            state => Err(self.error(
+                format!(
+                    "Unknown state '({})', expected (s), (l), (g) or (aq)",
+                    state
+                ),
+                start,
+                self.i,
+            )),
+        }
+    }
+
+    fn to_u16(&self, counts: Counts, start: usize) -> Result<BTreeMap<Element, u16>, FormulaError> {
+        counts
+            .into_iter()
+            .map(|(element, count)| Ok((element, u16::try_from(count).map_err(|_| ())?)))
+            .collect::<Result<_, ()>>()
+            .map_err(|_| self.error("Formula has too many atoms", start, self.chars.len()))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.i + offset).map(|(_, c)| *c)
+    }
+
+    fn digits_end(&self, start: usize) -> usize {
+        (start..self.chars.len())
+            .find(|&i| !self.chars[i].1.is_ascii_digit())
+            .unwrap_or(self.chars.len())
+    }
+
+    /// Whether the character at `i` is a sign ending the formula, optionally before a state.
+    fn is_trailing_sign(&self, i: usize) -> bool {
+        matches!(self.chars.get(i), Some((_, '+' | '-' | '−')))
+            && matches!(self.chars.get(i + 1), None | Some((_, '(')))
+    }
+
+    /// Whether everything before `i` is a single element symbol.
+    fn is_single_element(&self, i: usize) -> bool {
+        i > 0
+            && self.chars[0].1.is_ascii_uppercase()
+            && self.chars[1..i].iter().all(|(_, c)| c.is_ascii_lowercase())
+    }
+
+    fn offset(&self, i: usize) -> usize {
+        self.chars
+            .get(i)
+            .map(|(offset, _)| *offset)
+            .unwrap_or(self.formula.len())
+    }
+
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        &self.formula[self.offset(start)..self.offset(end)]
+    }
 
-    for (k, v) in count {
-        *new_count.entry(Element::from_symbol(&k)?).or_insert(0) += v as u16;
+    fn error(&self, message: impl Into<String>, start: usize, end: usize) -> FormulaError {
+        FormulaError {
+            message: message.into(),
+            start: self.offset(start),
+            end: self.offset(end.max(start + 1)),
+        }
     }
 
-    Some(Compound(new_count))
+    fn error_here(&self, message: &str) -> FormulaError {
+        self.error(message, self.i, self.i + 1)
+    }
+}
+
+fn superscript_digit(c: char) -> Option<u32> {
+    SUPERSCRIPT_DIGITS
+        .iter()
+        .position(|digit| *digit == c)
+        .map(|digit| digit as u32)
 }
 
 #[cfg(test)]
@@ -67,6 +352,14 @@ mod tests {
     use super::*;
     use std::collections::BTreeMap;
 
+    fn counts(compound: &Compound) -> BTreeMap<String, u16> {
+        compound
+            .elements()
+            .iter()
+            .map(|(element, count)| (element.symbol().to_string(), *count))
+            .collect()
+    }
+
     #[test]
     fn parse_compound_returns_correct_compound_for_valid_formula() {
         let formula = "H2O";
@@ -74,7 +367,7 @@ mod tests {
         let mut expected = BTreeMap::new();
         expected.insert(Element::from_symbol("H").unwrap(), 2);
         expected.insert(Element::from_symbol("O").unwrap(), 1);
-        assert_eq!(compound.0, expected);
+        assert_eq!(*compound.elements(), expected);
     }
 
     #[test]
@@ -86,13 +379,94 @@ mod tests {
         expected.insert(Element::from_symbol("Ca").unwrap(), 2);
         expected.insert(Element::from_symbol("O").unwrap(), 6);
         expected.insert(Element::from_symbol("H").unwrap(), 12);
-        assert_eq!(compound.0, expected);
+        assert_eq!(*compound.elements(), expected);
+    }
+
+    #[test]
+    fn parse_compound_returns_error_for_invalid_formula() {
+        let error = parse_compound("H2O)2").unwrap_err();
+        assert_eq!((error.start(), error.end()), (3, 4));
+
+        let error = parse_compound("Ca(OH2").unwrap_err();
+        assert_eq!((error.start(), error.end()), (2, 3));
+
+        let error = parse_compound("HXe2Qz").unwrap_err();
+        assert_eq!(error.message(), "Unknown element 'Qz'");
+        assert_eq!((error.start(), error.end()), (4, 6));
+
+        // Symbols that sort before `Ac` are unknown elements too
+        let error = parse_compound("NaAb").unwrap_err();
+        assert_eq!(error.message(), "Unknown element 'Ab'");
+        assert_eq!((error.start(), error.end()), (2, 4));
+        assert_eq!(
+            parse_compound("A").unwrap_err().message(),
+            "Unknown element 'A'"
+        );
+
+        assert!(parse_compound("").is_err());
+        assert!(parse_compound("NaCl(xyz)").is_err());
     }
 
     #[test]
-    fn parse_compound_returns_none_for_invalid_formula() {
-        let formula = "H2O)2";
-        let compound = parse_compound(formula);
-        assert_eq!(compound, None);
+    fn parse_compound_handles_hydrates_and_brackets() {
+        let hydrate = parse_compound("CuSO4·5H2O").unwrap();
+        assert_eq!(hydrate, parse_compound("CuSO4*5H2O").unwrap());
+        assert_eq!(hydrate.count(&Element::from_symbol("O").unwrap()), 9);
+        assert_eq!(hydrate.to_string(), "CuSO4·5H2O");
+
+        let compound = parse_compound("K4[Fe(CN)6]").unwrap();
+        assert_eq!(
+            counts(&compound),
+            BTreeMap::from([
+                ("C".to_string(), 6),
+                ("Fe".to_string(), 1),
+                ("K".to_string(), 4),
+                ("N".to_string(), 6)
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_compound_handles_isotopes() {
+        let carbon_14 = Element::from_symbol("C").unwrap().isotope(14).unwrap();
+        assert_eq!(
+            parse_compound("C-14").unwrap().as_element(),
+            Some(carbon_14)
+        );
+        assert_eq!(parse_compound("¹⁴CO2").unwrap().count(&carbon_14), 1);
+
+        let heavy_water = parse_compound("D2O").unwrap();
+        assert_eq!(heavy_water.to_string(), "D2O");
+        assert!(parse_compound("²H2O").unwrap().same_substance(&heavy_water));
+
+        let uranium = parse_compound("²³⁵U").unwrap();
+        assert_eq!(uranium.to_string(), "²³⁵U");
+
+        let error = parse_compound("HC-99").unwrap_err();
+        assert_eq!(error.message(), "No mass is known for C-99");
+        assert_eq!((error.start(), error.end()), (1, 5));
+    }
+
+    #[test]
+    fn parse_compound_handles_charges_and_states() {
+        let sulfate = parse_compound("SO4^2-").unwrap();
+        assert_eq!(sulfate.charge(), -2);
+        assert_eq!(sulfate.count(&Element::from_symbol("O").unwrap()), 4);
+        assert_eq!(sulfate.to_string(), "SO4^2-");
+
+        let iron = parse_compound("Fe3+").unwrap();
+        assert_eq!(iron.charge(), 3);
+        assert_eq!(iron.count(&Element::from_symbol("Fe").unwrap()), 1);
+
+        let permanganate = parse_compound("MnO4-").unwrap();
+        assert_eq!(permanganate.charge(), -1);
+        assert_eq!(permanganate.count(&Element::from_symbol("O").unwrap()), 4);
+
+        let chloride = parse_compound("Cl-(aq)").unwrap();
+        assert_eq!(chloride.charge(), -1);
+        assert_eq!(chloride.state(), Some(PhysicalState::Aqueous));
+        assert_eq!(chloride.to_string(), "Cl-(aq)");
+
+        assert!(parse_compound("SO4^2").is_err());
     }
 }
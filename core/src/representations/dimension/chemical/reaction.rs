@@ -0,0 +1,308 @@
+use crate::representations::{Compound, Element};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+/// A chemical reaction made up of coefficient-compound pairs on each side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reaction {
+    reactants: Vec<(u32, Compound)>,
+    products: Vec<(u32, Compound)>,
+}
+
+/// Reasons a reaction could not be balanced. Species are indexed reactants first, then products.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceError {
+    /// No set of positive coefficients balances the reaction. Holds the species responsible, if
+    /// a single one can be identified.
+    Unbalanceable(Option<usize>),
+    /// The reaction has several independent balancings. Holds a species whose coefficient can be
+    /// chosen freely.
+    Ambiguous(usize),
+}
+
+impl Reaction {
+    pub fn new(reactants: Vec<(u32, Compound)>, products: Vec<(u32, Compound)>) -> Self {
+        Self {
+            reactants,
+            products,
+        }
+    }
+
+    pub fn reactants(&self) -> &[(u32, Compound)] {
+        &self.reactants
+    }
+
+    pub fn products(&self) -> &[(u32, Compound)] {
+        &self.products
+    }
+
+    /// Returns every species in the reaction, reactants first.
+    pub fn species(&self) -> impl Iterator<Item = &(u32, Compound)> {
+        self.reactants.iter().chain(self.products.iter())
+    }
+
+    /// Returns the coefficient of a compound, if it takes part in the reaction. State symbols are
+    /// ignored.
+    pub fn coefficient(&self, compound: &Compound) -> Option<u32> {
+        self.species()
+            .find(|(_, species)| species.same_substance(compound))
+            .map(|(coefficient, _)| *coefficient)
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.elements().iter().all(|element| {
+            let count = |side: &[(u32, Compound)]| -> u64 {
+                side.iter()
+                    .map(|(coefficient, compound)| {
+                        *coefficient as u64 * *compound.elements().get(element).unwrap_or(&0) as u64
+                    })
+                    .sum()
+            };
+            count(&self.reactants) == count(&self.products)
+        }) && self.charge(&self.reactants) == self.charge(&self.products)
+    }
+
+    /// Returns the reaction with the smallest positive integer coefficients that conserve every
+    /// element. Any coefficients already present are ignored.
+    pub fn balance(&self) -> Result<Reaction, BalanceError> {
+        let elements = self.elements();
+        let species = self.species().map(|(_, c)| c).collect::<Vec<_>>();
+        let columns = species.len();
+
+        // One row per element, one column per species. Products are negated so that a balanced
+        // set of coefficients is a vector in the null space of the matrix.
+        let mut matrix = elements
+            .iter()
+            .map(|element| {
+                species
+                    .iter()
+                    .enumerate()
+                    .map(|(i, compound)| {
+                        let count = *compound.elements().get(element).unwrap_or(&0) as i128;
+                        if i < self.reactants.len() {
+                            count
+                        } else {
+                            -count
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // Charge is conserved like an element
+        if self.species().any(|(_, compound)| compound.charge() != 0) {
+            matrix.push(
+                species
+                    .iter()
+                    .enumerate()
+                    .map(|(i, compound)| {
+                        let charge = compound.charge() as i128;
+                        if i < self.reactants.len() {
+                            charge
+                        } else {
+                            -charge
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        // Fraction-free Gauss-Jordan elimination
+        let mut pivots = Vec::new();
+        let mut row = 0;
+        for column in 0..columns {
+            let Some(pivot_row) = (row..matrix.len()).find(|&r| matrix[r][column] != 0) else {
+                continue;
+            };
+            matrix.swap(row, pivot_row);
+
+            let pivot_row = matrix[row].clone();
+            for (other, values) in matrix.iter_mut().enumerate() {
+                if other == row || values[column] == 0 {
+                    continue;
+                }
+                let factor = values[column];
+                for (value, pivot_value) in values.iter_mut().zip(&pivot_row) {
+                    *value = *value * pivot_row[column] - pivot_value * factor;
+                }
+                reduce(values);
+            }
+
+            pivots.push(column);
+            row += 1;
+            if row == matrix.len() {
+                break;
+            }
+        }
+
+        let free = (0..columns)
+            .filter(|column| !pivots.contains(column))
+            .collect::<Vec<_>>();
+
+        match free.len() {
+            0 => return Err(BalanceError::Unbalanceable(self.lone_species(&elements))),
+            1 => {}
+            _ => return Err(BalanceError::Ambiguous(free[0])),
+        }
+        let free = free[0];
+
+        // Each pivot row reads `pivot * x[pivot_column] + value * x[free] = 0`
+        let scale = pivots
+            .iter()
+            .enumerate()
+            .fold(1, |acc, (r, &column)| lcm(acc, matrix[r][column].abs()));
+
+        let mut coefficients = vec![0; columns];
+        coefficients[free] = scale;
+        for (r, &column) in pivots.iter().enumerate() {
+            coefficients[column] = -matrix[r][free] * (scale / matrix[r][column]);
+        }
+
+        reduce(&mut coefficients);
+        if coefficients.iter().all(|c| *c <= 0) {
+            coefficients.iter_mut().for_each(|c| *c = -*c);
+        }
+
+        if let Some(index) = coefficients.iter().position(|c| *c <= 0) {
+            return Err(BalanceError::Unbalanceable(Some(index)));
+        }
+
+        let mut coefficients = coefficients.into_iter().map(|c| c as u32);
+        Ok(Reaction {
+            reactants: self
+                .reactants
+                .iter()
+                .map(|(_, compound)| (coefficients.next().unwrap(), compound.clone()))
+                .collect(),
+            products: self
+                .products
+                .iter()
+                .map(|(_, compound)| (coefficients.next().unwrap(), compound.clone()))
+                .collect(),
+        })
+    }
+
+    fn charge(&self, side: &[(u32, Compound)]) -> i64 {
+        side.iter()
+            .map(|(coefficient, compound)| *coefficient as i64 * compound.charge() as i64)
+            .sum()
+    }
+
+    fn elements(&self) -> BTreeSet<Element> {
+        self.species()
+            .flat_map(|(_, compound)| compound.elements().keys().copied())
+            .collect()
+    }
+
+    /// Finds the first species containing an element that only appears on one side.
+    fn lone_species(&self, elements: &BTreeSet<Element>) -> Option<usize> {
+        let on_side = |side: &[(u32, Compound)], element: &Element| {
+            side.iter()
+                .any(|(_, compound)| compound.elements().contains_key(element))
+        };
+
+        let element = elements.iter().find(|element| {
+            on_side(&self.reactants, element) != on_side(&self.products, element)
+        })?;
+
+        self.species()
+            .position(|(_, compound)| compound.elements().contains_key(element))
+    }
+}
+
+impl Display for Reaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = |species: &[(u32, Compound)]| {
+            species
+                .iter()
+                .map(|(coefficient, compound)| {
+                    if *coefficient == 1 {
+                        compound.to_string()
+                    } else {
+                        format!("{}{}", coefficient, compound)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+
+        write!(f, "{} -> {}", side(&self.reactants), side(&self.products))
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: i128, b: i128) -> i128 {
+    a / gcd(a, b) * b
+}
+
+/// Divides a row through by the greatest common divisor of its entries.
+fn reduce(row: &mut [i128]) {
+    let divisor = row.iter().fold(0, |acc, value| gcd(acc, *value));
+    if divisor > 1 {
+        row.iter_mut().for_each(|value| *value /= divisor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reaction(reactants: &[&str], products: &[&str]) -> Reaction {
+        let side = |formulas: &[&str]| {
+            formulas
+                .iter()
+                .map(|formula| (1, Compound::parse(formula).unwrap()))
+                .collect()
+        };
+        Reaction::new(side(reactants), side(products))
+    }
+
+    #[test]
+    fn balance_finds_smallest_coefficients() {
+        let balanced = reaction(&["Fe", "O2"], &["Fe2O3"]).balance().unwrap();
+        assert_eq!(balanced.to_string(), "4Fe + 3O2 -> 2Fe2O3");
+        assert!(balanced.is_balanced());
+        assert_eq!(
+            balanced.coefficient(&Compound::parse("O2").unwrap()),
+            Some(3)
+        );
+        assert_eq!(balanced.coefficient(&Compound::parse("H2O").unwrap()), None);
+
+        let balanced = reaction(&["C3H8", "O2"], &["CO2", "H2O"])
+            .balance()
+            .unwrap();
+        assert_eq!(balanced.to_string(), "C3H8 + 5O2 -> 3CO2 + 4H2O");
+    }
+
+    #[test]
+    fn balance_conserves_charge() {
+        let balanced = reaction(&["Fe3+", "Cu"], &["Fe2+", "Cu2+"])
+            .balance()
+            .unwrap();
+        assert_eq!(balanced.to_string(), "2Fe3+ + Cu -> 2Fe2+ + Cu2+");
+        assert!(balanced.is_balanced());
+    }
+
+    #[test]
+    fn balance_reports_species_with_missing_element() {
+        assert_eq!(
+            reaction(&["Fe", "O2"], &["FeCl3"]).balance(),
+            Err(BalanceError::Unbalanceable(Some(1)))
+        );
+    }
+
+    #[test]
+    fn balance_reports_ambiguous_reactions() {
+        assert!(matches!(
+            reaction(&["H2", "O2"], &["H2O", "H2O2"]).balance(),
+            Err(BalanceError::Ambiguous(_))
+        ));
+    }
+}
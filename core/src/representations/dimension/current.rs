@@ -0,0 +1,48 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the ampere.
+quantity!(
+    Current,
+    [
+        (
+            Ampere,
+            ratio!(1 / 1),
+            "A",
+            "ampere",
+            "amperes",
+            "amp",
+            "amps"
+        ),
+        (
+            Kiloampere,
+            ratio!(1_000 / 1),
+            "kA",
+            "kiloampere",
+            "kiloamperes"
+        ),
+        (
+            Milliampere,
+            ratio!(1 / 1_000),
+            "mA",
+            "milliampere",
+            "milliamperes",
+            "milliamp",
+            "milliamps"
+        ),
+        (
+            Microampere,
+            ratio!(1 / 1_000_000),
+            "uA",
+            "microampere",
+            "microamperes",
+            "µA"
+        ),
+        (
+            Nanoampere,
+            ratio!(1 / 1_000_000_000),
+            "nA",
+            "nanoampere",
+            "nanoamperes"
+        )
+    ]
+);
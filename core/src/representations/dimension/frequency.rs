@@ -0,0 +1,12 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the hertz, one cycle per second.
+quantity!(
+    Frequency,
+    [
+        (Hertz, ratio!(1 / 1), "Hz", "hertz"),
+        (Kilohertz, ratio!(1_000 / 1), "kHz", "kilohertz"),
+        (Megahertz, ratio!(1_000_000 / 1), "MHz", "megahertz"),
+        (Gigahertz, ratio!(1_000_000_000 / 1), "GHz", "gigahertz")
+    ]
+);
@@ -0,0 +1,34 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the henry.
+quantity!(
+    Inductance,
+    [
+        (Henry, ratio!(1 / 1), "H", "henry", "henries", "henrys"),
+        (
+            Millihenry,
+            ratio!(1 / 1_000),
+            "mH",
+            "millihenry",
+            "millihenries",
+            "millihenrys"
+        ),
+        (
+            Microhenry,
+            ratio!(1 / 1_000_000),
+            "uH",
+            "microhenry",
+            "microhenries",
+            "microhenrys",
+            "µH"
+        ),
+        (
+            Nanohenry,
+            ratio!(1 / 1_000_000_000),
+            "nH",
+            "nanohenry",
+            "nanohenries",
+            "nanohenrys"
+        )
+    ]
+);
@@ -16,7 +16,7 @@ macro_rules! ratio {
 /// ```
 #[macro_export]
 macro_rules! quantity {
-    ($name:ident, [$(($unit_name:ident, $unit_ratio:expr, $default_shorthand:expr, $default_longhand:expr, $($unit_aliases:expr),*)),*]) => {
+    ($name:ident, [$(($unit_name:ident, $unit_ratio:expr, $default_shorthand:expr, $default_longhand:expr $(, $unit_aliases:expr)*)),*]) => {
         use crate::representations::Float;
         use std::str::FromStr;
 
@@ -30,7 +30,7 @@ macro_rules! quantity {
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
                 match s {
-                    $($default_shorthand | $default_longhand | $($unit_aliases)|* => Ok($name::$unit_name)),*,
+                    $($default_shorthand | $default_longhand $(| $unit_aliases)* => Ok($name::$unit_name)),*,
                     _ => Err(())
                 }
             }
@@ -57,7 +57,7 @@ macro_rules! quantity {
 
             pub fn aliases(&self) -> Vec<&'static str> {
                 match self {
-                    $($name::$unit_name => vec![$default_shorthand, $default_longhand, $($unit_aliases),*]),*
+                    $($name::$unit_name => vec![$default_shorthand, $default_longhand $(, $unit_aliases)*]),*
                 }
             }
         }
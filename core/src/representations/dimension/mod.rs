@@ -1,26 +1,47 @@
+mod activity;
 mod amount;
 mod angle;
+mod capacitance;
 mod chemical;
+mod current;
+mod frequency;
+mod inductance;
 mod length;
 mod macros;
 mod mass;
+mod proportion;
+mod resistance;
 mod temperature;
 mod time;
+mod voltage;
+mod volume;
 
-use crate::representations::Float;
+use crate::representations::{Float, FloatFormat};
 use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::ops::Neg;
 use std::str::FromStr;
 
 pub use {
+    activity::Activity,
     amount::Amount,
     angle::Angle,
-    chemical::{Compound, Element},
+    capacitance::Capacitance,
+    chemical::{
+        gas_constant, normalize_name, BalanceError, Compound, Element, FormulaError, GasConditions,
+        PhysicalState, Reaction,
+    },
+    current::Current,
+    frequency::Frequency,
+    inductance::Inductance,
     length::Length,
     mass::Mass,
+    proportion::Proportion,
+    resistance::Resistance,
     temperature::TemperatureInterval,
     time::Time,
+    voltage::Voltage,
+    volume::Volume,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -32,6 +53,15 @@ pub enum QuantityKind {
     Angle,
     Temperature,
     TemperatureInterval,
+    Volume,
+    Activity,
+    Frequency,
+    Current,
+    Voltage,
+    Resistance,
+    Capacitance,
+    Inductance,
+    Proportion,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -42,6 +72,15 @@ pub enum Quantity {
     Amount(amount::Amount, Option<Compound>),
     Angle(angle::Angle),
     TemperatureInterval(temperature::TemperatureInterval),
+    Volume(volume::Volume, Option<Compound>),
+    Activity(activity::Activity),
+    Frequency(frequency::Frequency),
+    Current(current::Current),
+    Voltage(voltage::Voltage),
+    Resistance(resistance::Resistance),
+    Capacitance(capacitance::Capacitance),
+    Inductance(inductance::Inductance),
+    Proportion(proportion::Proportion),
 }
 
 impl FromStr for Quantity {
@@ -74,19 +113,118 @@ impl FromStr for Quantity {
             ));
         }
 
+        if volume::Volume::from_str(s).is_ok() {
+            return Ok(Quantity::Volume(volume::Volume::from_str(s).unwrap(), None));
+        }
+
+        if activity::Activity::from_str(s).is_ok() {
+            return Ok(Quantity::Activity(activity::Activity::from_str(s).unwrap()));
+        }
+
+        if frequency::Frequency::from_str(s).is_ok() {
+            return Ok(Quantity::Frequency(
+                frequency::Frequency::from_str(s).unwrap(),
+            ));
+        }
+
+        if current::Current::from_str(s).is_ok() {
+            return Ok(Quantity::Current(current::Current::from_str(s).unwrap()));
+        }
+
+        if voltage::Voltage::from_str(s).is_ok() {
+            return Ok(Quantity::Voltage(voltage::Voltage::from_str(s).unwrap()));
+        }
+
+        if resistance::Resistance::from_str(s).is_ok() {
+            return Ok(Quantity::Resistance(
+                resistance::Resistance::from_str(s).unwrap(),
+            ));
+        }
+
+        if capacitance::Capacitance::from_str(s).is_ok() {
+            return Ok(Quantity::Capacitance(
+                capacitance::Capacitance::from_str(s).unwrap(),
+            ));
+        }
+
+        if inductance::Inductance::from_str(s).is_ok() {
+            return Ok(Quantity::Inductance(
+                inductance::Inductance::from_str(s).unwrap(),
+            ));
+        }
+
+        if proportion::Proportion::from_str(s).is_ok() {
+            return Ok(Quantity::Proportion(
+                proportion::Proportion::from_str(s).unwrap(),
+            ));
+        }
+
         Err(())
     }
 }
 
 impl Quantity {
+    /// Expands a concentration unit, such as `M` or `% w/v`, into the quantities it is made of.
+    pub fn concentration(s: &str) -> Option<Vec<(Quantity, Float)>> {
+        let (numerator, denominator) = match s {
+            "M" | "molar" => (
+                Quantity::Amount(Amount::Mole, None),
+                Quantity::Volume(Volume::Liter, None),
+            ),
+            "mM" | "millimolar" => (
+                Quantity::Amount(Amount::Millimole, None),
+                Quantity::Volume(Volume::Liter, None),
+            ),
+            "uM" | "µM" | "micromolar" => (
+                Quantity::Amount(Amount::Micromole, None),
+                Quantity::Volume(Volume::Liter, None),
+            ),
+            "nM" | "nanomolar" => (
+                Quantity::Amount(Amount::Nanomole, None),
+                Quantity::Volume(Volume::Liter, None),
+            ),
+            "pM" | "picomolar" => (
+                Quantity::Amount(Amount::Picomole, None),
+                Quantity::Volume(Volume::Liter, None),
+            ),
+            "molal" => (
+                Quantity::Amount(Amount::Mole, None),
+                Quantity::Mass(Mass::Kilogram, None),
+            ),
+            // One percent weight per volume is a gram in every 100 mL
+            "% w/v" | "%w/v" => (
+                Quantity::Mass(Mass::Gram, None),
+                Quantity::Volume(Volume::Deciliter, None),
+            ),
+            _ => return None,
+        };
+
+        Some(vec![
+            (numerator, Float::from(1)),
+            (denominator, Float::from(-1)),
+        ])
+    }
+
     pub fn with_chemical(self, compound: Compound) -> Self {
         match self {
             Quantity::Mass(mass, _) => Quantity::Mass(mass, Some(compound)),
             Quantity::Amount(amount, _) => Quantity::Amount(amount, Some(compound)),
+            Quantity::Volume(volume, _) => Quantity::Volume(volume, Some(compound)),
             _ => self,
         }
     }
 
+    /// Returns the compound tagging a mass, amount or volume, such as the `CO2(g)` of
+    /// `L CO2(g)`.
+    pub fn chemical(&self) -> Option<&Compound> {
+        match self {
+            Quantity::Mass(_, compound)
+            | Quantity::Amount(_, compound)
+            | Quantity::Volume(_, compound) => compound.as_ref(),
+            _ => None,
+        }
+    }
+
     pub fn quantity_kind(&self) -> QuantityKind {
         match self {
             Quantity::Length(_) => QuantityKind::Length,
@@ -95,6 +233,15 @@ impl Quantity {
             Quantity::Amount(_, _) => QuantityKind::Amount,
             Quantity::Angle(_) => QuantityKind::Angle,
             Quantity::TemperatureInterval(_) => QuantityKind::Temperature,
+            Quantity::Volume(_, _) => QuantityKind::Volume,
+            Quantity::Activity(_) => QuantityKind::Activity,
+            Quantity::Frequency(_) => QuantityKind::Frequency,
+            Quantity::Current(_) => QuantityKind::Current,
+            Quantity::Voltage(_) => QuantityKind::Voltage,
+            Quantity::Resistance(_) => QuantityKind::Resistance,
+            Quantity::Capacitance(_) => QuantityKind::Capacitance,
+            Quantity::Inductance(_) => QuantityKind::Inductance,
+            Quantity::Proportion(_) => QuantityKind::Proportion,
         }
     }
 
@@ -104,20 +251,35 @@ impl Quantity {
             Quantity::Time(time) => time.shorthand().to_string(),
             Quantity::Mass(mass, compound) => {
                 if let Some(compound) = compound {
-                    mass.shorthand().to_string() + " " + &*compound.to_string()
+                    mass.shorthand().to_string() + " " + &compound.to_string()
                 } else {
                     mass.shorthand().to_string()
                 }
             }
             Quantity::Amount(amount, compound) => {
                 if let Some(compound) = compound {
-                    amount.shorthand().to_string() + " " + &*compound.to_string()
+                    amount.shorthand().to_string() + " " + &compound.to_string()
                 } else {
                     amount.shorthand().to_string()
                 }
             }
             Quantity::Angle(angle) => angle.shorthand().to_string(),
             Quantity::TemperatureInterval(temperature) => temperature.shorthand().to_string(),
+            Quantity::Volume(volume, compound) => {
+                if let Some(compound) = compound {
+                    volume.shorthand().to_string() + " " + &compound.to_string()
+                } else {
+                    volume.shorthand().to_string()
+                }
+            }
+            Quantity::Activity(activity) => activity.shorthand().to_string(),
+            Quantity::Frequency(frequency) => frequency.shorthand().to_string(),
+            Quantity::Current(current) => current.shorthand().to_string(),
+            Quantity::Voltage(voltage) => voltage.shorthand().to_string(),
+            Quantity::Resistance(resistance) => resistance.shorthand().to_string(),
+            Quantity::Capacitance(capacitance) => capacitance.shorthand().to_string(),
+            Quantity::Inductance(inductance) => inductance.shorthand().to_string(),
+            Quantity::Proportion(proportion) => proportion.shorthand().to_string(),
         }
     }
 
@@ -129,6 +291,48 @@ impl Quantity {
             Quantity::Amount(amount, _) => amount.ratio(),
             Quantity::Angle(angle) => angle.ratio(),
             Quantity::TemperatureInterval(temperature) => temperature.ratio(),
+            Quantity::Volume(volume, _) => volume.ratio(),
+            Quantity::Activity(activity) => activity.ratio(),
+            Quantity::Frequency(frequency) => frequency.ratio(),
+            Quantity::Current(current) => current.ratio(),
+            Quantity::Voltage(voltage) => voltage.ratio(),
+            Quantity::Resistance(resistance) => resistance.ratio(),
+            Quantity::Capacitance(capacitance) => capacitance.ratio(),
+            Quantity::Inductance(inductance) => inductance.ratio(),
+            Quantity::Proportion(proportion) => proportion.ratio(),
+        }
+    }
+
+    /// Returns the size of the unit in the base units it is defined from, the gram, meter,
+    /// second, ampere, mole, kelvin and radian, along with the powers of those units. This
+    /// relates derived units, so that `Ω` can be compared with `V/A`. Proportions such as `%`
+    /// have no base units.
+    pub fn base_units(&self) -> (Float, Vec<(QuantityKind, i32)>) {
+        use QuantityKind::{Current, Length, Mass, Time};
+
+        // The SI defines electrical units from the kilogram, which is a thousand grams
+        let kilograms = |ratio: Float| &ratio * &Float::from(1000);
+        match self {
+            Quantity::Volume(volume, _) => (volume.ratio(), vec![(Length, 3)]),
+            Quantity::Activity(_) | Quantity::Frequency(_) => (self.get_ratio(), vec![(Time, -1)]),
+            Quantity::Voltage(voltage) => (
+                kilograms(voltage.ratio()),
+                vec![(Mass, 1), (Length, 2), (Time, -3), (Current, -1)],
+            ),
+            Quantity::Resistance(resistance) => (
+                kilograms(resistance.ratio()),
+                vec![(Mass, 1), (Length, 2), (Time, -3), (Current, -2)],
+            ),
+            Quantity::Capacitance(capacitance) => (
+                &capacitance.ratio() / &Float::from(1000),
+                vec![(Mass, -1), (Length, -2), (Time, 4), (Current, 2)],
+            ),
+            Quantity::Inductance(inductance) => (
+                kilograms(inductance.ratio()),
+                vec![(Mass, 1), (Length, 2), (Time, -2), (Current, -2)],
+            ),
+            Quantity::Proportion(proportion) => (proportion.ratio(), vec![]),
+            quantity => (quantity.get_ratio(), vec![(quantity.quantity_kind(), 1)]),
         }
     }
 }
@@ -148,17 +352,20 @@ impl Display for Dimension {
                 if !numerator.is_empty() {
                     quantity_shorthand = format!("*{}", quantity_shorthand);
                 }
-                numerator.push_str(&*quantity_shorthand);
+                numerator.push_str(&quantity_shorthand);
                 if *power > Float::from(1) {
-                    numerator.push_str(&format!("^{}", power));
+                    numerator.push_str(&format!("^{}", power.format(&FloatFormat::default())));
                 }
             } else if *power < Float::from(0) {
                 if !denominator.is_empty() {
                     quantity_shorthand = format!("*{}", quantity_shorthand);
                 }
-                denominator.push_str(&*quantity_shorthand);
+                denominator.push_str(&quantity_shorthand);
                 if *power != Float::from(-1) {
-                    denominator.push_str(&format!("^{}", power.clone().neg()));
+                    denominator.push_str(&format!(
+                        "^{}",
+                        power.clone().neg().format(&FloatFormat::default())
+                    ));
                 }
             }
         }
@@ -259,6 +466,12 @@ impl Dimension {
 
     /// Returns the ratio of this value to another value.
     pub fn get_ratio(&self, other: &Self) -> Option<Float> {
+        self.get_ratio_at(other, &GasConditions::standard())
+    }
+
+    /// Returns the ratio of this value to another value, treating chemicals as ideal gases at
+    /// the given conditions when converting between their amount or mass and a volume.
+    pub fn get_ratio_at(&self, other: &Self, conditions: &GasConditions) -> Option<Float> {
         if !self.sanity_check() || !other.sanity_check() {
             return None;
         }
@@ -267,6 +480,38 @@ impl Dimension {
             return Some(Float::from(1));
         }
 
+        self.quantity_ratio_at(other, conditions)
+            .or_else(|| self.base_ratio(other))
+    }
+
+    /// Returns the ratio of this value to another value with the same dimension in base units,
+    /// such as `H/s` and `Ω`.
+    fn base_ratio(&self, other: &Self) -> Option<Float> {
+        let (size, units) = self.base_units();
+        let (other_size, other_units) = other.base_units();
+        (units == other_units).then(|| &other_size / &size)
+    }
+
+    /// Returns the size of the dimension in base units, and the powers of those units.
+    fn base_units(&self) -> (Float, BTreeMap<QuantityKind, Float>) {
+        let mut size = Float::from(1);
+        let mut units = BTreeMap::new();
+
+        for (quantity, power) in self.0.iter() {
+            let (ratio, base_units) = quantity.base_units();
+            size = &size * &ratio.pow(power);
+            for (kind, base_power) in base_units {
+                let total = units.entry(kind).or_insert(Float::from(0));
+                *total = &*total + &(power * &Float::from(base_power));
+            }
+        }
+
+        units.retain(|_, power| *power != Float::from(0));
+        (size, units)
+    }
+
+    /// Returns the ratio of this value to another value by matching up each of their quantities.
+    fn quantity_ratio_at(&self, other: &Self, conditions: &GasConditions) -> Option<Float> {
         let mut ratio = Float::from(1);
 
         for (quantity, power) in self.0.iter() {
@@ -291,7 +536,7 @@ impl Dimension {
                     for (other_quantity, other_power) in other.0.iter() {
                         if let Quantity::Amount(amount, other_chemical) = other_quantity {
                             if let Some(other_chemical) = other_chemical {
-                                if other_chemical != chemical {
+                                if !other_chemical.same_substance(chemical) {
                                     continue;
                                 }
                             }
@@ -307,8 +552,7 @@ impl Dimension {
                             let mut quantity_ratio = &mass_ratio / &amount_ratio;
 
                             // Divide by particulate mass
-                            quantity_ratio = &quantity_ratio
-                                * &Float::parse(&*chemical.particulate_mass().to_string()).unwrap();
+                            quantity_ratio = &quantity_ratio * &chemical.particulate_mass();
 
                             ratio = &ratio * &quantity_ratio;
                             found = true;
@@ -318,7 +562,7 @@ impl Dimension {
                     for (other_quantity, other_power) in other.0.iter() {
                         if let Quantity::Mass(mass, other_chemical) = other_quantity {
                             if let Some(other_chemical) = other_chemical {
-                                if other_chemical != chemical {
+                                if !other_chemical.same_substance(chemical) {
                                     continue;
                                 }
                             }
@@ -334,14 +578,27 @@ impl Dimension {
                             let mut quantity_ratio = &amount_ratio / &mass_ratio;
 
                             // Multiply by particulate mass
-                            quantity_ratio = &quantity_ratio
-                                / &Float::parse(&*chemical.particulate_mass().to_string()).unwrap();
+                            quantity_ratio = &quantity_ratio / &chemical.particulate_mass();
 
                             ratio = &ratio * &quantity_ratio;
                             found = true;
                         }
                     }
                 }
+                if !found {
+                    // Try to convert volume to a cubed length, or a gas to its volume
+                    if let Some(quantity_ratio) = volume_ratio(quantity, power, other, conditions) {
+                        ratio = &ratio * &quantity_ratio;
+                        found = true;
+                    }
+                }
+                if !found {
+                    // Try to convert an activity to decays per unit time
+                    if let Some(quantity_ratio) = activity_ratio(quantity, power, other) {
+                        ratio = &ratio * &quantity_ratio;
+                        found = true;
+                    }
+                }
                 if !found {
                     return None;
                 }
@@ -354,4 +611,270 @@ impl Dimension {
     pub fn is_unitless(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns the single electrical unit equal to a product of units with an electrical unit in
+    /// it, such as `Ω` for `V/A` or `1/(Hz*F)`.
+    pub fn electrical_unit(&self) -> Option<Self> {
+        let electrical = |quantity: &Quantity| {
+            matches!(
+                quantity.quantity_kind(),
+                QuantityKind::Voltage
+                    | QuantityKind::Current
+                    | QuantityKind::Resistance
+                    | QuantityKind::Capacitance
+                    | QuantityKind::Inductance
+            )
+        };
+        if self.0.len() < 2 || !self.0.iter().any(|(quantity, _)| electrical(quantity)) {
+            return None;
+        }
+
+        let (_, units) = self.base_units();
+        [
+            Quantity::Resistance(resistance::Resistance::Ohm),
+            Quantity::Voltage(voltage::Voltage::Volt),
+            Quantity::Current(current::Current::Ampere),
+            Quantity::Capacitance(capacitance::Capacitance::Farad),
+            Quantity::Inductance(inductance::Inductance::Henry),
+        ]
+        .into_iter()
+        .map(|quantity| Dimension(vec![(quantity, Float::from(1))]))
+        .find(|unit| unit.base_units().1 == units)
+    }
+
+    /// Returns the compound tagging this dimension if it is a single mass, amount or volume,
+    /// such as `g Fe`, `mol H2O` or `L CO2`.
+    pub fn compound(&self) -> Option<&Compound> {
+        match self.0.as_slice() {
+            [(quantity, power)] if *power == Float::from(1) => quantity.chemical(),
+            _ => None,
+        }
+    }
+
+    /// Tags a plain mass, amount or volume with a compound, turning `g` into `g water`. Returns
+    /// `None` for any other dimension.
+    pub fn of_compound(&self, compound: &Compound) -> Option<Self> {
+        match self.0.as_slice() {
+            [(
+                quantity @ (Quantity::Mass(_, None)
+                | Quantity::Amount(_, None)
+                | Quantity::Volume(_, None)),
+                power,
+            )] if *power == Float::from(1) => Some(Dimension(vec![(
+                quantity.clone().with_chemical(compound.clone()),
+                power.clone(),
+            )])),
+            _ => None,
+        }
+    }
+
+    /// Tags the plain masses and amounts in this dimension with the compound of another
+    /// dimension, so that converting `mol NaCl/L * mL` to `g` gives `g NaCl`. A volume is only
+    /// tagged when it is the compound's own volume, as in `2 mol CO2(g) to L`, and not the volume
+    /// of a solution, as in `0.5 M NaCl to g/L`. The dimension is returned unchanged unless the
+    /// other dimension is of exactly one substance.
+    pub fn inherit_compound(&self, other: &Self) -> Self {
+        let mut compounds = other
+            .0
+            .iter()
+            .filter_map(|(quantity, _)| quantity.chemical());
+
+        let Some(compound) = compounds.next() else {
+            return self.clone();
+        };
+        if compounds.any(|other| !other.same_substance(compound)) {
+            return self.clone();
+        }
+
+        Dimension(
+            self.0
+                .iter()
+                .map(|(quantity, power)| match quantity {
+                    Quantity::Mass(_, None) | Quantity::Amount(_, None) => (
+                        quantity.clone().with_chemical(compound.clone()),
+                        power.clone(),
+                    ),
+                    Quantity::Volume(_, None) if *power > Float::from(0) => (
+                        quantity.clone().with_chemical(compound.clone()),
+                        power.clone(),
+                    ),
+                    _ => (quantity.clone(), power.clone()),
+                })
+                .collect(),
+        )
+    }
+
+    /// Marks the compounds tagging this dimension as gases, so that they convert to and from
+    /// volumes, as when conditions are given with `at STP`.
+    pub fn as_gas(&self) -> Self {
+        Dimension(
+            self.0
+                .iter()
+                .map(|(quantity, power)| match quantity.chemical() {
+                    Some(compound) => (
+                        quantity
+                            .clone()
+                            .with_chemical(compound.clone().with_state(PhysicalState::Gas)),
+                        power.clone(),
+                    ),
+                    None => (quantity.clone(), power.clone()),
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether converting between this dimension and another would need a compound to be a gas,
+    /// because one is an amount or mass of a compound and the other a volume.
+    pub fn needs_gas(&self, other: &Self) -> bool {
+        let chemical = |dimension: &Self| {
+            dimension.0.iter().any(|(quantity, _)| {
+                matches!(quantity, Quantity::Mass(_, _) | Quantity::Amount(_, _))
+                    && quantity.chemical().is_some()
+            })
+        };
+        let volume = |dimension: &Self| {
+            dimension
+                .0
+                .iter()
+                .any(|(quantity, power)| gas_volume(quantity, power).is_some())
+        };
+        (chemical(self) && volume(other)) || (volume(self) && chemical(other))
+    }
+
+    /// Returns the dimension of moles of the given compound.
+    pub fn moles_of(compound: Compound) -> Self {
+        Dimension(vec![(
+            Quantity::Amount(Amount::Mole, Some(compound)),
+            Float::from(1),
+        )])
+    }
+}
+
+/// Returns the ratio of a single quantity to the matching volume, cubed length, or chemical
+/// quantity in another dimension. Only gases, marked by the state `(g)`, are converted between
+/// their amount or mass and a volume, treated as ideal gases at the given conditions.
+fn volume_ratio(
+    quantity: &Quantity,
+    power: &Float,
+    other: &Dimension,
+    conditions: &GasConditions,
+) -> Option<Float> {
+    let three = Float::from(3);
+
+    for (other_quantity, other_power) in other.0.iter() {
+        match (quantity, other_quantity) {
+            (Quantity::Volume(volume, _), Quantity::Length(length))
+                if *other_power == power * &three =>
+            {
+                let cubed = &(&length.ratio() * &length.ratio()) * &length.ratio();
+                return Some((&cubed / &volume.ratio()).pow(power));
+            }
+            (Quantity::Length(length), Quantity::Volume(volume, _))
+                if *power == other_power * &three =>
+            {
+                let cubed = &(&length.ratio() * &length.ratio()) * &length.ratio();
+                return Some((&volume.ratio() / &cubed).pow(other_power));
+            }
+            _ => {}
+        }
+    }
+
+    // Volume of a mole of gas in cubic meters
+    let molar_volume = conditions.molar_volume();
+
+    for (other_quantity, other_power) in other.0.iter() {
+        let quantity_ratio = if let Some((volume, gas)) = gas_volume(other_quantity, other_power) {
+            // Moles per cubic meter, then per unit of the other volume
+            let moles = &volume / &molar_volume;
+            match chemical_moles(quantity, power, gas)? {
+                Some(chemical_moles) => &moles / &chemical_moles,
+                None => continue,
+            }
+        } else if let Some((volume, gas)) = gas_volume(quantity, power) {
+            let moles = &volume / &molar_volume;
+            match chemical_moles(other_quantity, other_power, gas)? {
+                Some(chemical_moles) => &chemical_moles / &moles,
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+
+        return Some(quantity_ratio);
+    }
+
+    None
+}
+
+/// Returns the size in cubic meters of a volume, or of a cubed length, along with the compound
+/// the volume is of.
+fn gas_volume<'a>(quantity: &'a Quantity, power: &Float) -> Option<(Float, Option<&'a Compound>)> {
+    match quantity {
+        Quantity::Volume(volume, compound) if *power == Float::from(1) => {
+            Some((volume.ratio(), compound.as_ref()))
+        }
+        Quantity::Length(length) if *power == Float::from(3) => {
+            let cubed = &(&length.ratio() * &length.ratio()) * &length.ratio();
+            Some((cubed, None))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the moles in one unit of an amount or mass of a gas, given the compound of the volume
+/// it is converted to or from. Returns `Some(None)` if the quantity is not a chemical amount or
+/// mass, and `None` if it is not of a gas.
+fn chemical_moles(
+    quantity: &Quantity,
+    power: &Float,
+    volume_compound: Option<&Compound>,
+) -> Option<Option<Float>> {
+    if *power != Float::from(1) {
+        return Some(None);
+    }
+    let (ratio, compound) = match quantity {
+        Quantity::Amount(amount, compound) => (&amount.ratio() / &Amount::Mole.ratio(), compound),
+        Quantity::Mass(mass, compound) => (&mass.ratio() / &Mass::Gram.ratio(), compound),
+        _ => return Some(None),
+    };
+    let Some(compound) = compound.as_ref().or(volume_compound) else {
+        return Some(None);
+    };
+    if let Some(volume_compound) = volume_compound {
+        if !volume_compound.same_substance(compound) {
+            return Some(None);
+        }
+    }
+
+    let is_gas = |compound: &Compound| compound.state() == Some(PhysicalState::Gas);
+    if !is_gas(compound) && !volume_compound.is_some_and(is_gas) {
+        return None;
+    }
+
+    match quantity {
+        Quantity::Mass(_, _) => Some(Some(&ratio / &compound.particulate_mass())),
+        _ => Some(Some(ratio)),
+    }
+}
+
+/// Returns the ratio of an activity to the matching inverse time in another dimension, or of an
+/// inverse time to the matching activity.
+fn activity_ratio(quantity: &Quantity, power: &Float, other: &Dimension) -> Option<Float> {
+    for (other_quantity, other_power) in other.0.iter() {
+        if *other_power != power.clone().neg() {
+            continue;
+        }
+
+        match (quantity, other_quantity) {
+            (Quantity::Activity(activity), Quantity::Time(time)) => {
+                return Some((&Float::from(1) / &(&activity.ratio() * &time.ratio())).pow(power));
+            }
+            (Quantity::Time(time), Quantity::Activity(activity)) => {
+                return Some((&activity.ratio() * &time.ratio()).pow(other_power));
+            }
+            _ => {}
+        }
+    }
+
+    None
 }
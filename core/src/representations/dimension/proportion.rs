@@ -0,0 +1,21 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the whole, so proportions have no dimension.
+quantity!(
+    Proportion,
+    [
+        (Percent, ratio!(1 / 100), "%", "percent"),
+        (
+            PartsPerMillion,
+            ratio!(1 / 1_000_000),
+            "ppm",
+            "parts per million"
+        ),
+        (
+            PartsPerBillion,
+            ratio!(1 / 1_000_000_000),
+            "ppb",
+            "parts per billion"
+        )
+    ]
+);
@@ -0,0 +1,37 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the ohm. Impedances and reactances are resistances too.
+quantity!(
+    Resistance,
+    [
+        (Ohm, ratio!(1 / 1), "Ω", "ohm", "ohms"),
+        (
+            Gigaohm,
+            ratio!(1_000_000_000 / 1),
+            "GΩ",
+            "gigaohm",
+            "gigaohms"
+        ),
+        (
+            Megaohm,
+            ratio!(1_000_000 / 1),
+            "MΩ",
+            "megaohm",
+            "megaohms",
+            "megohm",
+            "megohms"
+        ),
+        (
+            Kiloohm,
+            ratio!(1_000 / 1),
+            "kΩ",
+            "kiloohm",
+            "kiloohms",
+            "kilohm",
+            "kilohms",
+            "kohm",
+            "kohms"
+        ),
+        (Milliohm, ratio!(1 / 1_000), "mΩ", "milliohm", "milliohms")
+    ]
+);
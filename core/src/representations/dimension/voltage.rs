@@ -0,0 +1,32 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the volt.
+quantity!(
+    Voltage,
+    [
+        (Volt, ratio!(1 / 1), "V", "volt", "volts"),
+        (
+            Megavolt,
+            ratio!(1_000_000 / 1),
+            "MV",
+            "megavolt",
+            "megavolts"
+        ),
+        (Kilovolt, ratio!(1_000 / 1), "kV", "kilovolt", "kilovolts"),
+        (
+            Millivolt,
+            ratio!(1 / 1_000),
+            "mV",
+            "millivolt",
+            "millivolts"
+        ),
+        (
+            Microvolt,
+            ratio!(1 / 1_000_000),
+            "uV",
+            "microvolt",
+            "microvolts",
+            "µV"
+        )
+    ]
+);
@@ -0,0 +1,101 @@
+use crate::{quantity, ratio};
+
+// Ratios are relative to the cubic meter so volumes can be bridged to cubed lengths.
+quantity!(
+    Volume,
+    [
+        // SI units
+        (
+            Liter,
+            ratio!(1 / 1_000),
+            "L",
+            "liter",
+            "liters",
+            "litre",
+            "litres"
+        ),
+        (
+            Kiloliter,
+            ratio!(1 / 1),
+            "kL",
+            "kiloliter",
+            "kiloliters",
+            "kilolitre",
+            "kilolitres"
+        ),
+        (
+            Deciliter,
+            ratio!(1 / 10_000),
+            "dL",
+            "deciliter",
+            "deciliters",
+            "decilitre",
+            "decilitres"
+        ),
+        (
+            Centiliter,
+            ratio!(1 / 100_000),
+            "cL",
+            "centiliter",
+            "centiliters",
+            "centilitre",
+            "centilitres"
+        ),
+        (
+            Milliliter,
+            ratio!(1 / 1_000_000),
+            "mL",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+            "ml",
+            "cc"
+        ),
+        (
+            Microliter,
+            ratio!(1 / 1_000_000_000),
+            "uL",
+            "microliter",
+            "microliters",
+            "microlitre",
+            "microlitres"
+        ),
+        // US customary units
+        (
+            Gallon,
+            ratio!(3_785_411_784 / 1_000_000_000_000),
+            "gal",
+            "gallon",
+            "gallons"
+        ),
+        (
+            Quart,
+            ratio!(946_352_946 / 1_000_000_000_000),
+            "qt",
+            "quart",
+            "quarts"
+        ),
+        (
+            Pint,
+            ratio!(473_176_473 / 1_000_000_000_000),
+            "pt",
+            "pint",
+            "pints"
+        ),
+        (
+            Cup,
+            ratio!(2_365_882_365 / 10_000_000_000_000),
+            "cup",
+            "cups",
+            "cupful"
+        ),
+        (
+            FluidOunce,
+            ratio!(295_735_295_625 / 10_000_000_000_000_000),
+            "floz",
+            "fluid ounce",
+            "fluid ounces"
+        )
+    ]
+);
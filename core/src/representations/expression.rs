@@ -1,5 +1,7 @@
 use crate::grammar::Span;
 use crate::representations::Dimension;
+use crate::representations::GasConditions;
+use crate::representations::Reaction;
 use crate::representations::Value;
 
 #[derive(Debug, Clone)]
@@ -12,14 +14,29 @@ pub struct Expression {
 #[derive(Debug, Clone)]
 pub enum InnerExpression {
     Constant(Value),
-    Convert(Box<Expression>, Dimension),
+    Convert {
+        value: Box<Expression>,
+        dimension: Dimension,
+        conditions: Option<GasConditions>,
+        /// Reaction relating the value's compound to the target compound
+        reaction: Option<Box<Expression>>,
+    },
     Variable(String),
-    FunctionCall { name: String, args: Vec<Expression> },
+    /// A list of numbers, as in `[1, 2, 3]`
+    List(Vec<Expression>),
+    /// A reaction along with the span of each of its species
+    Reaction(Reaction, Vec<Span>),
+    FunctionCall {
+        name: String,
+        args: Vec<Expression>,
+    },
     Multiply(Box<Expression>, Box<Expression>),
     Divide(Box<Expression>, Box<Expression>),
     Add(Box<Expression>, Box<Expression>),
     Subtract(Box<Expression>, Box<Expression>),
     Exponent(Box<Expression>, Box<Expression>),
+    /// A value with a standard uncertainty, as in `5.0 ± 0.2 m`
+    Uncertain(Box<Expression>, Box<Expression>),
 }
 
 impl Expression {
@@ -35,14 +52,32 @@ impl Expression {
         Expression::new(InnerExpression::Constant(value))
     }
 
-    pub fn convert(expression: Expression, dimension: Dimension) -> Expression {
-        Expression::new(InnerExpression::Convert(Box::new(expression), dimension))
+    pub fn convert(
+        expression: Expression,
+        dimension: Dimension,
+        conditions: Option<GasConditions>,
+        reaction: Option<Expression>,
+    ) -> Expression {
+        Expression::new(InnerExpression::Convert {
+            value: Box::new(expression),
+            dimension,
+            conditions,
+            reaction: reaction.map(Box::new),
+        })
     }
 
     pub fn variable(name: String) -> Expression {
         Expression::new(InnerExpression::Variable(name))
     }
 
+    pub fn list(elements: Vec<Expression>) -> Expression {
+        Expression::new(InnerExpression::List(elements))
+    }
+
+    pub fn reaction(reaction: Reaction, species_spans: Vec<Span>) -> Expression {
+        Expression::new(InnerExpression::Reaction(reaction, species_spans))
+    }
+
     pub fn function_call(name: String, args: Vec<Expression>) -> Expression {
         Expression::new(InnerExpression::FunctionCall { name, args })
     }
@@ -67,6 +102,13 @@ impl Expression {
         Expression::new(InnerExpression::Exponent(Box::new(lhs), Box::new(rhs)))
     }
 
+    pub fn uncertain(value: Expression, uncertainty: Expression) -> Expression {
+        Expression::new(InnerExpression::Uncertain(
+            Box::new(value),
+            Box::new(uncertainty),
+        ))
+    }
+
     pub fn with_span(mut self, span: Span) -> Expression {
         self.span = Some(span);
         self
@@ -1,9 +1,13 @@
+use crate::representations::format::{FloatFormat, MAX_LEN_BEFORE_SCIENTIFIC_NOTATION};
+use crate::representations::split_radix_prefix;
 use astro_float::Consts;
 use astro_float::RoundingMode;
 use astro_float::{BigFloat, Radix};
 use lazy_static::lazy_static;
+use std::cell::Cell;
 use std::fmt::Display;
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 lazy_static! {
@@ -12,18 +16,137 @@ lazy_static! {
     ));
 }
 
-const PRECISION: usize = 256;
-const ROUNDING_MODE: RoundingMode = RoundingMode::ToEven;
-const MAX_LEN_BEFORE_SCIENTIFIC_NOTATION: i32 = 9;
+const DEFAULT_PRECISION: usize = 256;
+pub const MIN_PRECISION: usize = 64;
+pub const MAX_PRECISION: usize = 16384;
+/// Digits shown at the default precision. Other precisions show proportionally more or fewer.
+const DEFAULT_DISPLAY_DIGITS: usize = 30;
+const POW_GUARD_BITS: usize = 64;
+/// Bits of the gamma function gained per term of Spouge's approximation, `log2(2π)`.
+const SPOUGE_BITS_PER_TERM: f64 = 2.651496129472319;
+/// The most bits the gamma function is found to. Spouge's approximation takes time that grows
+/// with the square of the precision, so higher precisions would take minutes.
+const MAX_GAMMA_PRECISION: usize = 1024;
+
+thread_local! {
+    static CONTEXT: Cell<FloatContext> = Cell::new(FloatContext::default());
+}
+
+/// How results that can't be represented exactly are rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// To the nearest value, with ties going to the even one
+    ToEven,
+    /// To the nearest value, with ties going to the odd one
+    ToOdd,
+    /// Towards positive infinity
+    Up,
+    /// Towards negative infinity
+    Down,
+    /// Towards zero
+    ToZero,
+    /// Away from zero
+    FromZero,
+}
+
+impl Rounding {
+    fn mode(&self) -> RoundingMode {
+        match self {
+            Rounding::ToEven => RoundingMode::ToEven,
+            Rounding::ToOdd => RoundingMode::ToOdd,
+            Rounding::Up => RoundingMode::Up,
+            Rounding::Down => RoundingMode::Down,
+            Rounding::ToZero => RoundingMode::ToZero,
+            Rounding::FromZero => RoundingMode::FromZero,
+        }
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "even" | "to_even" | "half_even" => Ok(Rounding::ToEven),
+            "odd" | "to_odd" | "half_odd" => Ok(Rounding::ToOdd),
+            "up" | "ceiling" => Ok(Rounding::Up),
+            "down" | "floor" => Ok(Rounding::Down),
+            "zero" | "to_zero" | "truncate" => Ok(Rounding::ToZero),
+            "away" | "from_zero" => Ok(Rounding::FromZero),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The precision, in bits, and rounding used by every `Float` operation on the current thread,
+/// and the format numbers are displayed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatContext {
+    pub precision: usize,
+    pub rounding: Rounding,
+    pub format: FloatFormat,
+}
+
+impl Default for FloatContext {
+    fn default() -> Self {
+        Self {
+            precision: DEFAULT_PRECISION,
+            rounding: Rounding::ToEven,
+            format: FloatFormat::default(),
+        }
+    }
+}
+
+impl FloatContext {
+    /// Returns the context operations on this thread currently use.
+    pub fn current() -> Self {
+        CONTEXT.with(Cell::get)
+    }
+
+    /// Runs `f` with every `Float` operation using this context, then restores the previous one.
+    pub fn scope<T>(self, f: impl FnOnce() -> T) -> T {
+        struct Restore(FloatContext);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CONTEXT.with(|context| context.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CONTEXT.with(|context| context.replace(self)));
+        f()
+    }
+
+    pub(crate) fn display_digits(&self) -> usize {
+        // Numbers short of scientific notation need all their integer digits
+        (self.precision * DEFAULT_DISPLAY_DIGITS / DEFAULT_PRECISION)
+            .max(MAX_LEN_BEFORE_SCIENTIFIC_NOTATION as usize + 1)
+    }
+}
+
+fn precision() -> usize {
+    FloatContext::current().precision
+}
+
+fn rounding_mode() -> RoundingMode {
+    FloatContext::current().rounding.mode()
+}
 
 #[derive(Debug, Clone)]
 pub struct Float(BigFloat);
 
 impl Float {
+    /// Reads a decimal number, or a number in another base with a prefix such as `0xFF`.
     pub fn parse(s: &str) -> Result<Self, ()> {
         let mut cache = CONST_CACHE.lock().unwrap();
-        let s = s.trim().replace('_', "").replace(',', "");
-        let res = BigFloat::parse(&s, Radix::Dec, PRECISION, ROUNDING_MODE, &mut *cache);
+        let s = s.trim().replace(['_', ','], "");
+        let (radix, digits) = match split_radix_prefix(&s) {
+            Some((2, digits)) => (Radix::Bin, digits.to_string()),
+            Some((8, digits)) => (Radix::Oct, digits.to_string()),
+            Some((_, digits)) => (Radix::Hex, digits.to_lowercase()),
+            None => (Radix::Dec, s),
+        };
+        let res = BigFloat::parse(&digits, radix, precision(), rounding_mode(), &mut cache);
         if res.is_nan() {
             Err(())
         } else {
@@ -33,213 +156,234 @@ impl Float {
 
     pub fn pi() -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(cache.pi(PRECISION, ROUNDING_MODE))
+        Self(cache.pi(precision(), rounding_mode()))
     }
 
     pub fn e() -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(cache.e(PRECISION, ROUNDING_MODE))
+        Self(cache.e(precision(), rounding_mode()))
     }
 
     pub fn pow(&self, other: &Self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.pow(&other.0, PRECISION, ROUNDING_MODE, &mut *cache))
+        // Exact results like `4^0.5` never satisfy the correct rounding check, so the power is
+        // found with guard bits and rounded afterwards
+        let mut result = self.0.pow(
+            &other.0,
+            precision() + POW_GUARD_BITS,
+            RoundingMode::None,
+            &mut cache,
+        );
+        result.set_precision(precision(), rounding_mode()).unwrap();
+        Self(result)
     }
 
     pub fn ln(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.ln(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.ln(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn log10(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.log10(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.log10(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn sqrt(&self) -> Self {
-        Self(self.0.sqrt(PRECISION, ROUNDING_MODE))
+        Self(self.0.sqrt(precision(), rounding_mode()))
     }
 
     pub fn sin(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.sin(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.sin(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn cos(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.cos(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.cos(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn tan(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.tan(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.tan(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn asin(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.asin(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.asin(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn acos(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.acos(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.acos(precision(), rounding_mode(), &mut cache))
     }
 
     pub fn atan(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.atan(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.atan(precision(), rounding_mode(), &mut cache))
     }
 
-    pub fn exp(&self) -> Self {
+    pub fn sinh(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        Self(self.0.exp(PRECISION, ROUNDING_MODE, &mut *cache))
+        Self(self.0.sinh(precision(), rounding_mode(), &mut cache))
     }
 
-    pub fn recip(&self) -> Self {
-        Self(self.0.reciprocal(PRECISION, ROUNDING_MODE))
+    pub fn cosh(&self) -> Self {
+        let mut cache = CONST_CACHE.lock().unwrap();
+        Self(self.0.cosh(precision(), rounding_mode(), &mut cache))
     }
 
-    pub fn abs(&self) -> Self {
-        Self(self.0.abs())
+    pub fn tanh(&self) -> Self {
+        let mut cache = CONST_CACHE.lock().unwrap();
+        Self(self.0.tanh(precision(), rounding_mode(), &mut cache))
     }
-}
 
-impl Display for Float {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    pub fn asinh(&self) -> Self {
         let mut cache = CONST_CACHE.lock().unwrap();
-        let string = self
-            .0
-            .format(Radix::Dec, ROUNDING_MODE, &mut *cache)
-            .unwrap();
+        Self(self.0.asinh(precision(), rounding_mode(), &mut cache))
+    }
 
-        // In scientific notation
-        let string = string.split('e').collect::<Vec<&str>>();
-        let (Some(mantissa), Some(exponent)) = (string.get(0), string.get(1)) else {
-            write!(f, "{}", string[0])?;
-            return Ok(());
-        };
+    pub fn acosh(&self) -> Self {
+        let mut cache = CONST_CACHE.lock().unwrap();
+        Self(self.0.acosh(precision(), rounding_mode(), &mut cache))
+    }
 
-        let mut exponent = exponent.parse::<i32>().unwrap();
+    pub fn atanh(&self) -> Self {
+        let mut cache = CONST_CACHE.lock().unwrap();
+        Self(self.0.atanh(precision(), rounding_mode(), &mut cache))
+    }
 
-        // NOTE: mantissa is in format "d.d*"
-        let mut mantissa = mantissa.to_string();
+    pub fn exp(&self) -> Self {
+        let mut cache = CONST_CACHE.lock().unwrap();
+        Self(self.0.exp(precision(), rounding_mode(), &mut cache))
+    }
 
-        let mut sign = false;
+    pub fn recip(&self) -> Self {
+        Self(self.0.reciprocal(precision(), rounding_mode()))
+    }
 
-        if mantissa.starts_with('-') {
-            mantissa.remove(0);
-            sign = true;
+    /// Rounds to the nearest integer, with halves going away from zero.
+    pub fn round(&self) -> Self {
+        let rounded = (&self.abs() + &Float::from(0.5)).floor();
+        if *self < Float::from(0) {
+            -rounded
+        } else {
+            rounded
         }
+    }
 
-        // remove decimal point
-        mantissa.remove(1);
+    /// Rounds down to an integer.
+    pub fn floor(&self) -> Self {
+        Self(self.0.floor())
+    }
 
-        // Truncate to 30 digits
-        mantissa.truncate(30);
-        // Add zeroes until length is 30
-        while mantissa.len() < 30 {
-            mantissa.push('0');
-        }
+    /// Rounds up to an integer.
+    pub fn ceil(&self) -> Self {
+        Self(self.0.ceil())
+    }
 
-        // round trailing nines
-        mantissa.insert(0, '0');
-        let mut should_round = false;
-        while mantissa.ends_with('9') {
-            mantissa.pop();
-            should_round = true;
-        }
-        if should_round {
-            let mut last_digit = mantissa.pop().unwrap();
-            last_digit = (last_digit as u8 + 1) as char;
-            mantissa.push(last_digit);
-        }
-        if !mantissa.starts_with('0') {
-            mantissa.insert(0, '0');
-            exponent += 2;
-        } else {
-            mantissa.remove(0);
-        }
+    /// Rounds towards zero to an integer.
+    pub fn trunc(&self) -> Self {
+        Self(self.0.int())
+    }
 
-        if exponent <= MAX_LEN_BEFORE_SCIENTIFIC_NOTATION
-            && exponent >= -MAX_LEN_BEFORE_SCIENTIFIC_NOTATION
-        {
-            // add leading zeros
-            mantissa.insert_str(0, &"0".repeat(MAX_LEN_BEFORE_SCIENTIFIC_NOTATION as usize));
-
-            // add decimal point
-            mantissa.insert_str(
-                (MAX_LEN_BEFORE_SCIENTIFIC_NOTATION + 1 + exponent) as usize,
-                ".",
-            );
-
-            // remove trailing zeros
-            while mantissa.ends_with('0') {
-                mantissa.pop();
-            }
+    /// The gamma function, which extends the factorial so that `Γ(n) = (n - 1)!`. Poles at zero
+    /// and the negative integers give infinity or NaN. Only the first `MAX_GAMMA_PRECISION` bits
+    /// are accurate at higher precisions.
+    pub fn gamma(&self) -> Self {
+        let target = precision().min(MAX_GAMMA_PRECISION);
+        // Spouge's approximation with `a` terms is accurate to about `a * log2(2π)` bits, but its
+        // terms are as large as `e^a`, so that many bits cancel away when they are added
+        let terms = (target as f64 / SPOUGE_BITS_PER_TERM).ceil() as u64 + 1;
+        let guard_bits = (terms as f64 * std::f64::consts::LOG2_E).ceil() as usize + 64;
+        let context = FloatContext {
+            precision: target + guard_bits,
+            ..FloatContext::current()
+        };
 
-            // remove decimal point if it's the last character
-            if mantissa.ends_with('.') {
-                mantissa.pop();
-            }
+        let mut result = context.scope(|| self.spouge_gamma(terms)).0;
+        result.set_precision(precision(), rounding_mode()).unwrap();
+        Self(result)
+    }
 
-            // remove leading zeros
-            while mantissa.starts_with('0') {
-                mantissa.remove(0);
-            }
+    fn spouge_gamma(&self, terms: u64) -> Self {
+        let one = Float::from(1);
+        let half = Float::from(0.5);
+        // Reflecting `Γ(x) Γ(1 - x) = π / sin(πx)` keeps the approximation where it converges
+        if self < &half {
+            let pi = Float::pi();
+            let sin = (&pi * self).sin();
+            return &pi / &(&sin * &(&one - self).spouge_gamma(terms));
+        }
 
-            if mantissa.starts_with('.') {
-                mantissa.insert(0, '0');
-            }
+        // Γ(z + 1) = (z + a)^(z + 1/2) e^-(z + a) (c0 + Σ ck / (z + k))
+        let z = self - &one;
+        let a = Float::from(terms);
+        let e = Float::e();
+        let mut sum = (&Float::pi() * &Float::from(2)).sqrt();
+        let mut exponential = Float::from(terms - 1).exp();
+        let mut factorial = Float::from(1);
+        for k in 1..terms {
+            let base = Float::from(terms - k);
+            let coefficient = &(&base.pow(&(&Float::from(k) - &half)) * &exponential) / &factorial;
+            let term = &coefficient / &(&z + &Float::from(k));
+            sum = if k % 2 == 1 {
+                &sum + &term
+            } else {
+                &sum - &term
+            };
+            exponential = &exponential / &e;
+            factorial = &factorial * &Float::from(k);
+        }
 
-            // add commas separating every 3 digits before decimal point
-            let mut i = 0;
-            while i < mantissa.len() {
-                if mantissa.chars().nth(i).unwrap() == '.' {
-                    break;
-                }
-                i += 1;
-            }
+        let shifted = &z + &a;
+        &(&shifted.pow(&(&z + &half)) * &(-shifted.clone()).exp()) * &sum
+    }
 
-            if i > 3 {
-                let mut i = i - 3;
-
-                loop {
-                    mantissa.insert(i, ',');
-                    if i > 3 {
-                        i -= 3;
-                    } else {
-                        break;
-                    }
-                }
-            }
+    /// Whether the number is neither infinite nor NaN.
+    pub fn is_finite(&self) -> bool {
+        !self.0.is_inf() && !self.0.is_nan()
+    }
 
-            if sign {
-                mantissa.insert(0, '-');
-            }
+    /// Returns the closest `f64`, for use where exactness doesn't matter.
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_string().parse().unwrap_or(f64::NAN)
+    }
 
-            write!(f, "{}", mantissa)?;
-            Ok(())
-        } else {
-            // add decimal point
-            mantissa.insert(1, '.');
+    pub fn abs(&self) -> Self {
+        Self(self.0.abs())
+    }
+}
 
-            while mantissa.ends_with('0') {
-                mantissa.pop();
-            }
+impl Float {
+    /// Splits a finite, non-zero number into its sign, decimal digits and exponent, so that
+    /// `-1.25` is `(true, "125", 0)`. Other numbers are returned as text, like `0.0` or `Inf`.
+    pub(crate) fn decimal_parts(&self) -> Result<(bool, String, i32), String> {
+        let mut cache = CONST_CACHE.lock().unwrap();
+        let string = self
+            .0
+            .format(Radix::Dec, rounding_mode(), &mut cache)
+            .unwrap();
 
-            // remove decimal point if it's the last character
-            if mantissa.ends_with('.') {
-                mantissa.pop();
-            }
+        // NOTE: finite non-zero numbers are in the format "-d.d*e-d*"
+        let Some((mantissa, exponent)) = string.split_once('e') else {
+            return Err(string);
+        };
 
-            if sign {
-                mantissa.insert(0, '-');
-            }
+        let negative = mantissa.starts_with('-');
+        let digits = mantissa.chars().filter(char::is_ascii_digit).collect();
 
-            write!(f, "{}E{}", mantissa, exponent)?;
+        Ok((negative, digits, exponent.parse().unwrap()))
+    }
 
-            Ok(())
-        }
+    /// Returns the power of ten of the first digit, or nothing for zero.
+    pub(crate) fn magnitude(&self) -> Option<i32> {
+        self.decimal_parts().ok().map(|(_, _, exponent)| exponent)
+    }
+}
+
+impl Display for Float {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(&FloatContext::current().format))
     }
 }
 
@@ -247,7 +391,7 @@ impl Add<&Float> for &Float {
     type Output = Float;
 
     fn add(self, rhs: &Float) -> Self::Output {
-        Float(self.0.add(&rhs.0, PRECISION, ROUNDING_MODE))
+        Float(self.0.add(&rhs.0, precision(), rounding_mode()))
     }
 }
 
@@ -255,7 +399,7 @@ impl Sub<&Float> for &Float {
     type Output = Float;
 
     fn sub(self, rhs: &Float) -> Self::Output {
-        Float(self.0.sub(&rhs.0, PRECISION, ROUNDING_MODE))
+        Float(self.0.sub(&rhs.0, precision(), rounding_mode()))
     }
 }
 
@@ -263,7 +407,7 @@ impl Mul<&Float> for &Float {
     type Output = Float;
 
     fn mul(self, rhs: &Float) -> Self::Output {
-        Float(self.0.mul(&rhs.0, PRECISION, ROUNDING_MODE))
+        Float(self.0.mul(&rhs.0, precision(), rounding_mode()))
     }
 }
 
@@ -271,7 +415,7 @@ impl Div<&Float> for &Float {
     type Output = Float;
 
     fn div(self, rhs: &Float) -> Self::Output {
-        Float(self.0.div(&rhs.0, PRECISION, ROUNDING_MODE))
+        Float(self.0.div(&rhs.0, precision(), rounding_mode()))
     }
 }
 
@@ -322,6 +466,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_gamma() {
+        let close = |a: Float, b: Float| (&a - &b).abs() < Float::parse("1e-60").unwrap();
+        assert!(close(Float::from(5).gamma(), Float::from(24)));
+        // Γ(1/2) = √π
+        assert!(close(Float::from(0.5).gamma(), Float::pi().sqrt()));
+        // Γ(-1/2) = -2√π
+        assert!(close(
+            Float::from(-0.5).gamma(),
+            -(&Float::pi().sqrt() * &Float::from(2))
+        ));
+    }
+
+    #[test]
+    fn gamma_is_limited_to_max_gamma_precision() {
+        let precise = FloatContext {
+            precision: 4 * MAX_GAMMA_PRECISION,
+            ..FloatContext::default()
+        };
+        let (gamma, root_pi) = precise.scope(|| (Float::from(0.5).gamma(), Float::pi().sqrt()));
+        let error = precise.scope(|| (&gamma - &root_pi).abs());
+        let tolerance = Float::from(2).pow(&Float::from(-(MAX_GAMMA_PRECISION as i64) + 8));
+        assert!(error < tolerance);
+    }
+
+    #[test]
+    fn test_round() {
+        assert_eq!(Float::parse("2.4").unwrap().round(), Float::from(2));
+        assert_eq!(Float::parse("-2.6").unwrap().round(), Float::from(-3));
+        assert_eq!(Float::parse("2.5").unwrap().round(), Float::from(3));
+        assert_eq!(Float::parse("-2.5").unwrap().round(), Float::from(-3));
+        assert_eq!(Float::parse("12.25").unwrap().to_f64(), 12.25);
+    }
+
+    #[test]
+    fn test_context() {
+        let third = || &Float::from(1) / &Float::from(3);
+        let precise = FloatContext {
+            precision: 512,
+            ..FloatContext::default()
+        };
+
+        assert_ne!(precise.scope(third), third());
+        assert_eq!(
+            precise.scope(|| third().to_string()),
+            format!("0.{}", "3".repeat(60))
+        );
+        assert_eq!(FloatContext::current(), FloatContext::default());
+
+        let down = FloatContext {
+            rounding: Rounding::Down,
+            ..FloatContext::default()
+        };
+        let up = FloatContext {
+            rounding: Rounding::Up,
+            ..FloatContext::default()
+        };
+        assert!(down.scope(third) < up.scope(third));
+        assert_eq!("floor".parse(), Ok(Rounding::Down));
+    }
+
     #[test]
     fn test_string() {
         assert_eq!(Float::parse("-10.123").unwrap().to_string(), "-10.123");
@@ -332,6 +537,12 @@ mod tests {
         assert_eq!(Float::parse("001.00").unwrap().to_string(), "1");
         assert_eq!(Float::parse("5e-20").unwrap().to_string(), "5E-20");
         assert_eq!(Float::parse("999").unwrap().to_string(), "999");
+        assert_eq!(
+            Float::parse("999.9999999999999999999999999999999")
+                .unwrap()
+                .to_string(),
+            "1,000"
+        );
         assert_eq!(
             Float::parse(".01123410918273418734182374")
                 .unwrap()
@@ -0,0 +1,514 @@
+use crate::representations::{Float, FloatContext};
+use std::str::FromStr;
+
+/// Numbers with more digits than this either side of the decimal point are shown in scientific
+/// notation by default.
+pub(crate) const MAX_LEN_BEFORE_SCIENTIFIC_NOTATION: i32 = 9;
+
+/// How many digits of a number are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Digits {
+    /// As many as the precision allows, without trailing zeros
+    Default,
+    DecimalPlaces(usize),
+    SignificantFigures(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// Plain for numbers of up to 9 digits either side of the decimal point, scientific otherwise
+    Auto,
+    Scientific,
+    /// Scientific notation with exponents that are a multiple of 3
+    Engineering,
+    Plain,
+    /// Exact results as fractions, as in `1/3`, and other results as with `Auto`
+    Fraction,
+    /// Exact results as decimals with their repeating digits in brackets, as in `0.(3)`
+    Repeating,
+    /// Complex results by magnitude and angle, as in `5 ∠ 53.13°`, and other results as with
+    /// `Auto`
+    Polar,
+}
+
+impl FromStr for Notation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" | "rectangular" | "rect" => Ok(Notation::Auto),
+            "sci" | "scientific" => Ok(Notation::Scientific),
+            "eng" | "engineering" => Ok(Notation::Engineering),
+            "plain" | "fixed" | "normal" => Ok(Notation::Plain),
+            "fraction" | "fractions" | "frac" => Ok(Notation::Fraction),
+            "repeating" | "recurring" => Ok(Notation::Repeating),
+            "polar" => Ok(Notation::Polar),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How numbers are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatFormat {
+    pub digits: Digits,
+    pub notation: Notation,
+    /// Whether digits before the decimal point are grouped in threes, as in `1,000,000`
+    pub grouping: bool,
+    pub group_separator: char,
+    pub decimal_separator: char,
+    /// The letter complex numbers are written with, `i` or `j`
+    pub imaginary_unit: char,
+    /// The base whole numbers are shown in, as in `0xFF` for 16
+    pub radix: u32,
+    /// The bits in a word in programmer mode, as in `#word 32`. Bitwise operations wrap around
+    /// into a word, and negative numbers are shown in other bases in two's complement.
+    pub word_size: Option<u32>,
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        Self {
+            digits: Digits::Default,
+            notation: Notation::Auto,
+            grouping: true,
+            group_separator: ',',
+            decimal_separator: '.',
+            imaginary_unit: 'i',
+            radix: 10,
+            word_size: None,
+        }
+    }
+}
+
+/// A change to how a single line is displayed, like `to 3 sf` or `to sci`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatChange {
+    Digits(Digits),
+    Notation(Notation),
+    Radix(u32),
+}
+
+impl FloatFormat {
+    pub fn with_change(self, change: FormatChange) -> Self {
+        match change {
+            FormatChange::Digits(digits) => Self { digits, ..self },
+            FormatChange::Notation(notation) => Self { notation, ..self },
+            FormatChange::Radix(radix) => Self { radix, ..self },
+        }
+    }
+}
+
+/// Reads the name of a base numbers can be shown in, as in `hex` or `base 36`.
+pub fn parse_radix(name: &str) -> Option<u32> {
+    match name {
+        "hexadecimal" | "hex" => Some(16),
+        "binary" | "bin" => Some(2),
+        "octal" | "oct" => Some(8),
+        "decimal" | "dec" => Some(10),
+        name => name
+            .strip_prefix("base")?
+            .trim()
+            .parse()
+            .ok()
+            .filter(|radix| (2..=36).contains(radix)),
+    }
+}
+
+/// The separators numbers are written with in a region, both when typed and when shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub group_separator: char,
+    pub decimal_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            group_separator: ',',
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl Locale {
+    /// Looks up a locale by its language or region, such as `de`, `fr-FR` or `de-CH`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (group_separator, decimal_separator) = match name.replace('_', "-").as_str() {
+            "en" | "en-US" | "en-GB" | "en-AU" | "en-CA" | "en-IN" | "ja" | "zh" | "ko" => {
+                (',', '.')
+            }
+            "de" | "de-DE" | "de-AT" | "nl" | "es" | "it" | "pt" | "pt-BR" | "da" | "id" | "tr" => {
+                ('.', ',')
+            }
+            "fr" | "fr-FR" | "fr-CA" | "sv" | "nb" | "no" | "fi" | "pl" | "cs" | "sk" | "ru"
+            | "uk" | "hu" => (' ', ','),
+            "ch" | "de-CH" | "fr-CH" | "it-CH" | "rm" => ('\'', '.'),
+            _ => return None,
+        };
+
+        Some(Self {
+            group_separator,
+            decimal_separator,
+        })
+    }
+
+    /// Rewrites the numbers in a line typed in this locale with the separators the grammar
+    /// expects, so `1.234,5` in German becomes `1_234.5`. Only single ASCII characters are
+    /// swapped for others, so positions in the line stay the same.
+    pub fn normalize_numbers(&self, line: &str) -> String {
+        if *self == Locale::default() {
+            return line.to_string();
+        }
+
+        let mut bytes = line.as_bytes().to_vec();
+        let is_digit = |bytes: &[u8], i: usize| bytes.get(i).is_some_and(u8::is_ascii_digit);
+        let is_group_separator = |byte: u8| {
+            byte == self.group_separator as u8 || (byte == b' ' && self.group_separator == ' ')
+        };
+
+        let mut i = 0;
+        while i < bytes.len() {
+            // Numbers can't continue a name, like the 2 in `H2O`
+            let continues_name =
+                i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_');
+            if !is_digit(&bytes, i) || continues_name {
+                i += 1;
+                continue;
+            }
+
+            let mut decimal = false;
+            while i < bytes.len() {
+                if is_digit(&bytes, i) {
+                    i += 1;
+                } else if self.group_separator.is_ascii()
+                    && !decimal
+                    && is_group_separator(bytes[i])
+                    && (1..=3).all(|offset| is_digit(&bytes, i + offset))
+                    && !is_digit(&bytes, i + 4)
+                {
+                    bytes[i] = b'_';
+                    i += 1;
+                } else if self.decimal_separator.is_ascii()
+                    && !decimal
+                    && bytes[i] == self.decimal_separator as u8
+                    && is_digit(&bytes, i + 1)
+                {
+                    bytes[i] = b'.';
+                    decimal = true;
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        String::from_utf8(bytes).unwrap()
+    }
+}
+
+impl Float {
+    /// Parses a number written in a locale, such as `3,5` in German. Returns `None` if it isn't
+    /// a number.
+    pub fn parse_localized(s: &str, locale: &Locale) -> Option<Self> {
+        Float::parse(&locale.normalize_numbers(s.trim())).ok()
+    }
+
+    /// Formats the number as text. Rounding to decimal places or significant figures is half
+    /// away from zero.
+    pub fn format(&self, format: &FloatFormat) -> String {
+        // Only exact values can be shown as fractions, and only complex values in polar form
+        if let Notation::Fraction | Notation::Repeating | Notation::Polar = format.notation {
+            return self.format(&format.with_change(FormatChange::Notation(Notation::Auto)));
+        }
+
+        let (negative, digits, exponent) = match self.decimal_parts() {
+            Ok(parts) => parts,
+            Err(text) if text.trim_start_matches('-') == "0.0" => return format_zero(format),
+            Err(text) => return text,
+        };
+
+        let positional = |exponent: i32| match format.notation {
+            Notation::Auto | Notation::Fraction | Notation::Repeating | Notation::Polar => {
+                (-MAX_LEN_BEFORE_SCIENTIFIC_NOTATION..=MAX_LEN_BEFORE_SCIENTIFIC_NOTATION)
+                    .contains(&exponent)
+            }
+            Notation::Plain => true,
+            Notation::Scientific | Notation::Engineering => false,
+        };
+
+        let (digits, exponent, positional) = match format.digits {
+            Digits::Default => {
+                let (digits, exponent) =
+                    truncate_digits(&digits, exponent, FloatContext::current().display_digits());
+                (digits, exponent, positional(exponent))
+            }
+            Digits::SignificantFigures(figures) => {
+                let (mut digits, exponent) = round_digits(&digits, exponent, figures as i32);
+                digits.truncate(figures.max(1));
+                (digits, exponent, positional(exponent))
+            }
+            Digits::DecimalPlaces(places) => {
+                let positional = positional(exponent);
+                // The digits shown before the decimal point depend on the exponent
+                let count = |exponent: i32| match format.notation {
+                    _ if positional => exponent + 1 + places as i32,
+                    Notation::Engineering => exponent.rem_euclid(3) + 1 + places as i32,
+                    _ => 1 + places as i32,
+                };
+
+                let (mut digits, exponent) = round_digits(&digits, exponent, count(exponent));
+                digits.truncate(count(exponent).max(1) as usize);
+                (digits, exponent, positional)
+            }
+        };
+
+        // Numbers that round to zero have no sign
+        let negative = negative && digits.chars().any(|digit| digit != '0');
+
+        let mut string = if positional {
+            format_positional(&digits, exponent, format)
+        } else {
+            format_scientific(&digits, exponent, format)
+        };
+
+        if negative {
+            string.insert(0, '-');
+        }
+
+        string
+    }
+}
+
+/// Formats zero, which has no digits or exponent of its own.
+fn format_zero(format: &FloatFormat) -> String {
+    let decimals = match format.digits {
+        Digits::Default => 1,
+        Digits::DecimalPlaces(places) => places,
+        Digits::SignificantFigures(figures) => figures.saturating_sub(1),
+    };
+
+    match decimals {
+        0 => "0".to_string(),
+        decimals => format!("0{}{}", format.decimal_separator, "0".repeat(decimals)),
+    }
+}
+
+/// Truncates digits to at most `count`, rounding a run of trailing nines up and removing
+/// trailing zeros.
+fn truncate_digits(digits: &str, mut exponent: i32, count: usize) -> (String, i32) {
+    let digits = format!("{:0<count$}", &digits[..digits.len().min(count)]);
+    let mut digits = "0".to_string() + &digits;
+
+    if digits.ends_with('9') {
+        while digits.ends_with('9') {
+            digits.pop();
+        }
+        let last_digit = digits.pop().unwrap();
+        digits.push((last_digit as u8 + 1) as char);
+    }
+
+    if digits.starts_with('0') {
+        digits.remove(0);
+    } else {
+        // Rounding carried into a new leading digit
+        exponent += 1;
+    }
+
+    let trimmed = digits.trim_end_matches('0').len().max(1);
+    digits.truncate(trimmed);
+
+    (digits, exponent)
+}
+
+/// Rounds digits to `count` of them, half away from zero, padding with zeros if there are fewer.
+/// Rounding can carry into an extra leading digit, in which case the exponent goes up by one.
+fn round_digits(digits: &str, exponent: i32, count: i32) -> (String, i32) {
+    if count < 0 || (count == 0 && digits.as_bytes()[0] < b'5') {
+        return ("0".to_string(), 0);
+    }
+    if count == 0 {
+        return ("1".to_string(), exponent + 1);
+    }
+
+    let count = count as usize;
+    if digits.len() <= count {
+        return (format!("{:0<count$}", digits), exponent);
+    }
+
+    let mut rounded = digits.as_bytes()[..count].to_vec();
+    if digits.as_bytes()[count] >= b'5' {
+        let mut i = count;
+        loop {
+            if i == 0 {
+                rounded.insert(0, b'1');
+                return (String::from_utf8(rounded).unwrap(), exponent + 1);
+            }
+            i -= 1;
+            if rounded[i] == b'9' {
+                rounded[i] = b'0';
+            } else {
+                rounded[i] += 1;
+                break;
+            }
+        }
+    }
+
+    (String::from_utf8(rounded).unwrap(), exponent)
+}
+
+fn format_positional(digits: &str, exponent: i32, format: &FloatFormat) -> String {
+    let (integer, fraction) = if exponent >= 0 {
+        let split = exponent as usize + 1;
+        if digits.len() <= split {
+            (format!("{:0<split$}", digits), String::new())
+        } else {
+            (digits[..split].to_string(), digits[split..].to_string())
+        }
+    } else {
+        (
+            "0".to_string(),
+            "0".repeat((-exponent - 1) as usize) + digits,
+        )
+    };
+
+    let mut fraction = fraction;
+    if let Digits::DecimalPlaces(places) = format.digits {
+        fraction = format!("{:0<places$}", fraction);
+        fraction.truncate(places);
+    }
+
+    let mut string = group(&integer, format);
+    if !fraction.is_empty() {
+        string.push(format.decimal_separator);
+        string.push_str(&fraction);
+    }
+
+    string
+}
+
+fn format_scientific(digits: &str, exponent: i32, format: &FloatFormat) -> String {
+    let shift = match format.notation {
+        Notation::Engineering => exponent.rem_euclid(3) as usize,
+        _ => 0,
+    };
+
+    let digits = format!("{:0<width$}", digits, width = shift + 1);
+    let (integer, fraction) = digits.split_at(shift + 1);
+
+    let mut string = integer.to_string();
+    if !fraction.is_empty() {
+        string.push(format.decimal_separator);
+        string.push_str(fraction);
+    }
+
+    format!("{}E{}", string, exponent - shift as i32)
+}
+
+/// Separates the digits before the decimal point into groups of three.
+fn group(integer: &str, format: &FloatFormat) -> String {
+    if !format.grouping || integer.len() <= 3 {
+        return integer.to_string();
+    }
+
+    let mut string = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            string.push(format.group_separator);
+        }
+        string.push(digit);
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(number: &str, change: FormatChange) -> String {
+        Float::parse(number)
+            .unwrap()
+            .format(&FloatFormat::default().with_change(change))
+    }
+
+    #[test]
+    fn test_decimal_places() {
+        let places = |places| FormatChange::Digits(Digits::DecimalPlaces(places));
+        assert_eq!(format("3.14159", places(2)), "3.14");
+        assert_eq!(format("2.5", places(0)), "3");
+        assert_eq!(format("1.5", places(3)), "1.500");
+        assert_eq!(format("9.996", places(2)), "10.00");
+        assert_eq!(format("-0.001", places(2)), "0.00");
+        assert_eq!(format("0.006", places(2)), "0.01");
+        assert_eq!(format("1234567.891", places(1)), "1,234,567.9");
+        assert_eq!(format("0", places(2)), "0.00");
+    }
+
+    #[test]
+    fn test_significant_figures() {
+        let figures = |figures| FormatChange::Digits(Digits::SignificantFigures(figures));
+        assert_eq!(format("3.14159", figures(3)), "3.14");
+        assert_eq!(format("1234", figures(2)), "1,200");
+        assert_eq!(format("0.0012", figures(3)), "0.00120");
+        assert_eq!(format("999.7", figures(3)), "1,000");
+        assert_eq!(format("-6.02214076E23", figures(4)), "-6.022E23");
+    }
+
+    #[test]
+    fn test_notation() {
+        let notation = FormatChange::Notation;
+        assert_eq!(format("12345", notation(Notation::Scientific)), "1.2345E4");
+        assert_eq!(format("12345", notation(Notation::Engineering)), "12.345E3");
+        assert_eq!(format("0.00012", notation(Notation::Engineering)), "120E-6");
+        assert_eq!(
+            format("1E12", notation(Notation::Plain)),
+            "1,000,000,000,000"
+        );
+
+        let format = FloatFormat {
+            digits: Digits::DecimalPlaces(2),
+            notation: Notation::Scientific,
+            ..FloatFormat::default()
+        };
+        assert_eq!(Float::parse("12345").unwrap().format(&format), "1.23E4");
+    }
+
+    #[test]
+    fn test_locale() {
+        let german = Locale::from_name("de").unwrap();
+        assert_eq!(
+            german.normalize_numbers("3,5 kg + 1.234,5 g * max(1, 2) / H2O"),
+            "3.5 kg + 1_234.5 g * max(1, 2) / H2O"
+        );
+        assert_eq!(
+            Float::parse_localized("3,5", &german).unwrap(),
+            Float::parse("3.5").unwrap()
+        );
+
+        let swiss = Locale::from_name("de-CH").unwrap();
+        assert_eq!(swiss.normalize_numbers("1'234'567.5"), "1_234_567.5");
+
+        let french = Locale::from_name("fr").unwrap();
+        assert_eq!(french.normalize_numbers("1 234,5 m"), "1_234.5 m");
+    }
+
+    #[test]
+    fn test_separators() {
+        let format = FloatFormat {
+            group_separator: '.',
+            decimal_separator: ',',
+            ..FloatFormat::default()
+        };
+        assert_eq!(
+            Float::parse("1234567.5").unwrap().format(&format),
+            "1.234.567,5"
+        );
+
+        let format = FloatFormat {
+            grouping: false,
+            ..FloatFormat::default()
+        };
+        assert_eq!(Float::parse("1234567").unwrap().format(&format), "1234567");
+    }
+}
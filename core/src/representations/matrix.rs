@@ -0,0 +1,659 @@
+use crate::representations::{Complex, Dimension, Float, FloatContext, Rational, Value};
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+/// A matrix of numbers, as in `[[1, 2], [3, 4]] m`. Each entry has its own units, so a matrix
+/// can mix units as long as they fit together, as the matrices of engineering often do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    columns: usize,
+    /// The entries, row by row
+    entries: Vec<Value>,
+}
+
+/// Why a matrix operation failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatrixError {
+    /// Rows of different lengths, or no entries at all
+    Ragged,
+    /// Two matrices whose sizes don't fit the operation, as rows and columns
+    Shape((usize, usize), (usize, usize)),
+    /// A matrix that needs to be square but isn't, as rows and columns
+    NotSquare(usize, usize),
+    /// Entries whose units don't fit together
+    Units,
+    Singular,
+    NoConvergence,
+}
+
+impl Display for MatrixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatrixError::Ragged => write!(
+                f,
+                "Every row of a matrix must have the same number of entries, and at least one"
+            ),
+            MatrixError::Shape((a_rows, a_columns), (b_rows, b_columns)) => write!(
+                f,
+                "A {}×{} matrix and a {}×{} matrix don't fit together",
+                a_rows, a_columns, b_rows, b_columns
+            ),
+            MatrixError::NotSquare(rows, columns) => {
+                write!(f, "The matrix must be square, not {}×{}", rows, columns)
+            }
+            MatrixError::Units => write!(f, "The units of the entries don't fit together"),
+            MatrixError::Singular => write!(f, "The matrix is singular, so it has no inverse"),
+            MatrixError::NoConvergence => write!(f, "The eigenvalues could not be found"),
+        }
+    }
+}
+
+impl Matrix {
+    pub fn from_rows(rows: Vec<Vec<Value>>) -> Result<Self, MatrixError> {
+        let columns = rows.first().map_or(0, Vec::len);
+        if columns == 0 || rows.iter().any(|row| row.len() != columns) {
+            return Err(MatrixError::Ragged);
+        }
+        Ok(Self {
+            rows: rows.len(),
+            columns,
+            entries: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The `n`×`n` identity matrix, without units.
+    pub fn identity(n: usize) -> Self {
+        Self::from_fn(n, n, |i, j| {
+            Value::from(if i == j {
+                Rational::one()
+            } else {
+                Rational::zero()
+            })
+        })
+    }
+
+    fn from_fn(rows: usize, columns: usize, mut entry: impl FnMut(usize, usize) -> Value) -> Self {
+        Self {
+            rows,
+            columns,
+            entries: (0..rows)
+                .flat_map(|i| (0..columns).map(move |j| (i, j)))
+                .map(|(i, j)| entry(i, j))
+                .collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> &Value {
+        &self.entries[row * self.columns + column]
+    }
+
+    /// The entries of a row, counting from 0.
+    pub fn row(&self, row: usize) -> &[Value] {
+        &self.entries[row * self.columns..(row + 1) * self.columns]
+    }
+
+    /// The entries, row by row.
+    pub fn entries(&self) -> &[Value] {
+        &self.entries
+    }
+
+    /// The units every entry shares, if they all have the same units.
+    pub fn shared_units(&self) -> Option<&Dimension> {
+        let first = &self.entries[0].dimension;
+        self.entries
+            .iter()
+            .all(|entry| entry.dimension == *first)
+            .then_some(first)
+    }
+
+    /// Applies an operation to every entry.
+    pub fn try_map<E>(&self, f: impl FnMut(&Value) -> Result<Value, E>) -> Result<Self, E> {
+        Ok(Self {
+            entries: self.entries.iter().map(f).collect::<Result<_, _>>()?,
+            ..*self
+        })
+    }
+
+    /// Applies an operation to each pair of entries in the same place in two matrices of the same
+    /// size.
+    pub fn try_zip<E>(
+        &self,
+        other: &Self,
+        mut f: impl FnMut(&Value, &Value) -> Result<Value, E>,
+    ) -> Result<Result<Self, E>, MatrixError> {
+        if self.shape() != other.shape() {
+            return Err(MatrixError::Shape(self.shape(), other.shape()));
+        }
+        Ok(self
+            .entries
+            .iter()
+            .zip(&other.entries)
+            .map(|(a, b)| f(a, b))
+            .collect::<Result<_, _>>()
+            .map(|entries| Self { entries, ..*self }))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_fn(self.columns, self.rows, |i, j| self.get(j, i).clone())
+    }
+
+    /// The matrix product, `self × other`.
+    pub fn try_mul(&self, other: &Self) -> Result<Self, MatrixError> {
+        if self.columns != other.rows {
+            return Err(MatrixError::Shape(self.shape(), other.shape()));
+        }
+
+        let mut entries = Vec::with_capacity(self.rows * other.columns);
+        for i in 0..self.rows {
+            for j in 0..other.columns {
+                let terms = (0..self.columns)
+                    .map(|k| self.get(i, k).try_mul(other.get(k, j)))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(MatrixError::Units)?;
+                // Zeros fit any units, so are left out of the sum unless every term is zero
+                let mut nonzero = terms
+                    .iter()
+                    .filter(|term| !is_zero(term) || term.uncertainty.is_some());
+                let sum = match nonzero.next() {
+                    Some(first) => nonzero.try_fold(first.clone(), |sum, term| sum.try_add(term)),
+                    None => Some(terms[0].clone()),
+                };
+                entries.push(sum.ok_or(MatrixError::Units)?);
+            }
+        }
+        Ok(Self {
+            rows: self.rows,
+            columns: other.columns,
+            entries,
+        })
+    }
+
+    /// Raises a square matrix to a whole power. Negative powers are powers of the inverse.
+    pub fn power(&self, exponent: &BigInt) -> Result<Self, MatrixError> {
+        self.check_square()?;
+        let mut base = if exponent.is_negative() {
+            self.inverse()?
+        } else {
+            self.clone()
+        };
+
+        // Squares the base for each binary digit of the exponent
+        let mut result = Self::identity(self.rows);
+        let mut remaining = exponent.abs();
+        while !remaining.is_zero() {
+            if remaining.bit(0) {
+                result = result.try_mul(&base)?;
+            }
+            remaining >>= 1;
+            if !remaining.is_zero() {
+                base = base.try_mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn determinant(&self) -> Result<Value, MatrixError> {
+        let (row_units, numbers, column_units) = self.factor_units()?;
+        let units = row_units
+            .iter()
+            .chain(&column_units)
+            .try_fold(one(), |product, units| product.try_mul(units))
+            .ok_or(MatrixError::Units)?;
+
+        let determinant = match numbers.eliminate(Self::from_fn(self.rows, 0, |_, _| one())) {
+            Ok((_, determinant)) => determinant,
+            Err(MatrixError::Singular) => Value::from(Rational::zero()),
+            Err(error) => return Err(error),
+        };
+        determinant.try_mul(&units).ok_or(MatrixError::Units)
+    }
+
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        let (row_units, numbers, column_units) = self.factor_units()?;
+        let (inverse, _) = numbers.eliminate(Self::identity(self.rows))?;
+
+        // The inverse of R N C is C⁻¹ N⁻¹ R⁻¹
+        let mut entries = Vec::with_capacity(inverse.entries.len());
+        for (i, column) in column_units.iter().enumerate() {
+            for (j, row) in row_units.iter().enumerate() {
+                let units = column.try_mul(row);
+                let entry = units.and_then(|units| inverse.get(i, j).try_div(&units));
+                entries.push(entry.ok_or(MatrixError::Units)?);
+            }
+        }
+        Ok(Self { entries, ..inverse })
+    }
+
+    /// Solves `self x = b` for `x`.
+    pub fn solve(&self, b: &[Value]) -> Result<Vec<Value>, MatrixError> {
+        let (mut row_units, numbers, mut column_units) = self.factor_units()?;
+        if b.len() != self.rows {
+            return Err(MatrixError::Shape(self.shape(), (b.len(), 1)));
+        }
+
+        // Units can move between the rows and the columns, so they are moved to match b
+        if let Some((b, row)) = b.iter().zip(&row_units).find(|(b, _)| !is_zero(b)) {
+            let shift = one()
+                .with_units(b.dimension())
+                .try_div(row)
+                .ok_or(MatrixError::Units)?;
+            for units in &mut row_units {
+                *units = units.try_mul(&shift).ok_or(MatrixError::Units)?;
+            }
+            for units in &mut column_units {
+                *units = units.try_div(&shift).ok_or(MatrixError::Units)?;
+            }
+        }
+
+        // R N C x = b, so N (C x) = R⁻¹ b
+        let b = b
+            .iter()
+            .zip(&row_units)
+            .map(|(b, units)| b.try_div(units).and_then(without_units).map(|b| vec![b]))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MatrixError::Units)?;
+        let (y, _) = numbers.eliminate(Self::from_rows(b)?)?;
+
+        y.entries
+            .iter()
+            .zip(&column_units)
+            .map(|(y, units)| y.try_div(units).ok_or(MatrixError::Units))
+            .collect()
+    }
+
+    /// Finds the eigenvalues, from smallest to largest, which may be complex. The entries on the
+    /// diagonal must all have the same units, which the eigenvalues are in.
+    pub fn eigenvalues(&self) -> Result<Vec<Value>, MatrixError> {
+        let (row_units, numbers, column_units) = self.factor_units()?;
+
+        // With the units of each diagonal entry k times those of the first, s, the matrix is
+        // R N C = s R (N K) R⁻¹, which has the eigenvalues of N K times s
+        let diagonal_units = row_units
+            .iter()
+            .zip(&column_units)
+            .map(|(row, column)| row.try_mul(column))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MatrixError::Units)?;
+        let units = diagonal_units[0].clone();
+        let scales = diagonal_units
+            .iter()
+            .map(|diagonal| diagonal.try_div(&units).and_then(without_units))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MatrixError::Units)?;
+        let scaled = Self::from_fn(self.rows, self.rows, |i, j| {
+            numbers.get(i, j).try_mul(&scales[j]).unwrap()
+        });
+
+        let mut eigenvalues = match scaled.triangular_diagonal() {
+            Some(diagonal) => diagonal,
+            None => {
+                let coefficients = scaled.characteristic_polynomial();
+                polynomial_roots(&coefficients)
+                    .ok_or(MatrixError::NoConvergence)?
+                    .into_iter()
+                    .map(|root| Value::new(Float::from(0), None).with_complex(root.cleaned()))
+                    .collect()
+            }
+        };
+
+        eigenvalues.sort_by(|a, b| {
+            let (a, b) = (a.complex(), b.complex());
+            a.re.partial_cmp(&b.re)
+                .unwrap_or(Ordering::Equal)
+                .then(a.im.partial_cmp(&b.im).unwrap_or(Ordering::Equal))
+        });
+        eigenvalues
+            .iter()
+            .map(|eigenvalue| eigenvalue.try_mul(&units).ok_or(MatrixError::Units))
+            .collect()
+    }
+
+    fn check_square(&self) -> Result<(), MatrixError> {
+        if self.rows != self.columns {
+            return Err(MatrixError::NotSquare(self.rows, self.columns));
+        }
+        Ok(())
+    }
+
+    /// Splits a square matrix into the units of each row, R, a matrix of numbers without units,
+    /// N, and the units of each column, C, so that each entry is `R[i] N[i][j] C[j]`. Zeros fit
+    /// any units.
+    fn factor_units(&self) -> Result<(Vec<Value>, Self, Vec<Value>), MatrixError> {
+        self.check_square()?;
+        let n = self.rows;
+        let mut row_units: Vec<Option<Value>> = vec![None; n];
+        let mut column_units: Vec<Option<Value>> = vec![None; n];
+
+        // Units spread from row to column and back through the non-zero entries. A row no
+        // units have reached yet starts without units.
+        loop {
+            let mut spread = false;
+            for (i, row) in row_units.iter_mut().enumerate() {
+                for (j, column) in column_units.iter_mut().enumerate() {
+                    let entry = self.get(i, j);
+                    if is_zero(entry) {
+                        continue;
+                    }
+                    let units = one().with_units(entry.dimension());
+                    match (&*row, &*column) {
+                        (Some(row), None) => *column = units.try_div(row),
+                        (None, Some(column)) => *row = units.try_div(column),
+                        _ => continue,
+                    }
+                    spread = true;
+                }
+            }
+            if spread {
+                continue;
+            }
+            match row_units.iter().position(Option::is_none) {
+                Some(i) => row_units[i] = Some(one()),
+                None => break,
+            }
+        }
+        let row_units = row_units
+            .into_iter()
+            .map(Option::unwrap)
+            .collect::<Vec<_>>();
+        let column_units = column_units
+            .into_iter()
+            .map(|units| units.unwrap_or_else(one))
+            .collect::<Vec<_>>();
+
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for (i, row) in row_units.iter().enumerate() {
+            for (j, column) in column_units.iter().enumerate() {
+                let number = self
+                    .get(i, j)
+                    .try_div(row)
+                    .and_then(|entry| entry.try_div(column))
+                    .and_then(without_units);
+                entries.push(number.ok_or(MatrixError::Units)?);
+            }
+        }
+        Ok((row_units, Self { entries, ..*self }, column_units))
+    }
+
+    /// Solves `self X = b` for `X` by Gauss-Jordan elimination of a square matrix without units,
+    /// returning `X` and the determinant.
+    fn eliminate(&self, b: Self) -> Result<(Self, Value), MatrixError> {
+        let n = self.rows;
+        let mut a = (0..n).map(|i| self.row(i).to_vec()).collect::<Vec<_>>();
+        let mut b = (0..n).map(|i| b.row(i).to_vec()).collect::<Vec<_>>();
+        let columns = b.first().map_or(0, Vec::len);
+
+        // Pivots that are only rounding error away from zero count as zero
+        let largest = self
+            .entries
+            .iter()
+            .map(|entry| entry.complex().magnitude())
+            .fold(Float::from(0), |a, b| if b > a { b } else { a });
+        let tolerance = &largest * &rounding_error();
+        let negligible = |value: &Value| match value.exact {
+            Some(_) => is_zero(value),
+            None => value.complex().magnitude() <= tolerance,
+        };
+
+        let mut determinant = one();
+        for k in 0..n {
+            // The largest entry left in the column is the pivot, for the least rounding error
+            let pivot_row = (k..n)
+                .max_by(|&i, &j| {
+                    let (a_i, a_j) = (a[i][k].complex(), a[j][k].complex());
+                    a_i.magnitude()
+                        .partial_cmp(&a_j.magnitude())
+                        .unwrap_or(Ordering::Equal)
+                })
+                .unwrap();
+            if negligible(&a[pivot_row][k]) {
+                return Err(MatrixError::Singular);
+            }
+            if pivot_row != k {
+                a.swap(pivot_row, k);
+                b.swap(pivot_row, k);
+                determinant = determinant.try_mul(&Value::from(-Rational::one())).unwrap();
+            }
+
+            let pivot = a[k][k].clone();
+            determinant = determinant.try_mul(&pivot).unwrap();
+            for entry in a[k][k..].iter_mut().chain(b[k].iter_mut()) {
+                *entry = entry.try_div(&pivot).unwrap();
+            }
+
+            for i in (0..n).filter(|&i| i != k) {
+                let factor = a[i][k].clone();
+                if is_zero(&factor) {
+                    continue;
+                }
+                let (pivot_a, pivot_b) = (a[k].clone(), b[k].clone());
+                let pairs = a[i][k..]
+                    .iter_mut()
+                    .zip(&pivot_a[k..])
+                    .chain(b[i].iter_mut().zip(&pivot_b));
+                for (entry, pivot_entry) in pairs {
+                    *entry = entry
+                        .try_sub(&factor.try_mul(pivot_entry).unwrap())
+                        .unwrap();
+                }
+            }
+        }
+
+        Ok((
+            Self {
+                rows: n,
+                columns,
+                entries: b.into_iter().flatten().collect(),
+            },
+            determinant,
+        ))
+    }
+
+    /// The diagonal of a triangular matrix, which is its eigenvalues.
+    fn triangular_diagonal(&self) -> Option<Vec<Value>> {
+        let n = self.rows;
+        let upper = (0..n).all(|i| (0..i).all(|j| is_zero(self.get(i, j))));
+        let lower = (0..n).all(|i| (i + 1..n).all(|j| is_zero(self.get(i, j))));
+        (upper || lower).then(|| (0..n).map(|i| self.get(i, i).clone()).collect())
+    }
+
+    /// The coefficients of `det(λI - self)` below the leading 1, starting with the constant,
+    /// found by the Faddeev-LeVerrier algorithm.
+    fn characteristic_polynomial(&self) -> Vec<Value> {
+        let n = self.rows;
+        let mut coefficients = vec![one(); n + 1];
+        let mut m = Self::from_fn(n, n, |_, _| Value::from(Rational::zero()));
+        for k in 1..=n {
+            // M_k = A M_(k-1) + c_(n-k+1) I
+            m = self.try_mul(&m).unwrap();
+            for i in 0..n {
+                let entry = &mut m.entries[i * n + i];
+                *entry = entry.try_add(&coefficients[n - k + 1]).unwrap();
+            }
+
+            // c_(n-k) = -tr(A M_k) / k
+            let product = self.try_mul(&m).unwrap();
+            let trace = (1..n).fold(product.get(0, 0).clone(), |trace, i| {
+                trace.try_add(product.get(i, i)).unwrap()
+            });
+            let count = Value::from(Rational::from_integer(BigInt::from(k)));
+            coefficients[n - k] = trace
+                .try_div(&count)
+                .and_then(|c| c.try_mul(&Value::from(-Rational::one())))
+                .unwrap();
+        }
+        coefficients.truncate(n);
+        coefficients
+    }
+}
+
+/// Finds the roots of a polynomial whose leading coefficient is 1 by the Durand-Kerner method,
+/// with twice the precision so that repeated roots are found to full precision.
+fn polynomial_roots(coefficients: &[Value]) -> Option<Vec<Complex>> {
+    let context = FloatContext::current();
+    let tolerance = rounding_error();
+    let working = FloatContext {
+        precision: context.precision * 2,
+        ..context
+    };
+
+    working.scope(|| {
+        let n = coefficients.len();
+        let coefficients = coefficients.iter().map(Value::complex).collect::<Vec<_>>();
+        let evaluate = |z: &Complex| {
+            coefficients
+                .iter()
+                .rev()
+                .fold(Complex::new(Float::from(1), Float::from(0)), |sum, c| {
+                    sum.mul(z).add(c)
+                })
+        };
+
+        // Every root is within this distance of zero
+        let radius = coefficients
+            .iter()
+            .map(Complex::magnitude)
+            .fold(Float::from(1), |a, b| &a + &b);
+        let mut roots = (0..n)
+            .map(|k| {
+                let angle = &(&Float::pi() * &Float::from(2 * k as u64)) / &Float::from(n as u64);
+                Complex::from_polar(&radius, &(&angle + &Float::from(0.4)))
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0..context.precision * 8 {
+            let mut converged = true;
+            for k in 0..n {
+                let denominator = (0..n).filter(|&j| j != k).fold(
+                    Complex::new(Float::from(1), Float::from(0)),
+                    |product, j| product.mul(&roots[k].sub(&roots[j])),
+                );
+                let step = evaluate(&roots[k]).div(&denominator);
+                let scale = &roots[k].magnitude() + &Float::from(1);
+                if !step.re.is_finite() || !step.im.is_finite() {
+                    return None;
+                }
+                if step.magnitude() > &scale * &tolerance {
+                    converged = false;
+                }
+                roots[k] = roots[k].sub(&step);
+            }
+            if converged {
+                return Some(roots);
+            }
+        }
+        None
+    })
+}
+
+/// The size of rounding error relative to a number, leaving some bits for errors that build up.
+fn rounding_error() -> Float {
+    let bits = FloatContext::current().precision as i32;
+    Float::from(2).pow(&Float::from(16 - bits))
+}
+
+fn one() -> Value {
+    Value::from(Rational::one())
+}
+
+fn is_zero(value: &Value) -> bool {
+    !value.is_complex() && value.value == Float::from(0)
+}
+
+/// The number if it has no units. Zeros lose any units they have.
+fn without_units(value: Value) -> Option<Value> {
+    if is_zero(&value) {
+        Some(Value::from(Rational::zero()))
+    } else {
+        Some(value).filter(Value::is_unitless)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&[i64]]) -> Matrix {
+        Matrix::from_rows(
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|n| Value::from(Rational::from_integer(BigInt::from(*n))))
+                        .collect()
+                })
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn exact(n: i64, d: i64) -> Value {
+        Value::from(Rational::new(BigInt::from(n), BigInt::from(d)))
+    }
+
+    #[test]
+    fn test_determinant_and_inverse() {
+        let a = matrix(&[&[1, 2], &[3, 4]]);
+        assert_eq!(a.determinant().unwrap(), exact(-2, 1));
+        assert_eq!(
+            a.inverse().unwrap(),
+            Matrix::from_rows(vec![
+                vec![exact(-2, 1), exact(1, 1)],
+                vec![exact(3, 2), exact(-1, 2)],
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            a.power(&BigInt::from(2)).unwrap(),
+            matrix(&[&[7, 10], &[15, 22]])
+        );
+
+        let singular = matrix(&[&[1, 2], &[2, 4]]);
+        assert_eq!(singular.determinant().unwrap(), exact(0, 1));
+        assert_eq!(singular.inverse(), Err(MatrixError::Singular));
+        assert_eq!(
+            matrix(&[&[1, 2, 3]]).determinant(),
+            Err(MatrixError::NotSquare(1, 3))
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        let a = matrix(&[&[2, 1], &[1, 3]]);
+        let x = a.solve(&[exact(3, 1), exact(5, 1)]).unwrap();
+        assert_eq!(x, vec![exact(4, 5), exact(7, 5)]);
+    }
+
+    #[test]
+    fn test_eigenvalues() {
+        let triangular = matrix(&[&[2, 5], &[0, 3]]);
+        assert_eq!(
+            triangular.eigenvalues().unwrap(),
+            vec![exact(2, 1), exact(3, 1)]
+        );
+
+        let symmetric = matrix(&[&[2, 1], &[1, 2]]).eigenvalues().unwrap();
+        assert_eq!(symmetric.len(), 2);
+        for (eigenvalue, expected) in symmetric.iter().zip([1.0, 3.0]) {
+            assert!(!eigenvalue.is_complex());
+            assert!((eigenvalue.value.to_f64() - expected).abs() < 1e-12);
+        }
+
+        let rotation = matrix(&[&[0, -1], &[1, 0]]).eigenvalues().unwrap();
+        assert_eq!(rotation[0].complex().im.to_f64(), -1.0);
+        assert_eq!(rotation[1].complex().im.to_f64(), 1.0);
+    }
+}
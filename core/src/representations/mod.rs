@@ -1,9 +1,23 @@
+mod complex;
 mod dimension;
 mod expression;
 mod float;
+mod format;
+mod matrix;
+mod object;
+mod rational;
+mod significant_figures;
+mod uncertainty;
 mod value;
 
+pub use complex::*;
 pub use dimension::*;
 pub use expression::*;
 pub use float::*;
+pub use format::*;
+pub use matrix::*;
+pub use object::*;
+pub use rational::*;
+pub use significant_figures::*;
+pub use uncertainty::*;
 pub use value::*;
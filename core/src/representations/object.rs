@@ -0,0 +1,146 @@
+use crate::representations::{
+    Compound, Dimension, FloatContext, FloatFormat, Matrix, Reaction, Value,
+};
+use std::fmt::Display;
+
+/// Anything an expression can evaluate to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Value(Value),
+    /// A list of numbers, as in `[1, 2, 3] m`
+    List(Vec<Value>),
+    /// A matrix, as in `[[1, 2], [3, 4]] m`
+    Matrix(Matrix),
+    Compound(Compound),
+    Reaction(Reaction),
+    Text(String),
+}
+
+impl Object {
+    /// Returns a short name for the kind of object, for use in error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Object::Value(_) => "number",
+            Object::List(_) => "list",
+            Object::Matrix(_) => "matrix",
+            Object::Compound(_) => "compound",
+            Object::Reaction(_) => "reaction",
+            Object::Text(_) => "text",
+        }
+    }
+
+    pub fn as_value(&self) -> Option<&Value> {
+        match self {
+            Object::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_value(self) -> Option<Value> {
+        match self {
+            Object::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<Value> for Object {
+    fn from(value: Value) -> Self {
+        Object::Value(value)
+    }
+}
+
+impl From<Matrix> for Object {
+    fn from(matrix: Matrix) -> Self {
+        Object::Matrix(matrix)
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Object::Value(value) => write_value(f, value, true),
+            Object::List(values) => {
+                // Units every number shares are written once, after the list
+                let shared = values
+                    .first()
+                    .map(|first| &first.dimension)
+                    .filter(|dimension| values.iter().all(|value| value.dimension == **dimension));
+                list_context().scope(|| write_list(f, values, shared.is_none()))?;
+                write_units(f, shared)
+            }
+            Object::Matrix(matrix) => {
+                // A matrix is written as a list of its rows
+                let shared = matrix.shared_units();
+                list_context().scope(|| {
+                    write!(f, "[")?;
+                    for i in 0..matrix.rows() {
+                        if i > 0 {
+                            write!(f, "{}", list_separator())?;
+                        }
+                        write_list(f, matrix.row(i), shared.is_none())?;
+                    }
+                    write!(f, "]")
+                })?;
+                write_units(f, shared)
+            }
+            Object::Compound(compound) => write!(f, "{}", compound),
+            Object::Reaction(reaction) => write!(f, "{}", reaction),
+            Object::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// The context lists are written in. Commas in the numbers would be mistaken for separators, so
+/// digits aren't grouped with commas.
+fn list_context() -> FloatContext {
+    let context = FloatContext::current();
+    let format = context.format;
+    let grouping = format.grouping && format.group_separator != ',';
+    FloatContext {
+        format: FloatFormat { grouping, ..format },
+        ..context
+    }
+}
+
+/// Lists of numbers with decimal commas are separated by semicolons.
+fn list_separator() -> &'static str {
+    match FloatContext::current().format.decimal_separator {
+        ',' => "; ",
+        _ => ", ",
+    }
+}
+
+fn write_list(
+    f: &mut std::fmt::Formatter<'_>,
+    values: &[Value],
+    with_units: bool,
+) -> std::fmt::Result {
+    write!(f, "[")?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", list_separator())?;
+        }
+        write_value(f, value, with_units)?;
+    }
+    write!(f, "]")
+}
+
+fn write_units(f: &mut std::fmt::Formatter<'_>, shared: Option<&Dimension>) -> std::fmt::Result {
+    match shared {
+        Some(dimension) if !dimension.is_unitless() => write!(f, " {}", dimension),
+        _ => Ok(()),
+    }
+}
+
+fn write_value(
+    f: &mut std::fmt::Formatter<'_>,
+    value: &Value,
+    with_units: bool,
+) -> std::fmt::Result {
+    write!(f, "{}", value.format_number())?;
+    if with_units && !value.dimension.is_unitless() {
+        write!(f, " {}", value.dimension)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,320 @@
+use crate::representations::{Float, FloatFormat};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::collections::HashMap;
+
+/// An exact fraction of arbitrarily large integers.
+pub type Rational = BigRational;
+
+/// Powers with larger exponents than this are calculated approximately, since their exact
+/// digits would take too long to find.
+const MAX_EXACT_EXPONENT: u32 = 10_000;
+
+/// Splits a number typed in another base, as in `0xFF`, `0b1010` or `0o755`, into its base and
+/// digits.
+pub fn split_radix_prefix(literal: &str) -> Option<(u32, &str)> {
+    let radix = match literal.get(..2)? {
+        "0x" => 16,
+        "0b" => 2,
+        "0o" => 8,
+        _ => return None,
+    };
+    Some((radix, &literal[2..]))
+}
+
+/// Reads a number as typed, such as `2.50`, `1.5e3` or `0xFF`, as an exact fraction.
+pub fn parse_rational(literal: &str) -> Option<Rational> {
+    let literal = literal.replace(['_', ','], "");
+    if let Some((radix, digits)) = split_radix_prefix(&literal) {
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let digits = BigInt::parse_bytes(format!("{}{}", integer, fraction).as_bytes(), radix)?;
+        return Some(Rational::new(
+            digits,
+            BigInt::from(radix).pow(fraction.len() as u32),
+        ));
+    }
+    let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (literal.as_str(), 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = format!("{}{}", integer, fraction).parse::<BigInt>().ok()?;
+    let exponent = exponent.checked_sub(fraction.len() as i32)?;
+    if exponent.unsigned_abs() > MAX_EXACT_EXPONENT {
+        return None;
+    }
+
+    let scale = BigInt::from(10).pow(exponent.unsigned_abs());
+    if exponent >= 0 {
+        Some(Rational::from_integer(digits * scale))
+    } else {
+        Some(Rational::new(digits, scale))
+    }
+}
+
+/// Raises an exact fraction to an exact whole power, if the result is small enough to find.
+pub fn rational_pow(base: &Rational, exponent: &Rational) -> Option<Rational> {
+    if !exponent.is_integer() {
+        return None;
+    }
+    let exponent = exponent.to_integer().to_i32()?;
+    if exponent.unsigned_abs() > MAX_EXACT_EXPONENT || (base.is_zero() && exponent < 0) {
+        return None;
+    }
+    Some(base.pow(exponent))
+}
+
+impl Float {
+    /// The closest number to an exact fraction at the current precision.
+    pub fn from_rational(rational: &Rational) -> Self {
+        let numerator = Float::parse(&rational.numer().to_string()).unwrap();
+        let denominator = Float::parse(&rational.denom().to_string()).unwrap();
+        &numerator / &denominator
+    }
+}
+
+/// Recovers the exact fraction of a whole number or of one over a whole number, such as the
+/// ratio between two units.
+pub(crate) fn exact_ratio(ratio: &Float) -> Option<Rational> {
+    let whole = |value: &Float| {
+        (value.abs() < Float::from(1e15))
+            .then(|| Rational::from_float(value.round().to_f64()))
+            .flatten()
+    };
+
+    match whole(ratio) {
+        Some(rational) if Float::from_rational(&rational) == *ratio => Some(rational),
+        _ => {
+            let reciprocal = whole(&ratio.recip())
+                .filter(|whole| !whole.is_zero())?
+                .recip();
+            (Float::from_rational(&reciprocal) == *ratio).then_some(reciprocal)
+        }
+    }
+}
+
+/// Recovers the whole number a number is, or nothing if it isn't one.
+pub(crate) fn whole_number(value: &Float) -> Option<BigInt> {
+    if value.round() != *value {
+        return None;
+    }
+
+    match value.decimal_parts() {
+        Ok((negative, digits, exponent)) if (0..=MAX_EXACT_EXPONENT as i32).contains(&exponent) => {
+            let length = exponent as usize + 1;
+            let whole = format!("{:0<length$}", digits)[..length]
+                .parse::<BigInt>()
+                .ok()?;
+            Some(if negative { -whole } else { whole })
+        }
+        Err(text) if text.trim_start_matches('-') == "0.0" => Some(BigInt::zero()),
+        _ => None,
+    }
+}
+
+/// Formats a whole number in another base, as in `0xFF`. With a word size, negative numbers are
+/// shown in two's complement and digits are padded to fill the word.
+pub fn format_radix(number: &BigInt, format: &FloatFormat) -> String {
+    let number = match format.word_size {
+        Some(bits) => number.mod_floor(&(BigInt::from(1) << bits)),
+        None => number.clone(),
+    };
+    with_radix_prefix(
+        number.is_negative(),
+        &radix_digits(&number, format),
+        format.radix,
+    )
+}
+
+/// Formats a number with a fraction in another base, as in `0x1.8`, with at most `max_digits`
+/// digits after the point. Word sizes only apply to whole numbers, so negative numbers keep
+/// their sign.
+pub fn format_radix_fraction(
+    number: &Float,
+    format: &FloatFormat,
+    max_digits: usize,
+) -> Option<String> {
+    let whole = number.trunc();
+    let integer = whole_number(&whole)?;
+    let radix = Float::from(format.radix);
+
+    let mut fraction = (number - &whole).abs();
+    let mut digits = String::new();
+    while fraction != Float::from(0) && digits.len() < max_digits {
+        fraction = &fraction * &radix;
+        let digit = fraction.trunc();
+        fraction = &fraction - &digit;
+        digits.push(char::from_digit(digit.to_f64() as u32, format.radix)?.to_ascii_uppercase());
+    }
+    let digits = digits.trim_end_matches('0');
+
+    let integer = radix_digits(&integer.abs(), format);
+    let digits = match digits {
+        "" => integer,
+        digits => format!("{}{}{}", integer, format.decimal_separator, digits),
+    };
+    Some(with_radix_prefix(
+        *number < Float::from(0),
+        &digits,
+        format.radix,
+    ))
+}
+
+/// Writes the digits of a whole number's magnitude in another base, padded to the word size and
+/// grouped as the format asks.
+fn radix_digits(number: &BigInt, format: &FloatFormat) -> String {
+    let mut digits = number.magnitude().to_str_radix(format.radix).to_uppercase();
+
+    // Each digit of a power of two base is a whole number of bits
+    if let (Some(bits), true) = (format.word_size, format.radix.is_power_of_two()) {
+        let width = bits.div_ceil(format.radix.trailing_zeros()) as usize;
+        digits = format!("{:0>width$}", digits);
+    }
+    // Binary and hexadecimal digits are grouped in fours, as in `0b1010_0101`
+    if format.grouping && matches!(format.radix, 2 | 16) {
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(4) {
+                grouped.push('_');
+            }
+            grouped.push(digit);
+        }
+        digits = grouped;
+    }
+    digits
+}
+
+fn with_radix_prefix(negative: bool, digits: &str, radix: u32) -> String {
+    let sign = if negative { "-" } else { "" };
+    match radix {
+        2 => format!("{}0b{}", sign, digits),
+        8 => format!("{}0o{}", sign, digits),
+        16 => format!("{}0x{}", sign, digits),
+        radix => {
+            const SUBSCRIPTS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+            let base = radix
+                .to_string()
+                .chars()
+                .map(|digit| SUBSCRIPTS[digit.to_digit(10).unwrap() as usize])
+                .collect::<String>();
+            format!("{}{}{}", sign, digits, base)
+        }
+    }
+}
+
+/// Formats an exact fraction in lowest terms, as in `-7/3`, or as a whole number.
+pub fn format_fraction(rational: &Rational) -> String {
+    if rational.is_integer() {
+        rational.numer().to_string()
+    } else {
+        format!("{}/{}", rational.numer(), rational.denom())
+    }
+}
+
+/// Formats an exact fraction as a decimal with its repeating digits in brackets, as in
+/// `0.1(6)`, or nothing if more than `max_digits` decimals would be needed.
+pub fn format_repeating(
+    rational: &Rational,
+    max_digits: usize,
+    format: &FloatFormat,
+) -> Option<String> {
+    let (integer, mut remainder) = rational.numer().abs().div_rem(rational.denom());
+    let mut decimals = String::new();
+    // Where each remainder was first seen, since the digits repeat from there
+    let mut seen = HashMap::new();
+
+    while !remainder.is_zero() {
+        if let Some(start) = seen.insert(remainder.clone(), decimals.len()) {
+            decimals.insert(start, '(');
+            decimals.push(')');
+            break;
+        }
+        if decimals.len() >= max_digits {
+            return None;
+        }
+        let (digit, rest) = (remainder * BigInt::from(10)).div_rem(rational.denom());
+        decimals.push_str(&digit.to_string());
+        remainder = rest;
+    }
+
+    let sign = if rational.is_negative() { "-" } else { "" };
+    if decimals.is_empty() {
+        Some(format!("{}{}", sign, integer))
+    } else {
+        Some(format!(
+            "{}{}{}{}",
+            sign, integer, format.decimal_separator, decimals
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational(numerator: i64, denominator: i64) -> Rational {
+        Rational::new(BigInt::from(numerator), BigInt::from(denominator))
+    }
+
+    #[test]
+    fn test_parse_rational() {
+        assert_eq!(parse_rational("2.50"), Some(rational(5, 2)));
+        assert_eq!(parse_rational("1,000"), Some(rational(1000, 1)));
+        assert_eq!(parse_rational("1.5e-3"), Some(rational(3, 2000)));
+        assert_eq!(parse_rational("1e100000"), None);
+        assert_eq!(parse_rational("0xFF"), Some(rational(255, 1)));
+        assert_eq!(parse_rational("0b1_0000"), Some(rational(16, 1)));
+        assert_eq!(parse_rational("0o7.4"), Some(rational(15, 2)));
+    }
+
+    #[test]
+    fn test_format_radix() {
+        let format = |radix, word_size| FloatFormat {
+            radix,
+            word_size,
+            ..FloatFormat::default()
+        };
+
+        assert_eq!(format_radix(&BigInt::from(255), &format(16, None)), "0xFF");
+        assert_eq!(
+            format_radix(&BigInt::from(-1), &format(16, Some(16))),
+            "0xFFFF"
+        );
+        assert_eq!(
+            format_radix(&BigInt::from(5), &format(2, Some(8))),
+            "0b0000_0101"
+        );
+        assert_eq!(format_radix(&BigInt::from(35), &format(36, None)), "Z₃₆");
+
+        let fraction = |number: &str, radix| {
+            format_radix_fraction(&Float::parse(number).unwrap(), &format(radix, None), 8).unwrap()
+        };
+        assert_eq!(fraction("1.5", 16), "0x1.8");
+        assert_eq!(fraction("-0.75", 2), "-0b0.11");
+        assert_eq!(fraction("0.1", 16), "0x0.19999999");
+    }
+
+    #[test]
+    fn test_format_exact() {
+        let format = FloatFormat::default();
+
+        assert_eq!(format_fraction(&rational(-14, 6)), "-7/3");
+        assert_eq!(format_fraction(&rational(6, 3)), "2");
+        assert_eq!(
+            format_repeating(&rational(1, 6), 30, &format).unwrap(),
+            "0.1(6)"
+        );
+        assert_eq!(
+            format_repeating(&rational(-22, 7), 30, &format).unwrap(),
+            "-3.(142857)"
+        );
+        assert_eq!(
+            format_repeating(&rational(5, 4), 30, &format).unwrap(),
+            "1.25"
+        );
+        assert_eq!(format_repeating(&rational(1, 97), 30, &format), None);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::representations::Float;
+
+/// The digits of a measured number that are significant, as in the three of `2.50 g`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignificantFigures {
+    pub count: usize,
+    /// The power of ten of the last significant digit, -2 for `2.50`
+    pub last_place: i32,
+}
+
+impl SignificantFigures {
+    /// Counts the significant figures of a number as it was typed. Leading zeros are never
+    /// significant, and trailing zeros are only significant after a decimal point. Whole numbers
+    /// typed without an exponent are counted as exact when `integers_exact` is set.
+    pub fn of_literal(literal: &str, integers_exact: bool) -> Option<Self> {
+        let literal = literal.replace(['_', ','], "");
+        let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+            None if integers_exact && !literal.contains('.') => return None,
+            None => (literal.as_str(), 0),
+        };
+
+        let (count, last_place) = match mantissa.split_once('.') {
+            Some((integer, fraction)) => {
+                let digits = format!("{}{}", integer, fraction);
+                let count = digits.trim_start_matches('0').len();
+                (count, -(fraction.len() as i32))
+            }
+            None => {
+                let digits = mantissa.trim_start_matches('0');
+                let significant = digits.trim_end_matches('0');
+                (significant.len(), (digits.len() - significant.len()) as i32)
+            }
+        };
+
+        Some(Self::zero_safe(count, last_place + exponent))
+    }
+
+    /// The precision of a result with `count` significant figures.
+    pub fn with_count(value: &Float, count: usize) -> Self {
+        match value.magnitude() {
+            Some(magnitude) => Self {
+                count,
+                last_place: magnitude - count as i32 + 1,
+            },
+            None => Self::zero_safe(0, 1 - count as i32),
+        }
+    }
+
+    /// The precision of a result whose last significant digit is at `last_place`.
+    pub fn at_place(value: &Float, last_place: i32) -> Self {
+        match value.magnitude() {
+            Some(magnitude) => Self {
+                count: (magnitude - last_place + 1).max(1) as usize,
+                last_place,
+            },
+            None => Self::zero_safe(0, last_place),
+        }
+    }
+
+    /// The same number of significant figures, for the value after it was converted.
+    pub fn rescaled(self, value: &Float) -> Self {
+        match value.magnitude() {
+            Some(_) => Self::with_count(value, self.count),
+            None => self,
+        }
+    }
+
+    /// Applies the rule for multiplying and dividing: the result has as many significant figures
+    /// as the operand with the fewest.
+    pub fn product(value: &Float, lhs: Option<Self>, rhs: Option<Self>) -> Option<Self> {
+        let count = match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.count.min(rhs.count),
+            (Some(figures), None) | (None, Some(figures)) => figures.count,
+            (None, None) => return None,
+        };
+        Some(Self::with_count(value, count))
+    }
+
+    /// Applies the rule for adding and subtracting: the result is significant to the last place
+    /// both operands are significant to.
+    pub fn sum(value: &Float, lhs: Option<Self>, rhs: Option<Self>) -> Option<Self> {
+        let last_place = match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.last_place.max(rhs.last_place),
+            (Some(figures), None) | (None, Some(figures)) => figures.last_place,
+            (None, None) => return None,
+        };
+        Some(Self::at_place(value, last_place))
+    }
+
+    /// Zero has no leading digit, so it is shown with as many figures as it has decimals.
+    fn zero_safe(count: usize, last_place: i32) -> Self {
+        match count {
+            0 => Self {
+                count: (1 - last_place).max(1) as usize,
+                last_place,
+            },
+            count => Self { count, last_place },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of_literal() {
+        let figures = |literal| {
+            SignificantFigures::of_literal(literal, true).map(|f| (f.count, f.last_place))
+        };
+
+        assert_eq!(figures("2.50"), Some((3, -2)));
+        assert_eq!(figures("0.0025"), Some((2, -4)));
+        assert_eq!(figures("1.50e3"), Some((3, 1)));
+        assert_eq!(figures("1,000.0"), Some((5, -1)));
+        assert_eq!(figures("12"), None);
+        assert_eq!(
+            SignificantFigures::of_literal("1500", false).map(|f| (f.count, f.last_place)),
+            Some((2, 2))
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let measured = |literal| SignificantFigures::of_literal(literal, false);
+
+        let product = SignificantFigures::product(
+            &Float::parse("2.4").unwrap(),
+            measured("2.50"),
+            measured("0.96"),
+        );
+        assert_eq!(product.map(|f| f.count), Some(2));
+
+        let sum = SignificantFigures::sum(
+            &Float::parse("13.6").unwrap(),
+            measured("12.52"),
+            measured("1.1"),
+        );
+        assert_eq!(sum.map(|f| (f.count, f.last_place)), Some((3, -1)));
+
+        let exact = SignificantFigures::product(&Float::from(5), measured("2.5"), None);
+        assert_eq!(exact.map(|f| f.count), Some(2));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::representations::Float;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_SOURCE: AtomicUsize = AtomicUsize::new(0);
+
+/// The standard uncertainty of a value, as in `5.0 ± 0.2 m`, propagated linearly.
+///
+/// The uncertainty is kept as the contribution of each independent measurement it came from, so
+/// that a measurement used twice is correlated with itself and `x - x` is exact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uncertainty(BTreeMap<usize, Float>);
+
+impl Uncertainty {
+    /// An independent measurement with the given standard uncertainty.
+    pub fn new(standard: Float) -> Self {
+        let source = NEXT_SOURCE.fetch_add(1, Ordering::Relaxed);
+        Self(BTreeMap::from([(source, standard)]))
+    }
+
+    /// The combined standard uncertainty of all measurements.
+    pub fn standard(&self) -> Float {
+        self.0
+            .values()
+            .fold(Float::from(0), |sum, part| &sum + &(part * part))
+            .sqrt()
+    }
+
+    /// The uncertainty of the value multiplied by `factor`.
+    pub fn scaled(&self, factor: &Float) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|(source, part)| (*source, part * factor))
+                .collect(),
+        )
+    }
+
+    /// The uncertainty of a result that changes by the given sensitivity to each of its inputs.
+    /// Exact inputs have no uncertainty, and a result with only exact inputs is exact.
+    pub fn linear<'a>(terms: impl IntoIterator<Item = (Option<&'a Self>, Float)>) -> Option<Self> {
+        terms
+            .into_iter()
+            .filter_map(|(uncertainty, sensitivity)| Some(uncertainty?.scaled(&sensitivity)))
+            .reduce(|mut sum, term| {
+                for (source, part) in term.0 {
+                    let total = match sum.0.get(&source) {
+                        Some(existing) => existing + &part,
+                        None => part,
+                    };
+                    sum.0.insert(source, total);
+                }
+                sum
+            })
+    }
+
+    /// Combines with an independent measurement of the same value.
+    pub fn with_independent(&self, standard: Float) -> Self {
+        Self::linear([
+            (Some(self), Float::from(1)),
+            (Some(&Self::new(standard)), Float::from(1)),
+        ])
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_independent_uncertainties_add_in_quadrature() {
+        let a = Uncertainty::new(Float::from(3));
+        let b = Uncertainty::new(Float::from(4));
+
+        let sum = Uncertainty::linear([(Some(&a), Float::from(1)), (Some(&b), Float::from(1))]);
+        assert_eq!(sum.unwrap().standard(), Float::from(5));
+    }
+
+    #[test]
+    fn test_correlated_uncertainties_cancel() {
+        let a = Uncertainty::new(Float::from(3));
+
+        let difference =
+            Uncertainty::linear([(Some(&a), Float::from(1)), (Some(&a), Float::from(-1))]);
+        assert_eq!(difference.unwrap().standard(), Float::from(0));
+
+        let double = Uncertainty::linear([(Some(&a), Float::from(1)), (Some(&a), Float::from(1))]);
+        assert_eq!(double.unwrap().standard(), Float::from(6));
+    }
+}
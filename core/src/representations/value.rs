@@ -1,11 +1,31 @@
+use num_bigint::BigInt;
+use num_traits::Zero;
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
 pub use crate::representations::*;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Value {
     pub dimension: Dimension,
     pub value: Float,
+    /// How precisely the value was measured, or nothing if it is exact
+    pub significant_figures: Option<SignificantFigures>,
+    /// The standard uncertainty of the value, as in `5.0 ± 0.2 m`
+    pub uncertainty: Option<Uncertainty>,
+    /// The exact fraction the value is, if it was only calculated from typed numbers with basic
+    /// arithmetic
+    pub exact: Option<Rational>,
+    /// The imaginary part of a complex value, as in `3 + 4i`, with `value` as the real part
+    pub imaginary: Option<Float>,
+}
+
+// Values are equal regardless of how precisely they were measured
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.dimension == other.dimension
+            && self.value == other.value
+            && self.imaginary == other.imaginary
+    }
 }
 
 impl From<f64> for Value {
@@ -13,6 +33,19 @@ impl From<f64> for Value {
         Self {
             dimension: Default::default(),
             value: Float::from(value),
+            significant_figures: None,
+            uncertainty: None,
+            exact: Rational::from_float(value),
+            imaginary: None,
+        }
+    }
+}
+
+impl From<Rational> for Value {
+    fn from(value: Rational) -> Self {
+        Self {
+            exact: Some(value.clone()),
+            ..Self::new(Float::from_rational(&value), None)
         }
     }
 }
@@ -22,6 +55,195 @@ impl Value {
         Self {
             dimension: dimension.unwrap_or(Default::default()),
             value,
+            significant_figures: None,
+            uncertainty: None,
+            exact: None,
+            imaginary: None,
+        }
+    }
+
+    pub fn is_complex(&self) -> bool {
+        self.imaginary.is_some()
+    }
+
+    /// The value as a complex number, which is real unless it has an imaginary part.
+    pub fn complex(&self) -> Complex {
+        Complex::new(
+            self.value.clone(),
+            self.imaginary.clone().unwrap_or(Float::from(0)),
+        )
+    }
+
+    /// Replaces the number with a complex one, which is neither exact nor uncertain.
+    pub fn with_complex(self, complex: Complex) -> Self {
+        Self {
+            imaginary: (!complex.is_real()).then_some(complex.im),
+            value: complex.re,
+            exact: None,
+            uncertainty: None,
+            ..self
+        }
+    }
+
+    /// Marks the value as exactly the given fraction, which it is then calculated as.
+    pub fn with_exact(self, exact: Option<Rational>) -> Self {
+        Self {
+            value: exact
+                .as_ref()
+                .map(Float::from_rational)
+                .unwrap_or(self.value),
+            exact,
+            ..self
+        }
+    }
+
+    /// Marks the value as measured to the given precision.
+    pub fn measured(self, significant_figures: Option<SignificantFigures>) -> Self {
+        Self {
+            significant_figures,
+            ..self
+        }
+    }
+
+    /// Adds an independent standard uncertainty to the value, in the value's units.
+    pub fn with_uncertainty(self, standard: Float) -> Self {
+        let uncertainty = match &self.uncertainty {
+            Some(uncertainty) => uncertainty.with_independent(standard),
+            None => Uncertainty::new(standard),
+        };
+        Self {
+            uncertainty: Some(uncertainty),
+            ..self
+        }
+    }
+
+    /// Formats the number, and its uncertainty if it has one, as in `5.0 ± 0.2`.
+    ///
+    /// Unless digits are chosen, the uncertainty is shown to two significant figures and the
+    /// number to the same decimal place.
+    pub fn format_number(&self) -> String {
+        if let Some(text) = self.format_radix() {
+            return text;
+        }
+        if self.is_complex() {
+            return self.format_complex();
+        }
+
+        let Some(uncertainty) = self
+            .uncertainty
+            .as_ref()
+            .map(Uncertainty::standard)
+            .filter(|standard| *standard != Float::from(0))
+        else {
+            return self
+                .format_exact()
+                .unwrap_or_else(|| self.value.to_string());
+        };
+
+        let format = FloatContext::current().format;
+        if format.digits != Digits::Default {
+            return format!(
+                "{} ± {}",
+                self.value.format(&format),
+                uncertainty.format(&format)
+            );
+        }
+
+        // An uncertainty that rounds to `0.20` is shown as `0.2`, and the number stops there too
+        let uncertainty_figures = SignificantFigures::with_count(&uncertainty, 2);
+        let scale = Float::from(10).pow(&Float::from(uncertainty_figures.last_place));
+        let leading = (&uncertainty / &scale).round();
+        let uncertainty_figures = if leading.to_f64() % 10.0 == 0.0 {
+            SignificantFigures::with_count(&uncertainty, 1)
+        } else {
+            uncertainty_figures
+        };
+
+        let figures = SignificantFigures::at_place(&self.value, uncertainty_figures.last_place);
+        format!(
+            "{} ± {}",
+            self.value.format(&FloatFormat {
+                digits: Digits::SignificantFigures(figures.count),
+                ..format
+            }),
+            uncertainty.format(&FloatFormat {
+                digits: Digits::SignificantFigures(uncertainty_figures.count),
+                ..format
+            })
+        )
+    }
+
+    /// Formats a complex value as in `3 + 4i`, or as in `5 ∠ 53.13°` in polar notation. Values
+    /// with units are put in brackets, as in `(3 + 4i) Ω`.
+    fn format_complex(&self) -> String {
+        let format = FloatContext::current().format;
+        let complex = self.complex();
+        let zero = Float::from(0);
+
+        let number = if format.notation == Notation::Polar {
+            let degrees = &(&complex.argument() * &Float::from(180)) / &Float::pi();
+            format!(
+                "{} ∠ {}°",
+                complex.magnitude().format(&format),
+                degrees.format(&format)
+            )
+        } else {
+            let imaginary = match complex.im.abs().format(&format) {
+                one if one == "1" => format.imaginary_unit.to_string(),
+                coefficient => format!("{}{}", coefficient, format.imaginary_unit),
+            };
+            let sign = if complex.im < zero { "-" } else { "+" };
+            if complex.re == zero {
+                format!("{}{}", sign.trim_start_matches('+'), imaginary)
+            } else {
+                format!("{} {} {}", complex.re.format(&format), sign, imaginary)
+            }
+        };
+
+        if self.is_unitless() || (complex.re == zero && format.notation != Notation::Polar) {
+            number
+        } else {
+            format!("({})", number)
+        }
+    }
+
+    /// Formats an exact value as a fraction or repeating decimal, if the format asks for one.
+    fn format_exact(&self) -> Option<String> {
+        let context = FloatContext::current();
+        let exact = self.exact.as_ref()?;
+        match context.format.notation {
+            Notation::Fraction => Some(format_fraction(exact)),
+            Notation::Repeating => {
+                format_repeating(exact, context.display_digits(), &context.format)
+            }
+            _ => None,
+        }
+    }
+
+    /// Formats a real number in the base the format asks for, unless it is decimal. Fractions
+    /// get as many digits as decimals would.
+    fn format_radix(&self) -> Option<String> {
+        let context = FloatContext::current();
+        let format = context.format;
+        if format.radix == 10 || self.is_complex() {
+            return None;
+        }
+        if let Some(integer) = self.as_integer() {
+            return Some(format_radix(&integer, &format));
+        }
+        let max_digits =
+            (context.display_digits() as f64 / f64::from(format.radix).log10()) as usize;
+        format_radix_fraction(&self.value, &format, max_digits)
+    }
+
+    /// The whole number the value is, if it is a real whole number.
+    pub fn as_integer(&self) -> Option<BigInt> {
+        if self.is_complex() {
+            return None;
+        }
+        match &self.exact {
+            Some(exact) => exact.is_integer().then(|| exact.to_integer()),
+            None => whole_number(&self.value),
         }
     }
 
@@ -42,29 +264,115 @@ impl Value {
     }
 
     pub fn convert(&self, new_dimension: &Dimension) -> Option<Self> {
-        let ratio = self.dimension.get_ratio(new_dimension)?;
+        self.convert_at(new_dimension, &GasConditions::standard())
+    }
+
+    /// Converts to a new dimension, treating chemicals as ideal gases at the given conditions.
+    pub fn convert_at(
+        &self,
+        new_dimension: &Dimension,
+        conditions: &GasConditions,
+    ) -> Option<Self> {
+        let ratio = self.dimension.get_ratio_at(new_dimension, conditions)?;
+        let exact = exact_ratio(&ratio).and_then(|ratio| Some(self.exact.as_ref()? / ratio));
+        let value = approximate(&exact, || self.value.clone().div(&ratio));
         Some(Self {
             dimension: new_dimension.clone(),
-            value: self.value.clone().div(&ratio),
+            significant_figures: self.significant_figures.map(|sf| sf.rescaled(&value)),
+            uncertainty: self.uncertainty.as_ref().map(|u| u.scaled(&ratio.recip())),
+            exact,
+            value,
+            imaginary: self.imaginary.as_ref().map(|imaginary| imaginary / &ratio),
         })
     }
 
     pub fn try_add(&self, other: &Self) -> Option<Self> {
         let ratio = self.dimension.get_ratio(&other.dimension)?;
-
-        Some(Self {
-            dimension: self.dimension.clone(),
-            value: self.value.clone().add(&other.value.clone().mul(&ratio)),
-        })
+        let other = other.converted_by(&ratio);
+        let exact = self.exact_with(&other, |a, b| a + b);
+        let value = approximate(&exact, || self.value.clone().add(&other.value));
+
+        Some(
+            Self {
+                dimension: self.dimension.clone(),
+                significant_figures: SignificantFigures::sum(
+                    &value,
+                    self.significant_figures,
+                    other.significant_figures,
+                ),
+                uncertainty: Uncertainty::linear([
+                    (self.uncertainty.as_ref(), Float::from(1)),
+                    (other.uncertainty.as_ref(), Float::from(1)),
+                ]),
+                exact,
+                value,
+                imaginary: None,
+            }
+            .with_complex_result(self, &other, Complex::add),
+        )
     }
 
     pub fn try_sub(&self, other: &Self) -> Option<Self> {
         let ratio = self.dimension.get_ratio(&other.dimension)?;
+        let other = other.converted_by(&ratio);
+        let exact = self.exact_with(&other, |a, b| a - b);
+        let value = approximate(&exact, || self.value.clone().sub(&other.value));
+
+        Some(
+            Self {
+                dimension: self.dimension.clone(),
+                significant_figures: SignificantFigures::sum(
+                    &value,
+                    self.significant_figures,
+                    other.significant_figures,
+                ),
+                uncertainty: Uncertainty::linear([
+                    (self.uncertainty.as_ref(), Float::from(1)),
+                    (other.uncertainty.as_ref(), Float::from(-1)),
+                ]),
+                exact,
+                value,
+                imaginary: None,
+            }
+            .with_complex_result(self, &other, Complex::sub),
+        )
+    }
 
-        Some(Self {
+    /// Scales the value into another unit of the same dimension, keeping its significant figures.
+    fn converted_by(&self, ratio: &Float) -> Self {
+        let exact = exact_ratio(ratio).and_then(|ratio| Some(self.exact.as_ref()? * ratio));
+        let value = approximate(&exact, || self.value.clone().mul(ratio));
+        Self {
             dimension: self.dimension.clone(),
-            value: self.value.clone().sub(&other.value.clone().mul(&ratio)),
-        })
+            significant_figures: self.significant_figures.map(|sf| sf.rescaled(&value)),
+            uncertainty: self.uncertainty.as_ref().map(|u| u.scaled(ratio)),
+            exact,
+            value,
+            imaginary: self.imaginary.as_ref().map(|imaginary| imaginary * ratio),
+        }
+    }
+
+    /// Combines the exact fractions of two values, if both are exact.
+    fn exact_with(
+        &self,
+        other: &Self,
+        op: impl FnOnce(&Rational, &Rational) -> Rational,
+    ) -> Option<Rational> {
+        Some(op(self.exact.as_ref()?, other.exact.as_ref()?))
+    }
+
+    /// Redoes an operation with complex numbers if either of its values is complex.
+    fn with_complex_result(
+        self,
+        a: &Self,
+        b: &Self,
+        op: impl FnOnce(&Complex, &Complex) -> Complex,
+    ) -> Self {
+        if a.is_complex() || b.is_complex() {
+            self.with_complex(op(&a.complex(), &b.complex()))
+        } else {
+            self
+        }
     }
 
     pub fn try_mul(&self, other: &Self) -> Option<Self> {
@@ -79,14 +387,61 @@ impl Value {
             dim.push((quantity.clone(), power.clone()));
         }
 
-        Some(Self {
+        let exact = self.exact_with(&other, |a, b| a * b);
+        let value = approximate(&exact, || self.value.clone().mul(&other.value));
+
+        let product = Self {
             dimension: Dimension(dim).simplify(),
-            value: self.value.clone().mul(&other.value),
-        })
+            significant_figures: SignificantFigures::product(
+                &value,
+                self.significant_figures,
+                other.significant_figures,
+            ),
+            uncertainty: Uncertainty::linear([
+                (self.uncertainty.as_ref(), other.value.clone()),
+                (other.uncertainty.as_ref(), self.value.clone()),
+            ]),
+            exact,
+            value,
+            imaginary: None,
+        }
+        .with_complex_result(self, &other, Complex::mul);
+        Some(product.fold_proportions().in_electrical_unit())
+    }
+
+    /// Writes a product of electrical units as the single unit it equals, so that
+    /// `1/(2 pi 50 Hz 10 uF)` is in `Ω`.
+    fn in_electrical_unit(self) -> Self {
+        match self.dimension.electrical_unit() {
+            Some(unit) => self.convert(&unit).unwrap_or(self),
+            None => self,
+        }
+    }
+
+    /// Folds proportions such as `%` into the number unless they are the only unit, so that
+    /// `50 % * 10 g` is `5 g`.
+    fn fold_proportions(self) -> Self {
+        let (proportions, others): (Vec<_>, Vec<_>) = self
+            .dimension
+            .0
+            .iter()
+            .cloned()
+            .partition(|(quantity, _)| matches!(quantity, Quantity::Proportion(_)));
+        match proportions.as_slice() {
+            [] => self,
+            [(_, power)] if others.is_empty() && *power == Float::from(1) => self,
+            _ => self.convert(&Dimension(others)).unwrap_or(self),
+        }
     }
 
     pub fn reciprocal(&self) -> Self {
-        Self {
+        let exact = self
+            .exact
+            .as_ref()
+            .filter(|exact| !exact.is_zero())
+            .map(Rational::recip);
+        let value = approximate(&exact, || self.value.clone().recip());
+        let reciprocal = Self {
             dimension: Dimension(
                 self.dimension
                     .0
@@ -94,7 +449,20 @@ impl Value {
                     .map(|(quantity, power)| (quantity.clone(), { power.clone().neg() }))
                     .collect(),
             ),
-            value: self.value.clone().recip(),
+            significant_figures: self.significant_figures.map(|sf| sf.rescaled(&value)),
+            uncertainty: self
+                .uncertainty
+                .as_ref()
+                .map(|u| u.scaled(&(&value * &value).neg())),
+            exact,
+            value,
+            imaginary: None,
+        };
+
+        if self.is_complex() {
+            reciprocal.with_complex(self.complex().recip())
+        } else {
+            reciprocal
         }
     }
 
@@ -111,11 +479,51 @@ impl Value {
 
         let new_dimension = self.dimension.pow(power);
 
-        let value = self.value.clone().pow(power);
+        // Fractional powers of negative numbers, as in `(-4)^0.5`, are complex
+        if self.is_complex()
+            || other.is_complex()
+            || (self.value < Float::from(0) && power.round() != *power)
+        {
+            if other.is_complex() && !self.is_unitless() {
+                return None;
+            }
+            return Some(
+                Self::new(Float::from(0), Some(new_dimension))
+                    .measured(self.significant_figures)
+                    .with_complex(self.complex().pow(&other.complex())),
+            );
+        }
+
+        let exact = self
+            .exact
+            .as_ref()
+            .zip(other.exact.as_ref())
+            .and_then(|(base, exponent)| rational_pow(base, exponent));
+        let value = approximate(&exact, || self.value.clone().pow(power));
+
+        // d(x^p) = p x^(p - 1) dx + x^p ln(x) dp
+        let uncertainty = Uncertainty::linear([
+            (
+                self.uncertainty.as_ref(),
+                power * &self.value.clone().pow(&(power - &Float::from(1))),
+            ),
+            (
+                other.uncertainty.as_ref(),
+                other
+                    .uncertainty
+                    .as_ref()
+                    .map(|_| &value * &self.value.ln())
+                    .unwrap_or(Float::from(0)),
+            ),
+        ]);
 
         Some(Self {
             dimension: new_dimension,
+            significant_figures: self.significant_figures.map(|sf| sf.rescaled(&value)),
+            uncertainty,
+            exact,
             value,
+            imaginary: None,
         })
     }
 
@@ -127,10 +535,22 @@ impl Value {
         Self {
             dimension,
             value: self.value.clone(),
+            significant_figures: self.significant_figures,
+            uncertainty: self.uncertainty.clone(),
+            exact: self.exact.clone(),
+            imaginary: self.imaginary.clone(),
         }
     }
 }
 
+/// The closest number to an exact result, or a calculated approximation of an inexact one.
+fn approximate(exact: &Option<Rational>, calculate: impl FnOnce() -> Float) -> Float {
+    exact
+        .as_ref()
+        .map(Float::from_rational)
+        .unwrap_or_else(calculate)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -157,6 +577,63 @@ mod test {
         );
     }
 
+    #[test]
+    fn convert_at_only_converts_gases_to_volumes() {
+        let moles = |formula: &str| {
+            let compound = dimension::Compound::parse(formula).unwrap();
+            Value::new(
+                Float::from(2),
+                Some(Dimension(vec![(
+                    Quantity::Amount(dimension::Amount::Mole, Some(compound)),
+                    Float::from(1),
+                )])),
+            )
+        };
+        let liters = Dimension(vec![(
+            Quantity::Volume(dimension::Volume::Liter, None),
+            Float::from(1),
+        )]);
+        let cubic_meters = Dimension(vec![(
+            Quantity::Length(dimension::Length::Meter),
+            Float::from(3),
+        )]);
+        let stp = GasConditions::standard();
+
+        let gas = moles("CO2(g)");
+        let volume = gas.convert_at(&liters, &stp).unwrap();
+        assert!(
+            (&volume.value - &Float::parse("44.828").unwrap()).abs()
+                < Float::parse("0.001").unwrap()
+        );
+        assert!(gas.convert_at(&cubic_meters, &stp).is_some());
+
+        let water = moles("H2O");
+        assert!(water.dimension.needs_gas(&liters));
+        assert!(water.convert_at(&liters, &stp).is_none());
+        assert!(water
+            .with_units(water.dimension.as_gas())
+            .convert_at(&liters, &stp)
+            .is_some());
+    }
+
+    #[test]
+    fn value_try_mul_folds_proportions() {
+        let unit = |quantity| Some(Dimension(vec![(quantity, Float::from(1))]));
+        let percent = Value::new(
+            Float::from(50),
+            unit(Quantity::Proportion(dimension::Proportion::Percent)),
+        );
+        let grams = Value::new(Float::from(10), unit(Quantity::Mass(Mass::Gram, None)));
+
+        let product = percent.try_mul(&grams).unwrap();
+        assert_eq!(product.dimension, grams.dimension);
+        assert_eq!(product.value, Float::from(5));
+
+        let doubled = percent.try_mul(&Value::from(2.0)).unwrap();
+        assert_eq!(doubled.dimension, percent.dimension);
+        assert!(percent.try_mul(&percent).unwrap().is_unitless());
+    }
+
     #[test]
     fn dimension_sanity_check_returns_true_for_sane_dimension() {
         let dimension = Dimension(vec![
@@ -274,4 +751,129 @@ mod test {
         assert!(result.is_some());
         assert_eq!(result.unwrap().value, Float::parse("5").unwrap());
     }
+
+    #[test]
+    fn value_converts_moles_to_particle_counts() {
+        let amount = Value::new(
+            Float::parse("2").unwrap(),
+            Some(Dimension(vec![(
+                Quantity::Amount(dimension::Amount::Mole, None),
+                Float::from(1),
+            )])),
+        );
+
+        let molecules = amount.convert(&Dimension(vec![(
+            Quantity::Amount(dimension::Amount::Molecule, None),
+            Float::from(1),
+        )]));
+
+        assert_eq!(
+            molecules.unwrap().value.to_string(),
+            Float::parse("1.204428152E24").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn value_arithmetic_propagates_significant_figures() {
+        let measured = |literal| {
+            Value::new(Float::parse(literal).unwrap(), None)
+                .measured(SignificantFigures::of_literal(literal, false))
+        };
+
+        let product = measured("2.50").try_mul(&measured("0.96")).unwrap();
+        assert_eq!(product.significant_figures.unwrap().count, 2);
+
+        let sum = measured("12.52").try_add(&measured("1.1")).unwrap();
+        assert_eq!(sum.significant_figures.unwrap().count, 3);
+
+        let exact = measured("2.50").try_mul(&Value::from(3.0)).unwrap();
+        assert_eq!(exact.significant_figures.unwrap().count, 3);
+    }
+
+    #[test]
+    fn value_arithmetic_propagates_uncertainty() {
+        let length = Value::new(
+            Float::from(5),
+            Some(Dimension(vec![(
+                Quantity::Length(dimension::Length::Meter),
+                Float::from(1),
+            )])),
+        )
+        .with_uncertainty(Float::from(0.5));
+
+        let area = length.try_mul(&length).unwrap();
+        assert_eq!(area.uncertainty.unwrap().standard(), Float::from(5));
+
+        let difference = length.try_sub(&length).unwrap();
+        assert_eq!(difference.uncertainty.unwrap().standard(), Float::from(0));
+
+        let centimeters = length
+            .convert(&Dimension(vec![(
+                Quantity::Length(dimension::Length::Centimeter),
+                Float::from(1),
+            )]))
+            .unwrap();
+        assert_eq!(
+            centimeters.uncertainty.unwrap().standard().to_string(),
+            Float::from(50).to_string()
+        );
+    }
+
+    #[test]
+    fn value_arithmetic_stays_exact() {
+        let third = Value::from(1.0).try_div(&Value::from(3.0)).unwrap();
+        let one = third.try_mul(&Value::from(3.0)).unwrap();
+        assert_eq!(one.value, Float::from(1));
+        assert_eq!(one.exact, Rational::from_float(1.0));
+
+        let inexact = Value::new(Float::from(2).sqrt(), None);
+        assert!(inexact.try_mul(&third).unwrap().exact.is_none());
+    }
+
+    #[test]
+    fn value_arithmetic_becomes_complex() {
+        let root = Value::from(-4.0).try_pow(&Value::from(0.5)).unwrap();
+        assert_eq!(root.complex(), Complex::new(Float::from(0), Float::from(2)));
+
+        let square = root.try_mul(&root).unwrap();
+        assert!(!square.is_complex());
+        assert_eq!(square.value, Float::from(-4));
+    }
+
+    #[test]
+    fn value_converts_between_derived_units() {
+        let unit = |quantity| Dimension(vec![(quantity, Float::from(1))]);
+        let inductance = Value::new(
+            Float::from(2),
+            Some(unit(Quantity::Inductance(
+                dimension::Inductance::Millihenry,
+            ))),
+        );
+        let frequency = Value::new(
+            Float::from(50),
+            Some(unit(Quantity::Frequency(dimension::Frequency::Kilohertz))),
+        );
+
+        let reactance = frequency
+            .try_mul(&inductance)
+            .unwrap()
+            .convert(&unit(Quantity::Resistance(dimension::Resistance::Ohm)))
+            .unwrap();
+        assert_eq!(reactance.value, Float::from(100));
+
+        let capacitance = Value::new(
+            Float::from(10),
+            Some(unit(Quantity::Capacitance(
+                dimension::Capacitance::Microfarad,
+            ))),
+        );
+        let reactance = Value::from(1.0)
+            .try_div(&frequency.try_mul(&capacitance).unwrap())
+            .unwrap();
+        assert_eq!(
+            reactance.dimension,
+            unit(Quantity::Resistance(dimension::Resistance::Ohm))
+        );
+        assert!((&reactance.value - &Float::from(2)).abs() < Float::parse("0.000001").unwrap());
+    }
 }
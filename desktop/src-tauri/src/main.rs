@@ -3,13 +3,20 @@
 
 use serde::{Deserialize, Serialize};
 use siffra::evaluation::{evaluate_line, SiffraState};
-use siffra::representations::Value;
+use siffra::representations::{Matrix, Object};
 
 #[derive(Serialize, Deserialize)]
 enum SiffraOutput {
     Value {
         string: String,
     },
+    /// A matrix, shown as a grid of its entries. Units every entry shares are left out of the
+    /// entries and given once.
+    Matrix {
+        string: String,
+        rows: Vec<Vec<String>>,
+        units: Option<String>,
+    },
     Error {
         span: Option<(usize, usize)>,
         message: String,
@@ -24,12 +31,42 @@ struct SiffraLineOutput {
     output: SiffraOutput,
 }
 
-fn display_value(val: Value) -> String {
-    let (val, dim) = val.into_parts();
+fn display_matrix(matrix: &Matrix) -> SiffraOutput {
+    let shared = matrix.shared_units();
+    let rows = (0..matrix.rows())
+        .map(|i| {
+            matrix
+                .row(i)
+                .iter()
+                .map(|entry| match shared {
+                    Some(_) => entry.format_number(),
+                    None => display_value(Object::Value(entry.clone())),
+                })
+                .collect()
+        })
+        .collect();
+
+    SiffraOutput::Matrix {
+        string: Object::Matrix(matrix.clone()).to_string(),
+        rows,
+        units: shared
+            .map(ToString::to_string)
+            .filter(|units| !units.is_empty()),
+    }
+}
+
+fn display_value(val: Object) -> String {
+    let val = match val {
+        Object::Value(val) => val,
+        object => return object.to_string(),
+    };
+
+    let number = val.format_number();
+    let (_, dim) = val.into_parts();
 
     let mut output = String::new();
 
-    output.push_str(&val.to_string());
+    output.push_str(&number);
 
     let dim_string = dim.to_string();
 
@@ -49,12 +86,17 @@ fn get_result(input: &str) -> Vec<SiffraLineOutput> {
         let result = evaluate_line(line, &mut state);
         match result {
             Ok(Some(value)) => {
-                output.push(SiffraLineOutput {
-                    line: i as u16,
-                    output: SiffraOutput::Value {
+                // Results are shown with as many digits as the document calculates
+                let output_value = state.float_context().scope(|| match value {
+                    Object::Matrix(matrix) => display_matrix(&matrix),
+                    value => SiffraOutput::Value {
                         string: display_value(value),
                     },
                 });
+                output.push(SiffraLineOutput {
+                    line: i as u16,
+                    output: output_value,
+                });
             }
             Ok(None) => {}
             Err(err) => {